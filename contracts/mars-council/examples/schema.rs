@@ -3,8 +3,16 @@ use std::fs::create_dir_all;
 
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
-use mars_council::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg};
-use mars_council::{Config, Proposal, ProposalVotesResponse, ProposalsListResponse};
+use mars_council::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, SudoMsg};
+use mars_council::{
+    Config, ConfigDiffResponse, ConfigHistoryResponse, ConfigResponse,
+    CurrentRequiredDepositResponse, EscrowBalanceResponse, ExecutionResultsResponse,
+    ExpiringExecutableProposalsResponse, IsExecutableResponse, PowerNeededToPassResponse, Proposal,
+    ProposalHistoryResponse, ProposalInfo, ProposalMessagesResponse, ProposalReservation,
+    ProposalTalliesResponse, ProposalTimeRemainingResponse, ProposalVotesResponse,
+    ProposalsListResponse, ResolvedAddressesResponse, StatsResponse, TotalLockedDepositsResponse,
+    VoterPowerResponse,
+};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -16,9 +24,30 @@ fn main() {
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(ReceiveMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(SudoMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
 
     export_schema(&schema_for!(Config), &out_dir);
+    export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(ConfigDiffResponse), &out_dir);
+    export_schema(&schema_for!(ConfigHistoryResponse), &out_dir);
+    export_schema(&schema_for!(CurrentRequiredDepositResponse), &out_dir);
+    export_schema(&schema_for!(ExecutionResultsResponse), &out_dir);
+    export_schema(&schema_for!(ExpiringExecutableProposalsResponse), &out_dir);
+    export_schema(&schema_for!(IsExecutableResponse), &out_dir);
+    export_schema(&schema_for!(PowerNeededToPassResponse), &out_dir);
     export_schema(&schema_for!(Proposal), &out_dir);
+    export_schema(&schema_for!(ProposalHistoryResponse), &out_dir);
+    export_schema(&schema_for!(ProposalInfo), &out_dir);
+    export_schema(&schema_for!(ProposalMessagesResponse), &out_dir);
+    export_schema(&schema_for!(ProposalReservation), &out_dir);
     export_schema(&schema_for!(ProposalsListResponse), &out_dir);
+    export_schema(&schema_for!(ProposalTalliesResponse), &out_dir);
     export_schema(&schema_for!(ProposalVotesResponse), &out_dir);
+    export_schema(&schema_for!(ProposalTimeRemainingResponse), &out_dir);
+    export_schema(&schema_for!(ResolvedAddressesResponse), &out_dir);
+    export_schema(&schema_for!(StatsResponse), &out_dir);
+    export_schema(&schema_for!(TotalLockedDepositsResponse), &out_dir);
+    export_schema(&schema_for!(EscrowBalanceResponse), &out_dir);
+    export_schema(&schema_for!(VoterPowerResponse), &out_dir);
 }
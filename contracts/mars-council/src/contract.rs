@@ -1,36 +1,68 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order,
-    QuerierWrapper, QueryRequest, Response, StdResult, Uint128, WasmMsg, WasmQuery,
+    attr, from_binary, to_binary, to_vec, Addr, Api, Binary, ContractResult, CosmosMsg, Deps,
+    DepsMut, Empty, Env, Fraction, MessageInfo, Order, OverflowError, OverflowOperation,
+    QuerierWrapper, QueryRequest, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    SubMsgExecutionResponse, SystemError, SystemResult, Uint128, Uint256, WasmMsg, WasmQuery,
 };
+use cw2::{get_contract_version, set_contract_version};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use cw_storage_plus::{Bound, U64Key};
+use cw_storage_plus::{Bound, Map, U64Key};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::convert::TryInto;
 
 use mars_core::council::error::ContractError;
 use mars_core::error::MarsError;
-use mars_core::helpers::{option_string_to_addr, zero_address};
+use mars_core::helpers::{
+    cw20_get_balance, cw20_get_info, cw20_get_total_supply, option_string_to_addr, zero_address,
+};
 use mars_core::math::decimal::Decimal;
 
 use mars_core::address_provider;
 use mars_core::address_provider::MarsContract;
+use mars_core::treasury;
 use mars_core::vesting;
 use mars_core::xmars_token;
 
-use crate::msg::{CreateOrUpdateConfig, ExecuteMsg, InstantiateMsg, QueryMsg, ReceiveMsg};
-use crate::state::{CONFIG, GLOBAL_STATE, PROPOSALS, PROPOSAL_VOTES};
+use crate::msg::{
+    CreateOrUpdateConfig, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, SudoMsg,
+};
+use crate::state::{
+    ARCHIVED_PROPOSALS, CONFIG, CONFIG_HISTORY, EXECUTING_PROPOSAL, EXECUTION_RESULTS,
+    GLOBAL_STATE, LAST_SUBMISSION, MIGRATION_VOTE_CURSORS, PENDING_DEPOSITS, PENDING_GUARDIAN,
+    PENDING_SLASHES, PROPOSALS, PROPOSALS_BY_SUBMITTER, PROPOSAL_COUNT_BY_START_HEIGHT,
+    PROPOSAL_COUNT_BY_STATUS, PROPOSAL_VOTES, RESERVATIONS, SPONSOR_REFUNDS, STATUS_HISTORY,
+    TITLE_HASH_INDEX,
+};
 use crate::{
-    Config, GlobalState, Proposal, ProposalMessage, ProposalStatus, ProposalVote,
-    ProposalVoteOption, ProposalVoteResponse, ProposalVotesResponse, ProposalsListResponse,
+    Config, ConfigDiffResponse, ConfigHistoryEntry, ConfigHistoryResponse, ConfigResponse,
+    CurrentRequiredDepositResponse, DepositStatus, EscrowBalanceResponse, ExecuteRoute,
+    ExecutionResult, ExecutionResultsResponse, ExpiringExecutableProposal,
+    ExpiringExecutableProposalsResponse, GlobalState, IcaControllerExecuteMsg,
+    IsExecutableResponse, PowerNeededToPassResponse, Proposal, ProposalExecutableReason,
+    ProposalHistoryResponse, ProposalInfo, ProposalMessage, ProposalMessagesResponse,
+    ProposalReservation, ProposalStatus, ProposalStatusChange, ProposalTalliesResponse,
+    ProposalTally, ProposalTimeRemainingResponse, ProposalVote, ProposalVoteOption,
+    ProposalVoteResponse, ProposalVotesResponse, ProposalsListResponse, QuorumBasis,
+    ResolvedAddressesResponse, SlashedDepositDestination, StatsResponse,
+    TotalLockedDepositsResponse, VoterPowerResponse, DEFAULT_AVG_BLOCK_TIME_SECONDS,
+    DEFAULT_DESC_MAX_LENGTH, DEFAULT_LINK_MAX_LENGTH, DEFAULT_TITLE_MAX_LENGTH, MAX_MEMO_LENGTH,
+    MIN_DESC_LENGTH, MIN_LINK_LENGTH, MIN_TITLE_LENGTH,
 };
 
-// Proposal validation attributes
-const MIN_TITLE_LENGTH: usize = 4;
-const MAX_TITLE_LENGTH: usize = 64;
-const MIN_DESC_LENGTH: usize = 4;
-const MAX_DESC_LENGTH: usize = 1024;
-const MIN_LINK_LENGTH: usize = 12;
-const MAX_LINK_LENGTH: usize = 128;
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:mars-council";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Batch limits
+const MAX_VOTES_PER_BATCH: usize = 10;
+const MAX_END_PROPOSALS_PER_BATCH: usize = 10;
+
+// Maximum status changes kept per proposal
+const MAX_STATUS_HISTORY_ENTRIES: u64 = 10;
 
 // INSTANTIATE
 
@@ -41,6 +73,8 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
     // Destructuring a struct’s fields into separate variables in order to force
     // compile error if we add more params
     let CreateOrUpdateConfig {
@@ -51,6 +85,40 @@ pub fn instantiate(
         proposal_required_deposit,
         proposal_required_quorum,
         proposal_required_threshold,
+        submitter_cooldown,
+        discussion_period,
+        emergency_owner,
+        title_max,
+        description_max,
+        link_max,
+        slashed_deposit_destination,
+        fast_track_targets,
+        reclaim_grace_period,
+        snapshot_offset,
+        end_proposal_reward,
+        quorum_basis,
+        quorum_excluded_addresses,
+        mint_per_proposal_cap,
+        mint_total_cap,
+        reservation_period,
+        allow_privileged_token_calls,
+        early_vote_bonus,
+        voting_token_override,
+        avg_block_time_seconds,
+        min_for_votes_absolute,
+        proposal_required_deposit_ratio,
+        min_vote_power,
+        dedupe_window,
+        executor_allowlist,
+        ica_controller_address,
+        surge_window,
+        surge_threshold,
+        surge_multiplier,
+        allowed_categories,
+        accepted_deposit_tokens,
+        refund_signaling_always,
+        auto_expire_grace,
+        require_call_simulation,
     } = msg.config;
 
     // Check required fields are available
@@ -79,6 +147,68 @@ pub fn instantiate(
         proposal_required_deposit: proposal_required_deposit.unwrap(),
         proposal_required_quorum: proposal_required_quorum.unwrap(),
         proposal_required_threshold: proposal_required_threshold.unwrap(),
+        submitter_cooldown: submitter_cooldown.unwrap_or(0),
+        discussion_period: discussion_period.unwrap_or(0),
+        emergency_owner: emergency_owner
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?,
+        submissions_paused: false,
+        voting_paused: false,
+        title_max: title_max.unwrap_or(DEFAULT_TITLE_MAX_LENGTH),
+        description_max: description_max.unwrap_or(DEFAULT_DESC_MAX_LENGTH),
+        link_max: link_max.unwrap_or(DEFAULT_LINK_MAX_LENGTH),
+        slashed_deposit_destination: validate_slashed_deposit_destination(
+            deps.api,
+            slashed_deposit_destination.unwrap_or(SlashedDepositDestination::Staking),
+        )?,
+        fast_track_targets: fast_track_targets
+            .unwrap_or_default()
+            .iter()
+            .map(|address| deps.api.addr_validate(address))
+            .collect::<StdResult<Vec<_>>>()?,
+        reclaim_grace_period: reclaim_grace_period.unwrap_or(0),
+        snapshot_offset: snapshot_offset.unwrap_or(1),
+        end_proposal_reward: end_proposal_reward.unwrap_or_else(Uint128::zero),
+        quorum_basis: quorum_basis.unwrap_or(QuorumBasis::TotalSupply),
+        quorum_excluded_addresses: quorum_excluded_addresses
+            .unwrap_or_default()
+            .iter()
+            .map(|address| deps.api.addr_validate(address))
+            .collect::<StdResult<Vec<_>>>()?,
+        mint_per_proposal_cap: mint_per_proposal_cap.unwrap_or_else(Uint128::zero),
+        mint_total_cap: mint_total_cap.unwrap_or_else(Uint128::zero),
+        reservation_period: reservation_period.unwrap_or(0),
+        allow_privileged_token_calls: allow_privileged_token_calls.unwrap_or(false),
+        early_vote_bonus: early_vote_bonus.unwrap_or_else(Decimal::zero),
+        voting_token_override: voting_token_override
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?,
+        avg_block_time_seconds: avg_block_time_seconds.unwrap_or(DEFAULT_AVG_BLOCK_TIME_SECONDS),
+        min_for_votes_absolute: min_for_votes_absolute.unwrap_or_else(Uint128::zero),
+        proposal_required_deposit_ratio: proposal_required_deposit_ratio
+            .unwrap_or_else(Decimal::zero),
+        min_vote_power: min_vote_power.unwrap_or_else(Uint128::zero),
+        dedupe_window: dedupe_window.unwrap_or(0),
+        executor_allowlist: executor_allowlist
+            .unwrap_or_default()
+            .iter()
+            .map(|address| deps.api.addr_validate(address))
+            .collect::<StdResult<Vec<_>>>()?,
+        ica_controller_address: ica_controller_address
+            .map(|address| deps.api.addr_validate(&address))
+            .transpose()?,
+        surge_window: surge_window.unwrap_or(0),
+        surge_threshold: surge_threshold.unwrap_or(0),
+        surge_multiplier: surge_multiplier.unwrap_or_else(Decimal::one),
+        allowed_categories: allowed_categories.unwrap_or_default(),
+        accepted_deposit_tokens: accepted_deposit_tokens
+            .unwrap_or_default()
+            .iter()
+            .map(|(token, minimum)| Ok((deps.api.addr_validate(token)?, *minimum)))
+            .collect::<StdResult<Vec<_>>>()?,
+        refund_signaling_always: refund_signaling_always.unwrap_or(false),
+        auto_expire_grace: auto_expire_grace.unwrap_or(0),
+        require_call_simulation: require_call_simulation.unwrap_or(false),
     };
 
     // Validate config
@@ -87,7 +217,14 @@ pub fn instantiate(
     CONFIG.save(deps.storage, &config)?;
 
     // initialize State
-    GLOBAL_STATE.save(deps.storage, &GlobalState { proposal_count: 0 })?;
+    GLOBAL_STATE.save(
+        deps.storage,
+        &GlobalState {
+            proposal_count: 0,
+            locked_deposits: Uint128::zero(),
+            total_minted: Uint128::zero(),
+        },
+    )?;
 
     // Prepare response, should instantiate Mars and use the Register hook
     Ok(Response::default())
@@ -109,15 +246,108 @@ pub fn execute(
             execute_cast_vote(deps, env, info, proposal_id, vote)
         }
 
+        ExecuteMsg::CastVotes { votes } => execute_cast_votes(deps, env, info, votes),
+
         ExecuteMsg::EndProposal { proposal_id } => {
             execute_end_proposal(deps, env, info, proposal_id)
         }
 
+        ExecuteMsg::EndProposalsBatch { proposal_ids } => {
+            execute_end_proposals_batch(deps, env, info, proposal_ids)
+        }
+
+        ExecuteMsg::ReclaimStaleDeposit { proposal_id } => {
+            execute_reclaim_stale_deposit(deps, env, info, proposal_id)
+        }
+
         ExecuteMsg::ExecuteProposal { proposal_id } => {
             execute_execute_proposal(deps, env, info, proposal_id)
         }
 
+        ExecuteMsg::ClaimPendingDeposit { proposal_id } => {
+            execute_claim_pending_deposit(deps, env, info, proposal_id)
+        }
+
+        ExecuteMsg::ClaimSponsorRefund { proposal_id } => {
+            execute_claim_sponsor_refund(deps, env, info, proposal_id)
+        }
+
+        ExecuteMsg::SweepPendingSlashes {} => execute_sweep_pending_slashes(deps, env, info),
+
         ExecuteMsg::UpdateConfig { config } => execute_update_config(deps, env, info, config),
+
+        ExecuteMsg::EmergencySetPaused {
+            submissions,
+            voting,
+        } => execute_emergency_set_paused(deps, env, info, submissions, voting),
+
+        ExecuteMsg::TransferGuardianship { new_guardian } => {
+            execute_transfer_guardianship(deps, env, info, new_guardian)
+        }
+
+        ExecuteMsg::AcceptGuardianship {} => execute_accept_guardianship(deps, env, info),
+
+        ExecuteMsg::FinalizeProposal {
+            proposal_id,
+            title,
+            description,
+            link,
+            messages,
+        } => execute_finalize_proposal(
+            deps,
+            env,
+            info,
+            proposal_id,
+            title,
+            description,
+            link,
+            messages,
+        ),
+
+        ExecuteMsg::ExpireReservation { proposal_id } => {
+            execute_expire_reservation(deps, env, info, proposal_id)
+        }
+
+        ExecuteMsg::AmendProposal {
+            proposal_id,
+            description,
+            link,
+        } => execute_amend_proposal(deps, env, info, proposal_id, description, link),
+
+        ExecuteMsg::MintTreasury { recipient, amount } => {
+            execute_mint_treasury(deps, env, info, recipient, amount)
+        }
+
+        ExecuteMsg::ForceCancelProposal { proposal_id } => {
+            execute_force_cancel_proposal(deps, env, info, proposal_id)
+        }
+
+        ExecuteMsg::RescueTokens {
+            token,
+            recipient,
+            amount,
+        } => execute_rescue_tokens(deps, env, info, token, recipient, amount),
+
+        ExecuteMsg::ArchiveProposals {
+            before_id,
+            start_after,
+            limit,
+        } => execute_archive_proposals(deps, env, info, before_id, start_after, limit),
+
+        ExecuteMsg::RotateGovernanceTokens {
+            address_provider,
+            voting_token_override,
+        } => execute_rotate_governance_tokens(
+            deps,
+            env,
+            info,
+            address_provider,
+            voting_token_override,
+        ),
+
+        ExecuteMsg::MarkImplemented { proposal_id, note } => {
+            execute_mark_implemented(deps, env, info, proposal_id, note)
+        }
     }
 }
 
@@ -134,6 +364,10 @@ pub fn execute_receive_cw20(
             description,
             link,
             messages,
+            auto_execute,
+            category,
+            min_quorum_power,
+            execution_deadline_blocks,
         } => execute_submit_proposal(
             deps,
             env,
@@ -144,12 +378,35 @@ pub fn execute_receive_cw20(
             description,
             link,
             messages,
+            auto_execute,
+            category,
+            min_quorum_power,
+            execution_deadline_blocks,
+        ),
+        ReceiveMsg::ReserveProposal {} => {
+            execute_reserve_proposal(deps, env, info, cw20_msg.sender, cw20_msg.amount)
+        }
+        ReceiveMsg::CoSponsor { proposal_id } => execute_co_sponsor(
+            deps,
+            env,
+            info,
+            cw20_msg.sender,
+            cw20_msg.amount,
+            proposal_id,
+        ),
+        ReceiveMsg::TopUpDeposit { proposal_id } => execute_top_up_deposit(
+            deps,
+            env,
+            info,
+            cw20_msg.sender,
+            cw20_msg.amount,
+            proposal_id,
         ),
     }
 }
 
 pub fn execute_submit_proposal(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     submitter_address_unchecked: String,
@@ -158,73 +415,118 @@ pub fn execute_submit_proposal(
     description: String,
     option_link: Option<String>,
     option_messages: Option<Vec<ProposalMessage>>,
+    option_auto_execute: Option<bool>,
+    option_category: Option<String>,
+    option_min_quorum_power: Option<Uint128>,
+    option_execution_deadline_blocks: Option<u64>,
 ) -> Result<Response, ContractError> {
-    // Validate title
-    if title.len() < MIN_TITLE_LENGTH {
-        return Err(ContractError::invalid_proposal("title too short"));
-    }
-    if title.len() > MAX_TITLE_LENGTH {
-        return Err(ContractError::invalid_proposal("title too long"));
+    let config = CONFIG.load(deps.storage)?;
+    if config.submissions_paused {
+        return Err(ContractError::SubmissionsPaused {});
     }
 
-    // Validate description
-    if description.len() < MIN_DESC_LENGTH {
-        return Err(ContractError::invalid_proposal("description too short"));
+    let mars_token_address = address_provider::helpers::query_address(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        MarsContract::MarsToken,
+    )?;
+
+    validate_proposal_content(
+        &env,
+        &config,
+        &mars_token_address,
+        &title,
+        &description,
+        &option_link,
+        &option_messages,
+    )?;
+    if config.require_call_simulation {
+        validate_call_targets(&deps.querier, &option_messages)?;
     }
-    if description.len() > MAX_DESC_LENGTH {
-        return Err(ContractError::invalid_proposal("description too long"));
+
+    let (submitter_address, deposit_token, required_deposit) = validate_submission(
+        deps.branch(),
+        &env,
+        &info,
+        &config,
+        &mars_token_address,
+        &submitter_address_unchecked,
+        deposit_amount,
+    )?;
+
+    validate_not_duplicate(deps.as_ref(), &env, &config, &submitter_address, &title)?;
+
+    let auto_execute = option_auto_execute.unwrap_or(false);
+    if auto_execute && !are_fast_track_messages(&config, &option_messages) {
+        return Err(ContractError::invalid_proposal(
+            "auto execute is only allowed when all messages target fast track contracts",
+        ));
     }
 
-    // Validate Link
-    if let Some(link) = &option_link {
-        if link.len() < MIN_LINK_LENGTH {
-            return Err(ContractError::invalid_proposal("Link too short"));
-        }
-        if link.len() > MAX_LINK_LENGTH {
-            return Err(ContractError::invalid_proposal("Link too long"));
+    if let Some(category) = &option_category {
+        if !config.allowed_categories.contains(category) {
+            return Err(ContractError::invalid_proposal("category is not allowed"));
         }
     }
 
-    let config = CONFIG.load(deps.storage)?;
-    let mars_token_address = address_provider::helpers::query_address(
-        &deps.querier,
-        config.address_provider_address,
-        MarsContract::MarsToken,
-    )?;
+    if option_execution_deadline_blocks == Some(0) {
+        return Err(ContractError::invalid_proposal(
+            "execution deadline blocks must be greater than zero",
+        ));
+    }
 
-    let is_mars = info.sender == mars_token_address;
-    // Validate deposit amount
-    if (deposit_amount < config.proposal_required_deposit) || !is_mars {
-        return Err(ContractError::invalid_proposal(format!(
-            "Must deposit at least {} Mars tokens",
-            config.proposal_required_deposit
-        )));
+    // A quorum above the voting power that exists now can only be met if it grows a lot by the
+    // snapshot, so it most likely is a mistake
+    if let Some(min_quorum_power) = option_min_quorum_power {
+        let total_voting_power =
+            get_total_voting_power_at(&deps.querier, &config, env.block.height)?
+                .unwrap_or_else(Uint128::zero);
+        if min_quorum_power > total_voting_power {
+            return Err(ContractError::invalid_proposal(
+                "min quorum power cannot exceed the total voting power",
+            ));
+        }
     }
 
-    // Update proposal totals
+    // Update proposal totals. Locked deposits only count MARS
     let mut global_state = GLOBAL_STATE.load(deps.storage)?;
-    global_state.proposal_count += 1;
+    global_state.proposal_count = proposal_count_checked_add(global_state.proposal_count)?;
+    if deposit_token.is_none() {
+        global_state.locked_deposits = global_state.locked_deposits.checked_add(deposit_amount)?;
+    }
     GLOBAL_STATE.save(deps.storage, &global_state)?;
 
     let new_proposal = Proposal {
-        proposal_id: global_state.proposal_count,
-        submitter_address: deps.api.addr_validate(&submitter_address_unchecked)?,
-        status: ProposalStatus::Active,
-        for_votes: Uint128::zero(),
-        against_votes: Uint128::zero(),
-        start_height: env.block.height,
-        end_height: env.block.height + config.proposal_voting_period,
         title,
         description,
         link: option_link,
         messages: option_messages,
-        deposit_amount,
+        auto_execute,
+        category: option_category,
+        min_quorum_power: option_min_quorum_power,
+        execution_deadline_blocks: option_execution_deadline_blocks,
+        ..new_active_proposal(
+            &env,
+            &config,
+            global_state.proposal_count,
+            submitter_address,
+            deposit_amount,
+            deposit_token,
+            required_deposit,
+        )?
     };
     PROPOSALS.save(
         deps.storage,
         U64Key::new(global_state.proposal_count),
         &new_proposal,
     )?;
+    index_proposal(deps.storage, &new_proposal)?;
+    record_status_change(
+        deps.storage,
+        &env,
+        new_proposal.proposal_id,
+        ProposalStatus::Active,
+    )?;
 
     let response = Response::new().add_attributes(vec![
         attr("action", "submit_proposal"),
@@ -236,1791 +538,12381 @@ pub fn execute_submit_proposal(
     Ok(response)
 }
 
-pub fn execute_cast_vote(
-    deps: DepsMut,
+/// Reserve a proposal id with its deposit. The proposal is populated later through
+/// FinalizeProposal, which allows assembling it before it is open for voting. The deposit can be
+/// completed by co-sponsors in the meantime
+pub fn execute_reserve_proposal(
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    proposal_id: u64,
-    vote_option: ProposalVoteOption,
+    submitter_address_unchecked: String,
+    deposit_amount: Uint128,
 ) -> Result<Response, ContractError> {
-    let proposal_path = PROPOSALS.key(U64Key::new(proposal_id));
-    let mut proposal = proposal_path.load(deps.storage)?;
-    if proposal.status != ProposalStatus::Active {
-        return Err(ContractError::ProposalNotActive {});
+    let config = CONFIG.load(deps.storage)?;
+    if config.submissions_paused {
+        return Err(ContractError::SubmissionsPaused {});
     }
-
-    if env.block.height > proposal.end_height {
-        return Err(ContractError::VoteVotingPeriodEnded {});
+    if config.reservation_period == 0 {
+        return Err(ContractError::ReservationsDisabled {});
     }
 
-    let proposal_vote_path = PROPOSAL_VOTES.key((U64Key::new(proposal_id), &info.sender));
+    let mars_token_address = address_provider::helpers::query_address(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        MarsContract::MarsToken,
+    )?;
 
-    if proposal_vote_path.may_load(deps.storage)?.is_some() {
-        return Err(ContractError::VoteUserAlreadyVoted {});
+    // The deposit amount is checked when the proposal is finalized
+    let (deposit_token, _) = deposit_requirement(
+        deps.as_ref(),
+        &env,
+        &config,
+        &mars_token_address,
+        &info.sender,
+    )?;
+    let submitter_address =
+        validate_submitter(deps.branch(), &env, &config, &submitter_address_unchecked)?;
+
+    // Reserved ids are taken from the proposal count so that finalized proposals keep their id.
+    // Deposits are locked from reservation until the proposal ends or the reservation expires
+    let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+    global_state.proposal_count = proposal_count_checked_add(global_state.proposal_count)?;
+    if deposit_token.is_none() {
+        global_state.locked_deposits = global_state.locked_deposits.checked_add(deposit_amount)?;
     }
+    GLOBAL_STATE.save(deps.storage, &global_state)?;
 
-    let config = CONFIG.load(deps.storage)?;
-    let mars_contracts = vec![MarsContract::XMarsToken, MarsContract::Vesting];
-    let mut addresses_query = address_provider::helpers::query_addresses(
-        &deps.querier,
-        config.address_provider_address,
-        mars_contracts,
+    let reservation = ProposalReservation {
+        submitter_address,
+        deposit_amount,
+        deposit_token,
+        co_sponsors: vec![],
+        expiration_height: height_checked_add(env.block.height, config.reservation_period)?,
+    };
+    RESERVATIONS.save(
+        deps.storage,
+        U64Key::new(global_state.proposal_count),
+        &reservation,
     )?;
-    let vesting_address = addresses_query.pop().unwrap();
-    let xmars_token_address = addresses_query.pop().unwrap();
 
-    let balance_at_block = proposal.start_height - 1;
+    let response = Response::new().add_attributes(vec![
+        attr("action", "reserve_proposal"),
+        attr("submitter", submitter_address_unchecked),
+        attr("proposal_id", global_state.proposal_count.to_string()),
+        attr(
+            "reservation_expiration_height",
+            reservation.expiration_height.to_string(),
+        ),
+    ]);
 
-    // The voting power of a user for a proposal is defined as the sum of two parts:
-    //
-    // - Free voting power: the amount of xMARS token in the user's wallet, at the block before the
-    //   proposal was created
-    // - Locked voting power: the amount of MARS locked in the vesting contract owned by the user,
-    //   at the block before the proposal was created
-    //
-    // The reason we can use the amount of MARS (instead of xMARS) for locked voting power is that,
-    // since vesting allocations can only be created when 1 MARS == 1 xMARS, these MARS tokens would
-    // have produced the same amount of xMARS if they were staked.
-    let voting_power_free = xmars_get_balance_at(
-        &deps.querier,
-        xmars_token_address,
-        info.sender.clone(),
-        balance_at_block,
-    )?;
-    let voting_power_locked = vesting_get_voting_power_at(
-        &deps.querier,
-        vesting_address,
-        info.sender.clone(),
-        balance_at_block,
-    )?;
-    let voting_power = voting_power_free + voting_power_locked;
+    Ok(response)
+}
 
-    if voting_power.is_zero() {
-        return Err(ContractError::VoteNoVotingPower {
-            block: balance_at_block,
+/// Add to the deposit of a reserved proposal
+pub fn execute_co_sponsor(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sponsor_address_unchecked: String,
+    deposit_amount: Uint128,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.submissions_paused {
+        return Err(ContractError::SubmissionsPaused {});
+    }
+
+    let reservation_path = RESERVATIONS.key(U64Key::new(proposal_id));
+    let mut reservation = reservation_path.load(deps.storage)?;
+    if env.block.height > reservation.expiration_height {
+        return Err(ContractError::FinalizeProposalReservationExpired {
+            expiration_height: reservation.expiration_height,
         });
     }
 
-    match vote_option {
-        ProposalVoteOption::For => proposal.for_votes += voting_power,
-        ProposalVoteOption::Against => proposal.against_votes += voting_power,
+    let deposit_token_address = match &reservation.deposit_token {
+        Some(deposit_token) => deposit_token.clone(),
+        None => address_provider::helpers::query_address(
+            &deps.querier,
+            config.address_provider_address,
+            MarsContract::MarsToken,
+        )?,
     };
+    if info.sender != deposit_token_address {
+        return Err(ContractError::CoSponsorInvalidToken {});
+    }
 
-    proposal_vote_path.save(
-        deps.storage,
-        &ProposalVote {
-            option: vote_option.clone(),
-            power: voting_power,
-        },
+    let sponsor_address = deps.api.addr_validate(&sponsor_address_unchecked)?;
+    reservation.deposit_amount = reservation.deposit_amount.checked_add(deposit_amount)?;
+    record_sponsor_deposit(
+        &reservation.submitter_address,
+        &mut reservation.co_sponsors,
+        sponsor_address,
+        deposit_amount,
     )?;
+    reservation_path.save(deps.storage, &reservation)?;
 
-    proposal_path.save(deps.storage, &proposal)?;
+    if reservation.deposit_token.is_none() {
+        let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+        global_state.locked_deposits = global_state.locked_deposits.checked_add(deposit_amount)?;
+        GLOBAL_STATE.save(deps.storage, &global_state)?;
+    }
 
     let response = Response::new().add_attributes(vec![
-        attr("action", "cast_vote"),
+        attr("action", "co_sponsor_proposal"),
+        attr("sponsor", sponsor_address_unchecked),
         attr("proposal_id", proposal_id.to_string()),
-        attr("voter", &info.sender),
-        attr("vote", vote_option.to_string()),
-        attr("voting_power", voting_power.to_string()),
+        attr("amount", deposit_amount),
+        attr("deposit_amount", reservation.deposit_amount),
     ]);
 
     Ok(response)
 }
 
-pub fn execute_end_proposal(
+/// Add to the deposit of an active proposal
+pub fn execute_top_up_deposit(
     deps: DepsMut,
-    env: Env,
-    _info: MessageInfo,
+    _env: Env,
+    info: MessageInfo,
+    sponsor_address_unchecked: String,
+    deposit_amount: Uint128,
     proposal_id: u64,
 ) -> Result<Response, ContractError> {
     let proposal_path = PROPOSALS.key(U64Key::new(proposal_id));
     let mut proposal = proposal_path.load(deps.storage)?;
-
     if proposal.status != ProposalStatus::Active {
         return Err(ContractError::ProposalNotActive {});
     }
 
-    if env.block.height <= proposal.end_height {
-        return Err(ContractError::EndProposalVotingPeriodNotEnded {});
+    let deposit_token_address = match &proposal.deposit_token {
+        Some(deposit_token) => deposit_token.clone(),
+        None => {
+            let config = CONFIG.load(deps.storage)?;
+            address_provider::helpers::query_address(
+                &deps.querier,
+                config.address_provider_address,
+                MarsContract::MarsToken,
+            )?
+        }
+    };
+    if info.sender != deposit_token_address {
+        return Err(ContractError::TopUpInvalidToken {});
     }
 
-    let config = CONFIG.load(deps.storage)?;
-    let mars_contracts = vec![
-        MarsContract::MarsToken,
-        MarsContract::Staking,
-        MarsContract::Vesting,
-        MarsContract::XMarsToken,
-    ];
-    let mut addresses_query = address_provider::helpers::query_addresses(
-        &deps.querier,
-        config.address_provider_address,
-        mars_contracts,
+    let sponsor_address = deps.api.addr_validate(&sponsor_address_unchecked)?;
+    proposal.deposit_amount = proposal.deposit_amount.checked_add(deposit_amount)?;
+    record_sponsor_deposit(
+        &proposal.submitter_address,
+        &mut proposal.co_sponsors,
+        sponsor_address,
+        deposit_amount,
     )?;
-    let xmars_token_address = addresses_query.pop().unwrap();
-    let vesting_address = addresses_query.pop().unwrap();
-    let staking_address = addresses_query.pop().unwrap();
-    let mars_token_address = addresses_query.pop().unwrap();
+    proposal_path.save(deps.storage, &proposal)?;
 
-    // The total voting power of a proposal is defined as the sum of two parts:
-    //
-    // - Free voting power: the total supply of xMARS token at the block before the proposal was
-    //   created
-    // - Locked voting power: the total amount of MARS token locked in the vesting contract, at the
-    //   block before the proposal was created
-    //
-    // The reason we can use the amount of MARS (instead of xMARS) for locked voting power is that,
-    // since vesting allocations can only be created when 1 MARS == 1 xMARS, these MARS tokens would
-    // have produced the same amount of xMARS if they were staked.
-    let total_voting_power_free = xmars_get_total_supply_at(
-        &deps.querier,
-        xmars_token_address,
-        proposal.start_height - 1,
-    )?;
-    let total_voting_power_locked = vesting_get_total_voting_power_at(
-        &deps.querier,
-        vesting_address,
-        proposal.start_height - 1,
-    )?;
-    let total_voting_power = total_voting_power_free + total_voting_power_locked;
+    if proposal.deposit_token.is_none() {
+        let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+        global_state.locked_deposits = global_state.locked_deposits.checked_add(deposit_amount)?;
+        GLOBAL_STATE.save(deps.storage, &global_state)?;
+    }
 
-    // Compute proposal quorum and threshold
-    let for_votes = proposal.for_votes;
-    let against_votes = proposal.against_votes;
-    let total_votes = for_votes + against_votes;
+    let response = Response::new().add_attributes(vec![
+        attr("action", "top_up_deposit"),
+        attr("sponsor", sponsor_address_unchecked),
+        attr("proposal_id", proposal_id.to_string()),
+        attr("amount", deposit_amount),
+        attr("deposit_amount", proposal.deposit_amount),
+    ]);
 
-    let mut proposal_quorum: Decimal = Decimal::zero();
-    let mut proposal_threshold: Decimal = Decimal::zero();
-    if total_voting_power > Uint128::zero() {
-        proposal_quorum = Decimal::from_ratio(total_votes, total_voting_power);
-    }
-    if total_votes > Uint128::zero() {
-        proposal_threshold = Decimal::from_ratio(for_votes, total_votes);
-    }
+    Ok(response)
+}
 
-    // Determine proposal result
-    let (new_proposal_status, log_proposal_result, messages) = if proposal_quorum
-        >= config.proposal_required_quorum
-        && proposal_threshold > config.proposal_required_threshold
-    {
-        // if quorum and threshold are met then proposal passes
-        // refund deposit amount to submitter
-        let msg = CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: mars_token_address.into(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: proposal.submitter_address.to_string(),
-                amount: proposal.deposit_amount,
-            })?,
+/// Populate a reserved proposal and open it for voting
+pub fn execute_finalize_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    title: String,
+    description: String,
+    option_link: Option<String>,
+    option_messages: Option<Vec<ProposalMessage>>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.submissions_paused {
+        return Err(ContractError::SubmissionsPaused {});
+    }
+
+    let reservation_path = RESERVATIONS.key(U64Key::new(proposal_id));
+    let reservation = reservation_path.load(deps.storage)?;
+    if info.sender != reservation.submitter_address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+    if env.block.height > reservation.expiration_height {
+        return Err(ContractError::FinalizeProposalReservationExpired {
+            expiration_height: reservation.expiration_height,
         });
+    }
 
-        (ProposalStatus::Passed, "passed", vec![msg])
-    } else {
-        // Else proposal is rejected
-        let msg = CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: mars_token_address.into(),
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: staking_address.into(),
-                amount: proposal.deposit_amount,
-            })?,
-            funds: vec![],
+    let mars_token_address = address_provider::helpers::query_address(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        MarsContract::MarsToken,
+    )?;
+
+    validate_proposal_content(
+        &env,
+        &config,
+        &mars_token_address,
+        &title,
+        &description,
+        &option_link,
+        &option_messages,
+    )?;
+    if config.require_call_simulation {
+        validate_call_targets(&deps.querier, &option_messages)?;
+    }
+
+    let (_, required_deposit) = deposit_requirement(
+        deps.as_ref(),
+        &env,
+        &config,
+        &mars_token_address,
+        reservation
+            .deposit_token
+            .as_ref()
+            .unwrap_or(&mars_token_address),
+    )?;
+    if reservation.deposit_amount < required_deposit {
+        return Err(ContractError::InsufficientDeposit {
+            required: required_deposit,
         });
+    }
 
-        (ProposalStatus::Rejected, "rejected", vec![msg])
-    };
+    reservation_path.remove(deps.storage);
 
-    // Update proposal status
-    proposal.status = new_proposal_status;
-    proposal_path.save(deps.storage, &proposal)?;
+    let new_proposal = Proposal {
+        title,
+        description,
+        link: option_link,
+        messages: option_messages,
+        co_sponsors: reservation.co_sponsors,
+        ..new_active_proposal(
+            &env,
+            &config,
+            proposal_id,
+            reservation.submitter_address,
+            reservation.deposit_amount,
+            reservation.deposit_token,
+            required_deposit,
+        )?
+    };
+    PROPOSALS.save(deps.storage, U64Key::new(proposal_id), &new_proposal)?;
+    index_proposal(deps.storage, &new_proposal)?;
+    record_status_change(
+        deps.storage,
+        &env,
+        new_proposal.proposal_id,
+        ProposalStatus::Active,
+    )?;
 
-    let response = Response::new()
-        .add_attributes(vec![
-            attr("action", "end_proposal"),
-            attr("proposal_id", proposal_id.to_string()),
-            attr("proposal_result", log_proposal_result),
-        ])
-        .add_messages(messages);
+    let response = Response::new().add_attributes(vec![
+        attr("action", "finalize_proposal"),
+        attr("submitter", new_proposal.submitter_address.to_string()),
+        attr("proposal_id", proposal_id.to_string()),
+        attr("proposal_end_height", new_proposal.end_height.to_string()),
+    ]);
 
     Ok(response)
 }
 
-pub fn execute_execute_proposal(
+/// Remove a reservation that was not finalized before its expiration, slashing its deposit
+pub fn execute_expire_reservation(
     deps: DepsMut,
     env: Env,
     _info: MessageInfo,
     proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let reservation_path = RESERVATIONS.key(U64Key::new(proposal_id));
+    let reservation = reservation_path.load(deps.storage)?;
+    if env.block.height <= reservation.expiration_height {
+        return Err(ContractError::ExpireReservationNotExpired {
+            expiration_height: reservation.expiration_height,
+        });
+    }
+
+    reservation_path.remove(deps.storage);
+
+    if reservation.deposit_token.is_none() {
+        let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+        global_state.locked_deposits = global_state
+            .locked_deposits
+            .checked_sub(reservation.deposit_amount)
+            .map_err(StdError::from)?;
+        GLOBAL_STATE.save(deps.storage, &global_state)?;
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut addresses_query = address_provider::helpers::query_addresses(
+        &deps.querier,
+        config.address_provider_address,
+        vec![MarsContract::MarsToken, MarsContract::Staking],
+    )?;
+    let staking_address = addresses_query.pop().unwrap();
+    let mars_token_address = addresses_query.pop().unwrap();
+
+    let res = Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: reservation
+                .deposit_token
+                .unwrap_or(mars_token_address)
+                .into(),
+            msg: to_binary(&slashed_deposit_msg(
+                config.slashed_deposit_destination,
+                staking_address,
+                reservation.deposit_amount,
+            ))?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![
+            attr("action", "expire_reservation"),
+            attr("proposal_id", proposal_id.to_string()),
+        ]);
+    Ok(res)
+}
+
+/// Update the description and/or link of a proposal during its discussion period
+pub fn execute_amend_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    option_description: Option<String>,
+    option_link: Option<String>,
 ) -> Result<Response, ContractError> {
     let proposal_path = PROPOSALS.key(U64Key::new(proposal_id));
     let mut proposal = proposal_path.load(deps.storage)?;
 
-    if proposal.status != ProposalStatus::Passed {
-        return Err(ContractError::ExecuteProposalNotPassed {});
+    if info.sender != proposal.submitter_address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+    if proposal.status != ProposalStatus::Active {
+        return Err(ContractError::ProposalNotActive {});
+    }
+    if env.block.height >= proposal.vote_start_height {
+        return Err(ContractError::AmendProposalVotingStarted {
+            vote_start_height: proposal.vote_start_height,
+        });
     }
 
-    let config = CONFIG.load(deps.storage)?;
-    if env.block.height < (proposal.end_height + config.proposal_effective_delay) {
-        return Err(ContractError::ExecuteProposalDelayNotEnded {});
+    if let Some(description) = option_description {
+        proposal.description = description;
     }
-    if env.block.height
-        > (proposal.end_height
-            + config.proposal_effective_delay
-            + config.proposal_expiration_period)
-    {
-        return Err(ContractError::ExecuteProposalExpired {});
+    if option_link.is_some() {
+        proposal.link = option_link;
     }
 
-    proposal.status = ProposalStatus::Executed;
-    proposal_path.save(deps.storage, &proposal)?;
+    let config = CONFIG.load(deps.storage)?;
+    validate_proposal_text(
+        &config,
+        &proposal.title,
+        &proposal.description,
+        &proposal.link,
+    )?;
 
-    let messages = match proposal.messages {
-        Some(mut messages) => {
-            messages.sort_by(|a, b| a.execution_order.cmp(&b.execution_order));
-            messages.into_iter().map(|message| message.msg).collect()
-        }
-        None => vec![],
-    };
+    proposal.amended_at = Some(env.block.height);
+    proposal_path.save(deps.storage, &proposal)?;
 
-    let response = Response::new()
-        .add_attributes(vec![
-            attr("action", "execute_proposal"),
-            attr("proposal_id", proposal_id.to_string()),
-        ])
-        .add_messages(messages);
+    let response = Response::new().add_attributes(vec![
+        attr("action", "amend_proposal"),
+        attr("proposal_id", proposal_id.to_string()),
+    ]);
 
     Ok(response)
 }
 
-/// Update config
-pub fn execute_update_config(
+pub fn execute_cast_vote(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    new_config: CreateOrUpdateConfig,
+    proposal_id: u64,
+    vote_option: ProposalVoteOption,
 ) -> Result<Response, ContractError> {
-    let mut config = CONFIG.load(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    if config.voting_paused {
+        return Err(ContractError::VotingPaused {});
+    }
 
-    // In council, config can be updated only by itself (through an approved proposal)
-    // instead of by it's owner
-    if info.sender != env.contract.address {
-        return Err(MarsError::Unauthorized {}.into());
+    let proposal_path = PROPOSALS.key(U64Key::new(proposal_id));
+    let mut proposal = proposal_path.load(deps.storage)?;
+    if proposal.status != ProposalStatus::Active {
+        return Err(ContractError::ProposalNotActive {});
     }
 
-    // Destructuring a struct’s fields into separate variables in order to force
-    // compile error if we add more params
-    let CreateOrUpdateConfig {
-        address_provider_address,
+    if env.block.height < proposal.vote_start_height {
+        return Err(ContractError::VoteVotingNotStarted {
+            vote_start_height: proposal.vote_start_height,
+        });
+    }
 
-        proposal_voting_period,
-        proposal_effective_delay,
-        proposal_expiration_period,
-        proposal_required_deposit,
-        proposal_required_quorum,
-        proposal_required_threshold,
-    } = new_config;
+    if env.block.height > proposal.end_height {
+        return Err(ContractError::VoteVotingPeriodEnded {});
+    }
 
-    // Update config
-    config.address_provider_address = option_string_to_addr(
-        deps.api,
-        address_provider_address,
-        config.address_provider_address,
+    let proposal_vote_path = PROPOSAL_VOTES.key((U64Key::new(proposal_id), &info.sender));
+
+    if proposal_vote_path.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::VoteUserAlreadyVoted {});
+    }
+
+    let balance_at_block = proposal.voting_power_snapshot_block;
+    let voting_power = get_voting_power_at(
+        &deps.querier,
+        &config,
+        info.sender.clone(),
+        balance_at_block,
     )?;
 
-    config.proposal_voting_period = proposal_voting_period.unwrap_or(config.proposal_voting_period);
-    config.proposal_effective_delay =
-        proposal_effective_delay.unwrap_or(config.proposal_effective_delay);
-    config.proposal_expiration_period =
-        proposal_expiration_period.unwrap_or(config.proposal_expiration_period);
-    config.proposal_required_deposit =
-        proposal_required_deposit.unwrap_or(config.proposal_required_deposit);
-    config.proposal_required_quorum =
-        proposal_required_quorum.unwrap_or(config.proposal_required_quorum);
-    config.proposal_required_threshold =
-        proposal_required_threshold.unwrap_or(config.proposal_required_threshold);
+    if voting_power.is_zero() {
+        return Err(ContractError::VoteNoVotingPower {
+            block: balance_at_block,
+        });
+    }
 
-    // Validate config
-    config.validate()?;
+    if voting_power < config.min_vote_power {
+        return Err(ContractError::VoteBelowMinimumPower {
+            min_vote_power: config.min_vote_power,
+        });
+    }
 
-    CONFIG.save(deps.storage, &config)?;
+    let weighted_voting_power = early_vote_weighted_power(
+        env.block.height,
+        &proposal,
+        config.early_vote_bonus,
+        voting_power,
+    );
 
-    let res = Response::new().add_attribute("action", "update_config");
-    Ok(res)
-}
+    match vote_option {
+        ProposalVoteOption::For => {
+            proposal.for_votes += weighted_voting_power;
+            proposal.for_voter_count += 1;
+        }
+        ProposalVoteOption::Against => {
+            proposal.against_votes += weighted_voting_power;
+            proposal.against_voter_count += 1;
+        }
+    };
+    proposal.early_vote_bonus_votes += weighted_voting_power - voting_power;
 
-// QUERIES
+    proposal_vote_path.save(
+        deps.storage,
+        &ProposalVote {
+            option: vote_option.clone(),
+            power: voting_power,
+            weighted_power: weighted_voting_power,
+        },
+    )?;
 
-// Pagination defaults
-const PAGINATION_DEFAULT_LIMIT: u32 = 10;
-const PAGINATION_MAX_LIMIT: u32 = 30;
+    proposal_path.save(deps.storage, &proposal)?;
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::Proposals { start, limit } => to_binary(&query_proposals(deps, start, limit)?),
-        QueryMsg::Proposal { proposal_id } => to_binary(&query_proposal(deps, proposal_id)?),
-        QueryMsg::ProposalVotes {
-            proposal_id,
-            start_after,
-            limit,
-        } => to_binary(&query_proposal_votes(
-            deps,
+    let response = Response::new().add_attributes(vec![
+        attr("action", "cast_vote"),
+        attr("proposal_id", proposal_id.to_string()),
+        attr("voter", &info.sender),
+        attr("vote", vote_option.to_string()),
+        attr("voting_power", voting_power.to_string()),
+        attr("weighted_voting_power", weighted_voting_power.to_string()),
+    ]);
+
+    Ok(response)
+}
+
+/// Cast several votes from the same sender, one per (proposal_id, vote) pair
+pub fn execute_cast_votes(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    votes: Vec<(u64, ProposalVoteOption)>,
+) -> Result<Response, ContractError> {
+    if votes.len() > MAX_VOTES_PER_BATCH {
+        return Err(ContractError::BatchTooLarge {
+            max: MAX_VOTES_PER_BATCH,
+        });
+    }
+
+    let mut response = Response::new();
+    for (proposal_id, vote_option) in votes {
+        let vote_response = execute_cast_vote(
+            deps.branch(),
+            env.clone(),
+            info.clone(),
             proposal_id,
-            start_after,
-            limit,
-        )?),
+            vote_option,
+        )?;
+        response = response.add_attributes(vote_response.attributes);
     }
+
+    Ok(response)
 }
 
-fn query_config(deps: Deps) -> StdResult<Config> {
+pub fn execute_end_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    Ok(config)
+    let (proposal, total_voting_power) =
+        load_proposal_to_end(deps.as_ref(), &env, &config, proposal_id)?;
+    end_proposal(deps, env, info, config, proposal, total_voting_power)
 }
 
-fn query_proposals(
+/// Proposal that can be ended with the total voting power its result is computed against. Only
+/// reads state, so a batch can skip the proposals it fails for
+fn load_proposal_to_end(
     deps: Deps,
-    start_from: Option<u64>,
-    option_limit: Option<u32>,
-) -> StdResult<ProposalsListResponse> {
-    let global_state = GLOBAL_STATE.load(deps.storage)?;
+    env: &Env,
+    config: &Config,
+    proposal_id: u64,
+) -> Result<(Proposal, Uint128), ContractError> {
+    let proposal = PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?;
 
-    let option_start = start_from.map(|start| Bound::inclusive(U64Key::new(start)));
-    let limit = option_limit
-        .unwrap_or(PAGINATION_DEFAULT_LIMIT)
-        .min(PAGINATION_MAX_LIMIT) as usize;
+    if proposal.status != ProposalStatus::Active {
+        return Err(ContractError::ProposalNotActive {});
+    }
 
-    let proposals_list: StdResult<Vec<_>> = PROPOSALS
-        .range(deps.storage, option_start, None, Order::Ascending)
-        .take(limit)
-        .map(|item| {
-            let (_k, v) = item?;
-            Ok(v)
-        })
-        .collect();
+    if env.block.height <= proposal.end_height {
+        return Err(ContractError::EndProposalVotingPeriodNotEnded {});
+    }
 
-    Ok(ProposalsListResponse {
-        proposal_count: global_state.proposal_count,
-        proposal_list: proposals_list?,
-    })
-}
+    // A missing snapshot would otherwise read as zero voting power and reject the proposal for
+    // lack of quorum, so ending it is left for when the snapshot exists
+    let total_voting_power =
+        get_total_voting_power_at(&deps.querier, config, proposal.voting_power_snapshot_block)?
+            .ok_or(ContractError::SnapshotUnavailable {
+                block: proposal.voting_power_snapshot_block,
+            })?;
 
-fn query_proposal(deps: Deps, proposal_id: u64) -> StdResult<Proposal> {
-    let proposal = PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?;
-    Ok(proposal)
+    Ok((proposal, total_voting_power))
 }
 
-fn query_proposal_votes(
-    deps: Deps,
-    proposal_id: u64,
-    start_after: Option<String>,
-    option_limit: Option<u32>,
-) -> StdResult<ProposalVotesResponse> {
-    let limit = option_limit
-        .unwrap_or(PAGINATION_DEFAULT_LIMIT)
-        .min(PAGINATION_MAX_LIMIT) as usize;
-    let option_start = start_after.map(Bound::exclusive);
-
-    let votes: StdResult<Vec<ProposalVoteResponse>> = PROPOSAL_VOTES
-        .prefix(U64Key::new(proposal_id))
-        .range(deps.storage, option_start, None, Order::Ascending)
-        .take(limit)
-        .map(|vote| {
-            let (k, v) = vote?;
-            let voter_address = String::from_utf8(k)?;
-
-            Ok(ProposalVoteResponse {
-                voter_address,
-                option: v.option,
-                power: v.power,
-            })
-        })
-        .collect();
-
-    Ok(ProposalVotesResponse {
-        proposal_id,
-        votes: votes?,
-    })
-}
-
-// HELPERS
-
-fn xmars_get_total_supply_at(
-    querier: &QuerierWrapper,
-    xmars_address: Addr,
-    block: u64,
-) -> StdResult<Uint128> {
-    let query: xmars_token::TotalSupplyResponse =
-        querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-            contract_addr: xmars_address.into(),
-            msg: to_binary(&xmars_token::msg::QueryMsg::TotalSupplyAt { block })?,
-        }))?;
-
-    Ok(query.total_supply)
-}
+/// End a proposal loaded through `load_proposal_to_end`
+fn end_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: Config,
+    mut proposal: Proposal,
+    total_voting_power: Uint128,
+) -> Result<Response, ContractError> {
+    let proposal_id = proposal.proposal_id;
+    let proposal_path = PROPOSALS.key(U64Key::new(proposal_id));
 
-fn xmars_get_balance_at(
-    querier: &QuerierWrapper,
-    xmars_address: Addr,
-    user_address: Addr,
-    block: u64,
-) -> StdResult<Uint128> {
-    let query: cw20::BalanceResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-        contract_addr: xmars_address.into(),
-        msg: to_binary(&xmars_token::msg::QueryMsg::BalanceAt {
-            address: user_address.to_string(),
-            block,
-        })?,
-    }))?;
+    let mars_contracts = vec![
+        MarsContract::MarsToken,
+        MarsContract::Staking,
+        MarsContract::Treasury,
+    ];
+    let mut addresses_query = address_provider::helpers::query_addresses(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        mars_contracts,
+    )?;
+    let treasury_address = addresses_query.pop().unwrap();
+    let staking_address = addresses_query.pop().unwrap();
+    let mars_token_address = addresses_query.pop().unwrap();
 
-    Ok(query.balance)
-}
+    let result = proposal_result(&config, &proposal, total_voting_power);
 
-fn vesting_get_total_voting_power_at(
-    querier: &QuerierWrapper,
-    vesting_address: Addr,
-    block: u64,
-) -> StdResult<Uint128> {
-    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-        contract_addr: vesting_address.into(),
-        msg: to_binary(&vesting::msg::QueryMsg::TotalVotingPowerAt { block })?,
-    }))
-}
+    // Determine proposal result
+    let (new_proposal_status, log_proposal_result) = if result.passed {
+        (ProposalStatus::Passed, "passed")
+    } else {
+        (ProposalStatus::Rejected, "rejected")
+    };
 
-fn vesting_get_voting_power_at(
-    querier: &QuerierWrapper,
-    vesting_address: Addr,
-    user_address: Addr,
-    block: u64,
-) -> StdResult<Uint128> {
-    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
-        contract_addr: vesting_address.into(),
-        msg: to_binary(&vesting::msg::QueryMsg::VotingPowerAt {
-            user_address: user_address.to_string(),
-            block,
-        })?,
-    }))
-}
+    // Update proposal status and keep the result so queries do not need to recompute it
+    set_proposal_status(deps.storage, &env, &mut proposal, new_proposal_status)?;
+    proposal.final_quorum = Some(result.quorum);
+    proposal.final_threshold = Some(result.threshold);
+    proposal.final_total_voting_power = Some(total_voting_power);
+    proposal.deposit_status = settled_deposit_status(&config, &proposal);
+    proposal_path.save(deps.storage, &proposal)?;
 
-// TESTS
+    // Deposit is refunded to the submitter if the proposal passed, slashed otherwise. If the
+    // transfer fails the proposal still ends and the deposit can be claimed later
+    // (see `reply`)
+    let deposit_msg =
+        deposit_settlement_msg(&config, &proposal, &mars_token_address, staking_address)?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{MockApi, MockStorage, MOCK_CONTRACT_ADDR};
-    use cosmwasm_std::{Coin, OwnedDeps, StdError, SubMsg};
-    use mars_core::council::MINIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE;
-    use mars_core::math::decimal::Decimal;
-    use mars_core::testing::{
-        mock_dependencies, mock_env, mock_info, MarsMockQuerier, MockEnvParams,
+    // Deposit is either refunded or sent to staking, so it is no longer locked. Refunds of
+    // co-sponsor deposits stay locked until each co-sponsor claims them
+    let unlocked_deposit = if is_deposit_slashed(&config, &proposal) {
+        proposal.deposit_amount
+    } else {
+        for (co_sponsor, co_sponsor_deposit) in &proposal.co_sponsors {
+            SPONSOR_REFUNDS.save(
+                deps.storage,
+                (U64Key::new(proposal_id), co_sponsor),
+                co_sponsor_deposit,
+            )?;
+        }
+        submitter_deposit(&proposal)?
     };
+    if proposal.deposit_token.is_none() {
+        let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+        global_state.locked_deposits = global_state
+            .locked_deposits
+            .checked_sub(unlocked_deposit)
+            .map_err(StdError::from)?;
+        GLOBAL_STATE.save(deps.storage, &global_state)?;
+    }
 
-    use crate::msg::ExecuteMsg::UpdateConfig;
-
-    const TEST_PROPOSAL_VOTING_PERIOD: u64 = 2000;
-    const TEST_PROPOSAL_EFFECTIVE_DELAY: u64 = 200;
-    const TEST_PROPOSAL_EXPIRATION_PERIOD: u64 = 300;
-    const TEST_PROPOSAL_REQUIRED_DEPOSIT: Uint128 = Uint128::new(10000);
+    let mut response = Response::new()
+        .add_submessage(SubMsg::reply_on_error(deposit_msg, proposal_id))
+        .add_attributes(vec![
+            attr("action", "end_proposal"),
+            attr("proposal_id", proposal_id.to_string()),
+            attr("proposal_result", log_proposal_result),
+        ]);
+
+    // Reward the caller for ending the proposal. Proposals can only be ended once so the reward
+    // cannot be paid twice
+    if !config.end_proposal_reward.is_zero() && info.sender != proposal.submitter_address {
+        response = response.add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: treasury_address.into(),
+            msg: to_binary(&treasury::msg::ExecuteMsg::ExecuteCosmosMsg(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: mars_token_address.into(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: info.sender.to_string(),
+                        amount: config.end_proposal_reward,
+                    })?,
+                    funds: vec![],
+                }),
+            ))?,
+            funds: vec![],
+        }));
+        response = response.add_attribute("end_proposal_reward", config.end_proposal_reward);
+    }
 
-    #[test]
-    fn test_proper_initialization() {
-        let mut deps = mock_dependencies(&[]);
-        let env = cosmwasm_std::testing::mock_env();
-        let info = mock_info("someone");
+    // Proposals flagged for auto execution are executed right away through ExecuteProposal
+    // (fast track proposals skip the effective delay), as long as all their messages still
+    // target fast track contracts. A failing message reverts the whole execution but not the
+    // end of the proposal, which stays Passed so it can be executed later
+    if proposal.status == ProposalStatus::Passed
+        && proposal.auto_execute
+        && is_fast_track_proposal(&config, &proposal)
+    {
+        response = response
+            .add_submessage(SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: env.contract.address.to_string(),
+                    msg: to_binary(&ExecuteMsg::ExecuteProposal { proposal_id })?,
+                    funds: vec![],
+                }),
+                AUTO_EXECUTE_REPLY_ID_FLAG | proposal_id,
+            ))
+            .add_attribute("auto_executed", "true");
+    }
 
-        // init config with empty params
-        {
-            let empty_config = CreateOrUpdateConfig {
-                address_provider_address: None,
+    Ok(response)
+}
 
-                proposal_voting_period: None,
-                proposal_effective_delay: None,
-                proposal_expiration_period: None,
-                proposal_required_deposit: None,
-                proposal_required_threshold: None,
-                proposal_required_quorum: None,
-            };
-            let msg = InstantiateMsg {
-                config: empty_config,
-            };
-            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-            assert_eq!(error_res, MarsError::InstantiateParamsUnavailable {}.into());
-        }
+pub fn execute_end_proposals_batch(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_ids: Vec<u64>,
+) -> Result<Response, ContractError> {
+    if proposal_ids.len() > MAX_END_PROPOSALS_PER_BATCH {
+        return Err(ContractError::BatchTooLarge {
+            max: MAX_END_PROPOSALS_PER_BATCH,
+        });
+    }
 
-        let init_config = CreateOrUpdateConfig {
-            address_provider_address: Some(String::from("address_provider")),
-            proposal_voting_period: Some(1),
-            proposal_effective_delay: Some(1),
-            proposal_expiration_period: Some(1),
-            proposal_required_deposit: Some(Uint128::new(1)),
-            proposal_required_quorum: Some(Decimal::percent(75)),
-            proposal_required_threshold: Some(Decimal::percent(
-                MINIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE,
-            )),
+    let config = CONFIG.load(deps.storage)?;
+    let mut response = Response::new();
+    for proposal_id in proposal_ids {
+        // Skip proposals that cannot be ended instead of failing the whole batch
+        let is_eligible = match PROPOSALS.may_load(deps.storage, U64Key::new(proposal_id))? {
+            Some(proposal) => {
+                proposal.status == ProposalStatus::Active && env.block.height > proposal.end_height
+            }
+            None => false,
         };
+        if !is_eligible {
+            continue;
+        }
 
-        // *
-        // init with invalid params
-        // *
-        {
-            // init with proposal_required_quorum greater than 1
-            let config = CreateOrUpdateConfig {
-                proposal_required_quorum: Some(Decimal::percent(101)),
-                ..init_config.clone()
-            };
-            let msg = InstantiateMsg { config };
-            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-            assert_eq!(
-                error_res,
-                MarsError::InvalidParam {
-                    param_name: "proposal_required_quorum".to_string(),
-                    invalid_value: "1.01".to_string(),
-                    predicate: "<= 1".to_string(),
+        // Proposals whose result cannot be computed yet (e.g. without a voting power snapshot)
+        // are reported and left for a later batch. Nothing is written before this point, so a
+        // skipped proposal is left as it was
+        let (proposal, total_voting_power) =
+            match load_proposal_to_end(deps.as_ref(), &env, &config, proposal_id) {
+                Ok(proposal_to_end) => proposal_to_end,
+                Err(error) => {
+                    response = response.add_attributes(vec![
+                        attr("skipped_proposal_id", proposal_id.to_string()),
+                        attr("error", error.to_string()),
+                    ]);
+                    continue;
                 }
-                .into()
-            );
-
-            // init with proposal_required_threshold less than 50%
-            let config = CreateOrUpdateConfig {
-                proposal_required_threshold: Some(Decimal::percent(49)),
-                ..init_config.clone()
             };
-            let msg = InstantiateMsg { config };
-            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-            assert_eq!(
-                error_res,
-                MarsError::InvalidParam {
-                    param_name: "proposal_required_threshold".to_string(),
-                    invalid_value: "0.49".to_string(),
-                    predicate: ">= 0.5 and <= 1".to_string(),
-                }
-                .into()
-            );
 
-            // init with proposal_required_threshold greater than 100%
-            let config = CreateOrUpdateConfig {
-                proposal_required_threshold: Some(Decimal::percent(101)),
-                ..init_config.clone()
-            };
-            let msg = InstantiateMsg { config };
-            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-            assert_eq!(
-                error_res,
-                MarsError::InvalidParam {
-                    param_name: "proposal_required_threshold".to_string(),
-                    invalid_value: "1.01".to_string(),
-                    predicate: ">= 0.5 and <= 1".to_string(),
-                }
-                .into()
-            );
-        }
+        let end_response = end_proposal(
+            deps.branch(),
+            env.clone(),
+            info.clone(),
+            config.clone(),
+            proposal,
+            total_voting_power,
+        )?;
+        response = response
+            .add_submessages(end_response.messages)
+            .add_attributes(end_response.attributes);
+    }
 
-        // Successful Init
-        {
-            let msg = InstantiateMsg {
-                config: init_config,
-            };
-            let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-            assert_eq!(0, res.messages.len());
+    Ok(response)
+}
 
-            let config = CONFIG.load(&deps.storage).unwrap();
-            assert_eq!(
-                Addr::unchecked("address_provider"),
-                config.address_provider_address
-            );
+/// Retry the deposit transfer of an ended proposal that failed when the proposal was ended
+pub fn execute_claim_pending_deposit(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let deposit_msg = PENDING_DEPOSITS.load(deps.storage, U64Key::new(proposal_id))?;
+    PENDING_DEPOSITS.remove(deps.storage, U64Key::new(proposal_id));
 
-            let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
-            assert_eq!(global_state.proposal_count, 0);
+    // The proposal may have been archived while its deposit was pending
+    let config = CONFIG.load(deps.storage)?;
+    for proposals in [&PROPOSALS, &ARCHIVED_PROPOSALS] {
+        if let Some(mut proposal) = proposals.may_load(deps.storage, U64Key::new(proposal_id))? {
+            proposal.deposit_status = settled_deposit_status(&config, &proposal);
+            proposals.save(deps.storage, U64Key::new(proposal_id), &proposal)?;
+
+            // The deposit is no longer locked once it is transferred
+            if proposal.deposit_token.is_none() {
+                let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+                global_state.locked_deposits = global_state
+                    .locked_deposits
+                    .checked_sub(settled_deposit_amount(&config, &proposal)?)
+                    .map_err(StdError::from)?;
+                GLOBAL_STATE.save(deps.storage, &global_state)?;
+            }
         }
     }
 
-    #[test]
-    fn test_update_config() {
-        let mut deps = mock_dependencies(&[]);
+    let res = Response::new()
+        .add_message(deposit_msg)
+        .add_attributes(vec![
+            attr("action", "claim_pending_deposit"),
+            attr("proposal_id", proposal_id.to_string()),
+        ]);
+    Ok(res)
+}
 
-        // *
+/// Refund the deposit the sender added through CoSponsor to a proposal whose deposit was refunded
+pub fn execute_claim_sponsor_refund(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let refund_key = (U64Key::new(proposal_id), &info.sender);
+    let refund_amount = SPONSOR_REFUNDS
+        .may_load(deps.storage, refund_key.clone())?
+        .ok_or(ContractError::NoSponsorRefund {})?;
+    SPONSOR_REFUNDS.remove(deps.storage, refund_key);
+
+    // The proposal may have been archived before the refund was claimed
+    let proposal = match PROPOSALS.may_load(deps.storage, U64Key::new(proposal_id))? {
+        Some(proposal) => proposal,
+        None => ARCHIVED_PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?,
+    };
+
+    let deposit_token_address = match proposal.deposit_token {
+        Some(deposit_token) => deposit_token,
+        None => {
+            let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+            global_state.locked_deposits = global_state
+                .locked_deposits
+                .checked_sub(refund_amount)
+                .map_err(StdError::from)?;
+            GLOBAL_STATE.save(deps.storage, &global_state)?;
+
+            let config = CONFIG.load(deps.storage)?;
+            address_provider::helpers::query_address(
+                &deps.querier,
+                config.address_provider_address,
+                MarsContract::MarsToken,
+            )?
+        }
+    };
+
+    let res = Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deposit_token_address.into(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: refund_amount,
+            })?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![
+            attr("action", "claim_sponsor_refund"),
+            attr("proposal_id", proposal_id.to_string()),
+            attr("sponsor", info.sender),
+            attr("amount", refund_amount),
+        ]);
+    Ok(res)
+}
+
+/// Send the accumulated slashed deposits that failed to transfer. The destination is resolved
+/// again so deposits are not stuck if the staking contract was paused or replaced
+pub fn execute_sweep_pending_slashes(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending_slashes = PENDING_SLASHES
+        .may_load(deps.storage)?
+        .unwrap_or_else(Uint128::zero);
+    if pending_slashes.is_zero() {
+        return Err(ContractError::NoPendingSlashes {});
+    }
+    PENDING_SLASHES.save(deps.storage, &Uint128::zero())?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut addresses_query = address_provider::helpers::query_addresses(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        vec![MarsContract::MarsToken, MarsContract::Staking],
+    )?;
+    let staking_address = addresses_query.pop().unwrap();
+    let mars_token_address = addresses_query.pop().unwrap();
+
+    let res = Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: mars_token_address.into(),
+            msg: to_binary(&slashed_deposit_msg(
+                config.slashed_deposit_destination,
+                staking_address,
+                pending_slashes,
+            ))?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![
+            attr("action", "sweep_pending_slashes"),
+            attr("amount", pending_slashes),
+        ]);
+    Ok(res)
+}
+
+/// Lets the submitter end its proposal if nobody did after the voting period. The proposal goes
+/// through the same tally as in EndProposal so the deposit is refunded or slashed accordingly
+pub fn execute_reclaim_stale_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let proposal = PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?;
+    if info.sender != proposal.submitter_address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let reclaim_height = height_checked_add(proposal.end_height, config.reclaim_grace_period)?;
+    if env.block.height <= reclaim_height {
+        return Err(ContractError::ReclaimStaleDepositGracePeriodNotEnded { reclaim_height });
+    }
+
+    execute_end_proposal(deps, env, info, proposal_id)
+}
+
+pub fn execute_execute_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let proposal_path = PROPOSALS.key(U64Key::new(proposal_id));
+    let mut proposal = proposal_path.load(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    // The council executes auto execute proposals itself when they are ended
+    if !config.executor_allowlist.is_empty()
+        && !config.executor_allowlist.contains(&info.sender)
+        && info.sender != env.contract.address
+    {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    match proposal_executable_reason(&env, &config, &proposal)? {
+        ProposalExecutableReason::NotPassed => {
+            return Err(ContractError::ExecuteProposalNotPassed {})
+        }
+        ProposalExecutableReason::DelayNotEnded => {
+            return Err(ContractError::ExecuteProposalDelayNotEnded {})
+        }
+        ProposalExecutableReason::Expired => return Err(ContractError::ExecuteProposalExpired {}),
+        ProposalExecutableReason::Ready => {}
+    }
+
+    // Status is persisted before the proposal messages are dispatched so that any of them
+    // calling back into ExecuteProposal for the same id fails the `Passed` status check above
+    set_proposal_status(deps.storage, &env, &mut proposal, ProposalStatus::Executed)?;
+    proposal_path.save(deps.storage, &proposal)?;
+
+    let response = Response::new().add_attributes(vec![
+        attr("action", "execute_proposal"),
+        attr("proposal_id", proposal_id.to_string()),
+    ]);
+    add_proposal_messages(
+        deps.storage,
+        &config,
+        response,
+        proposal_id,
+        proposal.messages.unwrap_or_default(),
+    )
+}
+
+/// Reply ids of proposal messages have the highest bit set, which tells them apart from deposit
+/// settlement replies (whose id is the proposal id). The remaining bits hold the proposal id and
+/// the position of the message in execution order
+const EXECUTION_REPLY_ID_FLAG: u64 = 1 << 63;
+const EXECUTION_REPLY_POSITION_BITS: u32 = 32;
+/// Reply id of the auto execution of a proposal is the proposal id with this bit set
+const AUTO_EXECUTE_REPLY_ID_FLAG: u64 = 1 << 62;
+
+fn execution_reply_id(proposal_id: u64, position: usize) -> StdResult<u64> {
+    let position = position as u64;
+    if proposal_id >= EXECUTION_REPLY_ID_FLAG >> EXECUTION_REPLY_POSITION_BITS
+        || position >= 1 << EXECUTION_REPLY_POSITION_BITS
+    {
+        return Err(StdError::generic_err(
+            "proposal message execution cannot be tracked",
+        ));
+    }
+    Ok(EXECUTION_REPLY_ID_FLAG | proposal_id << EXECUTION_REPLY_POSITION_BITS | position)
+}
+
+/// Add the proposal messages to the response in execution order, routing Ica messages through
+/// the ICA controller. Each message replies on success so its execution is recorded. The
+/// proposal is recorded as the executing one until its last message executes, as messages
+/// calling back into the council run after the response is returned
+fn add_proposal_messages(
+    storage: &mut dyn Storage,
+    config: &Config,
+    mut response: Response,
+    proposal_id: u64,
+    mut messages: Vec<ProposalMessage>,
+) -> Result<Response, ContractError> {
+    if !messages.is_empty() {
+        EXECUTING_PROPOSAL.save(storage, &proposal_id)?;
+    }
+    messages.sort_by(|a, b| a.execution_order.cmp(&b.execution_order));
+
+    // Wasm messages have no memo field, so memos are emitted as attributes keyed by the
+    // position of the message in execution order for relayers to pick up
+    for (index, message) in messages.into_iter().enumerate() {
+        if let Some(memo) = message.memo {
+            response = response.add_attribute(format!("memo_{}", index), memo);
+        }
+        let msg = match message.route {
+            ExecuteRoute::Local => message.msg,
+            ExecuteRoute::Ica { channel } => {
+                let ica_controller_address = config
+                    .ica_controller_address
+                    .clone()
+                    .ok_or(ContractError::IcaRouteNotSupported {})?;
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: ica_controller_address.into(),
+                    msg: to_binary(&IcaControllerExecuteMsg::SendTx {
+                        channel,
+                        msgs: vec![message.msg],
+                    })?,
+                    funds: vec![],
+                })
+            }
+        };
+        response = response.add_submessage(SubMsg::reply_on_success(
+            msg,
+            execution_reply_id(proposal_id, index)?,
+        ));
+    }
+
+    Ok(response)
+}
+
+/// Mint MARS through an approved proposal. The council needs to be a minter of the MARS token
+pub fn execute_mint_treasury(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    // Can only be done through an approved proposal
+    if info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+    if amount > config.mint_per_proposal_cap {
+        return Err(ContractError::MintTreasuryProposalCapExceeded {
+            cap: config.mint_per_proposal_cap,
+        });
+    }
+
+    let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+    let remaining = config
+        .mint_total_cap
+        .saturating_sub(global_state.total_minted);
+    if amount > remaining {
+        return Err(ContractError::MintTreasuryTotalCapExceeded { remaining });
+    }
+    global_state.total_minted = global_state.total_minted.checked_add(amount)?;
+    GLOBAL_STATE.save(deps.storage, &global_state)?;
+
+    let recipient_address = deps.api.addr_validate(&recipient)?;
+    let mars_token_address = address_provider::helpers::query_address(
+        &deps.querier,
+        config.address_provider_address,
+        MarsContract::MarsToken,
+    )?;
+
+    let res = Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: mars_token_address.into(),
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: recipient_address.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![
+            attr("action", "mint_treasury"),
+            attr("recipient", recipient_address),
+            attr("amount", amount),
+            attr("total_minted", global_state.total_minted),
+        ]);
+    Ok(res)
+}
+
+/// Cancel an active proposal found to be harmful while being voted on. The deposit is slashed to
+/// the configured destination
+pub fn execute_force_cancel_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let is_emergency_owner = config.emergency_owner.as_ref() == Some(&info.sender);
+    if !is_emergency_owner && info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    let proposal_path = PROPOSALS.key(U64Key::new(proposal_id));
+    let mut proposal = proposal_path.load(deps.storage)?;
+    if proposal.status != ProposalStatus::Active {
+        return Err(ContractError::ProposalNotActive {});
+    }
+
+    set_proposal_status(deps.storage, &env, &mut proposal, ProposalStatus::Cancelled)?;
+    proposal.deposit_status = settled_deposit_status(&config, &proposal);
+    proposal_path.save(deps.storage, &proposal)?;
+
+    if proposal.deposit_token.is_none() {
+        let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+        global_state.locked_deposits = global_state
+            .locked_deposits
+            .checked_sub(proposal.deposit_amount)
+            .map_err(StdError::from)?;
+        GLOBAL_STATE.save(deps.storage, &global_state)?;
+    }
+
+    let mut addresses_query = address_provider::helpers::query_addresses(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        vec![MarsContract::MarsToken, MarsContract::Staking],
+    )?;
+    let staking_address = addresses_query.pop().unwrap();
+    let mars_token_address = addresses_query.pop().unwrap();
+
+    // As when ending a proposal, the cancellation goes through if the slash fails and the
+    // deposit is kept as pending (see `reply`)
+    let deposit_msg =
+        deposit_settlement_msg(&config, &proposal, &mars_token_address, staking_address)?;
+
+    let res = Response::new()
+        .add_submessage(SubMsg::reply_on_error(deposit_msg, proposal_id))
+        .add_attributes(vec![
+            attr("action", "force_cancel_proposal"),
+            attr("proposal_id", proposal_id.to_string()),
+        ]);
+    Ok(res)
+}
+
+/// Move a passed signaling proposal to Implemented once its mandate was carried out off-chain,
+/// storing a note about how it was
+pub fn execute_mark_implemented(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    proposal_id: u64,
+    note: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let is_emergency_owner = config.emergency_owner.as_ref() == Some(&info.sender);
+    if !is_emergency_owner && info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    let proposal_path = PROPOSALS.key(U64Key::new(proposal_id));
+    let mut proposal = proposal_path.load(deps.storage)?;
+    if proposal.status != ProposalStatus::Passed {
+        return Err(ContractError::ExecuteProposalNotPassed {});
+    }
+    if proposal.messages.is_some() {
+        return Err(ContractError::MarkImplementedNotSignaling {});
+    }
+    if note.len() as u64 > config.description_max {
+        return Err(ContractError::ImplementationNoteTooLong {
+            max: config.description_max,
+        });
+    }
+
+    set_proposal_status(
+        deps.storage,
+        &env,
+        &mut proposal,
+        ProposalStatus::Implemented,
+    )?;
+    proposal.implementation_note = Some(note);
+    proposal_path.save(deps.storage, &proposal)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "mark_implemented"),
+        attr("proposal_id", proposal_id.to_string()),
+    ]);
+    Ok(res)
+}
+
+/// Update config
+pub fn execute_update_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_config: CreateOrUpdateConfig,
+) -> Result<Response, ContractError> {
+    // In council, config can be updated only by itself (through an approved proposal)
+    // instead of by it's owner
+    if info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    let proposal_id = EXECUTING_PROPOSAL.may_load(deps.storage)?;
+    update_config(deps, &env, proposal_id, new_config)
+}
+
+/// Apply the config update and record it in the config history. Callers are responsible for
+/// checking authorization
+fn update_config(
+    deps: DepsMut,
+    env: &Env,
+    proposal_id: Option<u64>,
+    new_config: CreateOrUpdateConfig,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Destructuring a struct’s fields into separate variables in order to force
+    // compile error if we add more params
+    let CreateOrUpdateConfig {
+        address_provider_address,
+
+        proposal_voting_period,
+        proposal_effective_delay,
+        proposal_expiration_period,
+        proposal_required_deposit,
+        proposal_required_quorum,
+        proposal_required_threshold,
+        submitter_cooldown,
+        discussion_period,
+        emergency_owner,
+        title_max,
+        description_max,
+        link_max,
+        slashed_deposit_destination,
+        fast_track_targets,
+        reclaim_grace_period,
+        snapshot_offset,
+        end_proposal_reward,
+        quorum_basis,
+        quorum_excluded_addresses,
+        mint_per_proposal_cap,
+        mint_total_cap,
+        reservation_period,
+        allow_privileged_token_calls,
+        early_vote_bonus,
+        voting_token_override,
+        avg_block_time_seconds,
+        min_for_votes_absolute,
+        proposal_required_deposit_ratio,
+        min_vote_power,
+        dedupe_window,
+        executor_allowlist,
+        ica_controller_address,
+        surge_window,
+        surge_threshold,
+        surge_multiplier,
+        allowed_categories,
+        accepted_deposit_tokens,
+        refund_signaling_always,
+        auto_expire_grace,
+        require_call_simulation,
+    } = new_config;
+
+    // Update config
+    let previous_address_provider_address = config.address_provider_address.clone();
+    config.address_provider_address = option_string_to_addr(
+        deps.api,
+        address_provider_address,
+        config.address_provider_address,
+    )?;
+    if config.address_provider_address != previous_address_provider_address {
+        validate_address_provider(&deps.querier, &config.address_provider_address)?;
+    }
+
+    config.proposal_voting_period = proposal_voting_period.unwrap_or(config.proposal_voting_period);
+    config.proposal_effective_delay =
+        proposal_effective_delay.unwrap_or(config.proposal_effective_delay);
+    config.proposal_expiration_period =
+        proposal_expiration_period.unwrap_or(config.proposal_expiration_period);
+    config.proposal_required_deposit =
+        proposal_required_deposit.unwrap_or(config.proposal_required_deposit);
+    config.proposal_required_quorum =
+        proposal_required_quorum.unwrap_or(config.proposal_required_quorum);
+    config.proposal_required_threshold =
+        proposal_required_threshold.unwrap_or(config.proposal_required_threshold);
+    config.submitter_cooldown = submitter_cooldown.unwrap_or(config.submitter_cooldown);
+    config.discussion_period = discussion_period.unwrap_or(config.discussion_period);
+    if let Some(emergency_owner) = emergency_owner {
+        config.emergency_owner = Some(deps.api.addr_validate(&emergency_owner)?);
+    }
+    config.title_max = title_max.unwrap_or(config.title_max);
+    config.description_max = description_max.unwrap_or(config.description_max);
+    config.link_max = link_max.unwrap_or(config.link_max);
+    if let Some(slashed_deposit_destination) = slashed_deposit_destination {
+        config.slashed_deposit_destination =
+            validate_slashed_deposit_destination(deps.api, slashed_deposit_destination)?;
+    }
+    if let Some(fast_track_targets) = fast_track_targets {
+        config.fast_track_targets = fast_track_targets
+            .iter()
+            .map(|address| deps.api.addr_validate(address))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+    config.reclaim_grace_period = reclaim_grace_period.unwrap_or(config.reclaim_grace_period);
+    config.snapshot_offset = snapshot_offset.unwrap_or(config.snapshot_offset);
+    config.end_proposal_reward = end_proposal_reward.unwrap_or(config.end_proposal_reward);
+    config.quorum_basis = quorum_basis.unwrap_or(config.quorum_basis);
+    if let Some(quorum_excluded_addresses) = quorum_excluded_addresses {
+        config.quorum_excluded_addresses = quorum_excluded_addresses
+            .iter()
+            .map(|address| deps.api.addr_validate(address))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+    config.mint_per_proposal_cap = mint_per_proposal_cap.unwrap_or(config.mint_per_proposal_cap);
+    config.mint_total_cap = mint_total_cap.unwrap_or(config.mint_total_cap);
+    config.reservation_period = reservation_period.unwrap_or(config.reservation_period);
+    config.allow_privileged_token_calls =
+        allow_privileged_token_calls.unwrap_or(config.allow_privileged_token_calls);
+    config.early_vote_bonus = early_vote_bonus.unwrap_or(config.early_vote_bonus);
+    if let Some(voting_token_override) = voting_token_override {
+        config.voting_token_override = Some(deps.api.addr_validate(&voting_token_override)?);
+    }
+    config.avg_block_time_seconds = avg_block_time_seconds.unwrap_or(config.avg_block_time_seconds);
+    config.min_for_votes_absolute = min_for_votes_absolute.unwrap_or(config.min_for_votes_absolute);
+    config.proposal_required_deposit_ratio =
+        proposal_required_deposit_ratio.unwrap_or(config.proposal_required_deposit_ratio);
+    config.min_vote_power = min_vote_power.unwrap_or(config.min_vote_power);
+    config.dedupe_window = dedupe_window.unwrap_or(config.dedupe_window);
+    if let Some(executor_allowlist) = executor_allowlist {
+        config.executor_allowlist = executor_allowlist
+            .iter()
+            .map(|address| deps.api.addr_validate(address))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+    if let Some(ica_controller_address) = ica_controller_address {
+        config.ica_controller_address = Some(deps.api.addr_validate(&ica_controller_address)?);
+    }
+    config.surge_window = surge_window.unwrap_or(config.surge_window);
+    config.surge_threshold = surge_threshold.unwrap_or(config.surge_threshold);
+    config.surge_multiplier = surge_multiplier.unwrap_or(config.surge_multiplier);
+    config.allowed_categories = allowed_categories.unwrap_or(config.allowed_categories);
+    if let Some(accepted_deposit_tokens) = accepted_deposit_tokens {
+        config.accepted_deposit_tokens = accepted_deposit_tokens
+            .iter()
+            .map(|(token, minimum)| Ok((deps.api.addr_validate(token)?, *minimum)))
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+    config.refund_signaling_always =
+        refund_signaling_always.unwrap_or(config.refund_signaling_always);
+    config.auto_expire_grace = auto_expire_grace.unwrap_or(config.auto_expire_grace);
+    config.require_call_simulation =
+        require_call_simulation.unwrap_or(config.require_call_simulation);
+
+    // Validate config
+    config.validate()?;
+
+    CONFIG.save(deps.storage, &config)?;
+    record_config_history(deps.storage, env, proposal_id, config)?;
+
+    let res = Response::new().add_attribute("action", "update_config");
+    Ok(res)
+}
+
+fn record_config_history(
+    storage: &mut dyn Storage,
+    env: &Env,
+    proposal_id: Option<u64>,
+    config: Config,
+) -> StdResult<()> {
+    CONFIG_HISTORY.update(
+        storage,
+        U64Key::new(env.block.height),
+        |entries| -> StdResult<_> {
+            let mut entries = entries.unwrap_or_default();
+            entries.push(ConfigHistoryEntry {
+                height: env.block.height,
+                proposal_id,
+                config,
+            });
+            Ok(entries)
+        },
+    )?;
+    Ok(())
+}
+
+/// Pause or unpause proposal submissions and voting. Gives the emergency owner a way to react
+/// quickly without being able to change any other parameter
+pub fn execute_emergency_set_paused(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    submissions_paused: bool,
+    voting_paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    let is_emergency_owner = config.emergency_owner.as_ref() == Some(&info.sender);
+    if !is_emergency_owner && info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    config.submissions_paused = submissions_paused;
+    config.voting_paused = voting_paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "emergency_set_paused"),
+        attr("submissions_paused", submissions_paused.to_string()),
+        attr("voting_paused", voting_paused.to_string()),
+    ]);
+    Ok(res)
+}
+
+/// Propose a new emergency owner. It only replaces the current one after accepting, so the role
+/// cannot be handed to an address nobody controls
+pub fn execute_transfer_guardianship(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_guardian: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let is_emergency_owner = config.emergency_owner.as_ref() == Some(&info.sender);
+    if !is_emergency_owner && info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    let new_guardian_address = deps.api.addr_validate(&new_guardian)?;
+    PENDING_GUARDIAN.save(deps.storage, &new_guardian_address)?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "transfer_guardianship"),
+        attr("new_guardian", new_guardian_address),
+    ]);
+    Ok(res)
+}
+
+/// Accept the emergency owner role proposed through TransferGuardianship
+pub fn execute_accept_guardianship(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending_guardian = PENDING_GUARDIAN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingGuardianship {})?;
+    if info.sender != pending_guardian {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.emergency_owner = Some(pending_guardian.clone());
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_GUARDIAN.remove(deps.storage);
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "accept_guardianship"),
+        attr("guardian", pending_guardian),
+    ]);
+    Ok(res)
+}
+
+/// Transfer cw20 tokens other than MARS sent to the council by mistake
+pub fn execute_rescue_tokens(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: String,
+    recipient: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    // Can only be done through an approved proposal
+    if info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+    let token_address = deps.api.addr_validate(&token)?;
+    let mars_token_address = address_provider::helpers::query_address(
+        &deps.querier,
+        config.address_provider_address,
+        MarsContract::MarsToken,
+    )?;
+    if token_address == mars_token_address {
+        return Err(ContractError::RescueTokensMarsToken {});
+    }
+    if config
+        .accepted_deposit_tokens
+        .iter()
+        .any(|(token, _)| *token == token_address)
+    {
+        return Err(ContractError::RescueTokensDepositToken {});
+    }
+
+    let recipient_address = deps.api.addr_validate(&recipient)?;
+
+    let res = Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: token_address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient_address.to_string(),
+                amount,
+            })?,
+            funds: vec![],
+        }))
+        .add_attributes(vec![
+            attr("action", "rescue_tokens"),
+            attr("token", token_address),
+            attr("recipient", recipient_address),
+            attr("amount", amount),
+        ]);
+    Ok(res)
+}
+
+/// Change the address provider and the voting token override together so the council never
+/// resolves MARS and the voting token from different deployments
+pub fn execute_rotate_governance_tokens(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address_provider: String,
+    voting_token_override: Option<String>,
+) -> Result<Response, ContractError> {
+    // Can only be done through an approved proposal
+    if info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    };
+
+    let mut config = CONFIG.load(deps.storage)?;
+    config.address_provider_address = deps.api.addr_validate(&address_provider)?;
+    config.voting_token_override = voting_token_override
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
+    validate_address_provider(&deps.querier, &config.address_provider_address)?;
+
+    let mars_token_address = address_provider::helpers::query_address(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        MarsContract::MarsToken,
+    )?;
+    let voting_token_address = get_voting_token_address(&deps.querier, &config)?;
+    let balance_at = xmars_get_balance_at(
+        &deps.querier,
+        voting_token_address.clone(),
+        env.contract.address.clone(),
+        env.block.height,
+    );
+    if voting_token_address == mars_token_address || balance_at.is_err() {
+        return Err(ContractError::IncompatibleVotingToken {
+            voting_token: voting_token_address.into(),
+        });
+    }
+
+    CONFIG.save(deps.storage, &config)?;
+    let proposal_id = EXECUTING_PROPOSAL.may_load(deps.storage)?;
+    record_config_history(deps.storage, &env, proposal_id, config.clone())?;
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "rotate_governance_tokens"),
+        attr("address_provider", config.address_provider_address),
+        attr("voting_token", voting_token_address),
+    ]);
+    Ok(res)
+}
+
+/// Move finalized proposals with an id lower than `before_id` from PROPOSALS to the archive.
+/// Their votes, status history and indexes are kept
+pub fn execute_archive_proposals(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    before_id: u64,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let is_emergency_owner = config.emergency_owner.as_ref() == Some(&info.sender);
+    if !is_emergency_owner && info.sender != env.contract.address {
+        return Err(MarsError::Unauthorized {}.into());
+    }
+
+    // Proposals are scanned in pages, with the same limits as migrate
+    let start = start_after.map(|id| Bound::exclusive(U64Key::new(id)));
+    let end = Bound::exclusive(U64Key::new(before_id));
+    let limit = limit
+        .unwrap_or(MIGRATE_DEFAULT_LIMIT)
+        .min(MIGRATE_MAX_LIMIT) as usize;
+    let proposals: StdResult<Vec<Proposal>> = PROPOSALS
+        .range(deps.storage, start, Some(end), Order::Ascending)
+        .take(limit)
+        .map(|item| Ok(item?.1))
+        .collect();
+    let proposals = proposals?;
+    let last_proposal_id = proposals.last().map(|proposal| proposal.proposal_id);
+
+    let mut archived_count: u64 = 0;
+    for proposal in proposals {
+        let is_finalized = match proposal.status {
+            // Proposals are never stored as Expired
+            ProposalStatus::Active | ProposalStatus::Expired => false,
+            ProposalStatus::Passed => matches!(
+                proposal_executable_reason(&env, &config, &proposal)?,
+                ProposalExecutableReason::Expired
+            ),
+            ProposalStatus::Rejected
+            | ProposalStatus::Executed
+            | ProposalStatus::Cancelled
+            | ProposalStatus::Implemented => true,
+        };
+        if !is_finalized {
+            continue;
+        }
+
+        let key = U64Key::new(proposal.proposal_id);
+        PROPOSALS.remove(deps.storage, key.clone());
+        ARCHIVED_PROPOSALS.save(deps.storage, key, &proposal)?;
+        archived_count += 1;
+    }
+
+    let mut res = Response::new().add_attributes(vec![
+        attr("action", "archive_proposals"),
+        attr("before_id", before_id.to_string()),
+        attr("archived_count", archived_count.to_string()),
+    ]);
+    if let Some(last_proposal_id) = last_proposal_id {
+        res = res.add_attribute("last_proposal_id", last_proposal_id.to_string());
+    }
+    Ok(res)
+}
+
+// REPLY
+
+/// Only deposit transfers sent when ending a proposal reply (on error), with the proposal id as
+/// reply id. The transfer is recorded so the proposal can end regardless: refunds are retried
+/// through ClaimPendingDeposit while slashed deposits accumulate to be sent through
+/// SweepPendingSlashes
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, reply: Reply) -> Result<Response, ContractError> {
+    if reply.id & EXECUTION_REPLY_ID_FLAG != 0 {
+        return record_execution_result(deps, env, reply.id);
+    }
+    if reply.id & AUTO_EXECUTE_REPLY_ID_FLAG != 0 {
+        return record_auto_execute_failure(reply.id & !AUTO_EXECUTE_REPLY_ID_FLAG, reply.result);
+    }
+
+    let proposal_id = reply.id;
+    let error = match reply.result {
+        ContractResult::Err(error) => error,
+        ContractResult::Ok(_) => return Ok(Response::new()),
+    };
+
+    // Pending slashes are in MARS, failed slashes of deposits in other tokens are kept as pending
+    // deposits instead
+    let config = CONFIG.load(deps.storage)?;
+    let mut proposal = PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?;
+    if is_deposit_slashed(&config, &proposal) && proposal.deposit_token.is_none() {
+        let pending_slashes = PENDING_SLASHES
+            .may_load(deps.storage)?
+            .unwrap_or_else(Uint128::zero)
+            .checked_add(proposal.deposit_amount)?;
+        PENDING_SLASHES.save(deps.storage, &pending_slashes)?;
+
+        let res = Response::new().add_attributes(vec![
+            attr("action", "record_pending_slash"),
+            attr("proposal_id", proposal_id.to_string()),
+            attr("error", error),
+        ]);
+        return Ok(res);
+    }
+
+    let mut addresses_query = address_provider::helpers::query_addresses(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        vec![MarsContract::MarsToken, MarsContract::Staking],
+    )?;
+    let staking_address = addresses_query.pop().unwrap();
+    let mars_token_address = addresses_query.pop().unwrap();
+
+    let deposit_msg =
+        deposit_settlement_msg(&config, &proposal, &mars_token_address, staking_address)?;
+    PENDING_DEPOSITS.save(deps.storage, U64Key::new(proposal_id), &deposit_msg)?;
+
+    // The deposit stays with the council, and locked, until the transfer is claimed
+    proposal.deposit_status = DepositStatus::Locked;
+    PROPOSALS.save(deps.storage, U64Key::new(proposal_id), &proposal)?;
+    if proposal.deposit_token.is_none() {
+        let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+        global_state.locked_deposits = global_state
+            .locked_deposits
+            .checked_add(settled_deposit_amount(&config, &proposal)?)?;
+        GLOBAL_STATE.save(deps.storage, &global_state)?;
+    }
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "record_pending_deposit"),
+        attr("proposal_id", proposal_id.to_string()),
+        attr("error", error),
+    ]);
+    Ok(res)
+}
+
+/// Record that a proposal message executed successfully
+fn record_execution_result(
+    deps: DepsMut,
+    env: Env,
+    reply_id: u64,
+) -> Result<Response, ContractError> {
+    let proposal_id = (reply_id & !EXECUTION_REPLY_ID_FLAG) >> EXECUTION_REPLY_POSITION_BITS;
+    let position = reply_id & ((1 << EXECUTION_REPLY_POSITION_BITS) - 1);
+
+    let proposal = PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?;
+    let mut messages = proposal.messages.unwrap_or_default();
+    messages.sort_by_key(|message| message.execution_order);
+    let execution_order = messages
+        .get(position as usize)
+        .ok_or_else(|| StdError::not_found("ProposalMessage"))?
+        .execution_order;
+
+    EXECUTION_RESULTS.save(
+        deps.storage,
+        (U64Key::new(proposal_id), U64Key::new(position)),
+        &ExecutionResult {
+            position,
+            execution_order,
+            height: env.block.height,
+        },
+    )?;
+
+    // Later calls from the council to itself are not part of the proposal
+    if position as usize + 1 == messages.len() {
+        EXECUTING_PROPOSAL.remove(deps.storage);
+    }
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "record_execution_result"),
+        attr("proposal_id", proposal_id.to_string()),
+        attr("execution_order", execution_order.to_string()),
+    ]);
+    Ok(res)
+}
+
+/// Record that the auto execution of a proposal failed. The proposal is left Passed, as the
+/// execution was reverted
+fn record_auto_execute_failure(
+    proposal_id: u64,
+    result: ContractResult<SubMsgExecutionResponse>,
+) -> Result<Response, ContractError> {
+    let error = match result {
+        ContractResult::Err(error) => error,
+        ContractResult::Ok(_) => return Ok(Response::new()),
+    };
+
+    let res = Response::new().add_attributes(vec![
+        attr("action", "record_auto_execute_failure"),
+        attr("proposal_id", proposal_id.to_string()),
+        attr("error", error),
+    ]);
+    Ok(res)
+}
+
+// SUDO
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        // Sudo messages can only be sent by the chain (e.g. through chain governance), so they
+        // are already authorized
+        SudoMsg::UpdateConfig { config } => update_config(deps, &env, None, config),
+    }
+}
+
+// MIGRATION
+
+// Migration pagination defaults
+const MIGRATE_DEFAULT_LIMIT: u32 = 50;
+const MIGRATE_MAX_LIMIT: u32 = 100;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // Councils deployed before cw2 was added have no version stored. Migrating from a newer
+    // version could misread state stored in a format this version does not know
+    if let Ok(version) = get_contract_version(deps.storage) {
+        if version.contract != CONTRACT_NAME {
+            return Err(ContractError::MigrateInvalidContract {
+                contract: version.contract,
+            });
+        }
+        match parse_version(&version.version) {
+            Some(stored_version) if Some(stored_version) <= parse_version(CONTRACT_VERSION) => {}
+            _ => {
+                return Err(ContractError::MigrateInvalidVersion {
+                    version: version.version,
+                })
+            }
+        }
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // Config and global state fields added since the council was deployed are filled with their
+    // defaults on load, so saving them back stores them in the current format
+    let config = CONFIG.load(deps.storage)?;
+    CONFIG.save(deps.storage, &config)?;
+    let mut global_state = GLOBAL_STATE.load(deps.storage)?;
+
+    let start = msg.start_after.map(|id| Bound::exclusive(U64Key::new(id)));
+    let limit = msg
+        .limit
+        .unwrap_or(MIGRATE_DEFAULT_LIMIT)
+        .min(MIGRATE_MAX_LIMIT) as usize;
+
+    let proposals: StdResult<Vec<_>> = PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_k, v) = item?;
+            Ok(v)
+        })
+        .collect();
+    let proposals = proposals?;
+
+    // Proposals submitted after the upgrade are already indexed, which also makes running the
+    // same page twice harmless. Proposals that are not indexed yet were stored in the previous
+    // format, so their new fields are backfilled as well.
+    //
+    // The limit is a budget on the proposals and votes a page stores, so a proposal with many
+    // votes can take several pages. It is only indexed once all its votes are backfilled, so the
+    // next page starting after the last migrated proposal resumes it
+    let mut budget = limit.max(1);
+    let mut indexed_count: u64 = 0;
+    let mut last_proposal_id = None;
+    let mut migrating_proposal_id = None;
+    for proposal in proposals {
+        if budget == 0 {
+            break;
+        }
+
+        let proposal_id = proposal.proposal_id;
+        if !is_proposal_indexed(deps.storage, &proposal) {
+            let proposal = match migrate_legacy_proposal(deps.storage, proposal, &mut budget)? {
+                Some(proposal) => proposal,
+                None => {
+                    migrating_proposal_id = Some(proposal_id);
+                    break;
+                }
+            };
+            index_proposal(deps.storage, &proposal)?;
+            if proposal.status == ProposalStatus::Active {
+                global_state.locked_deposits = global_state
+                    .locked_deposits
+                    .checked_add(proposal.deposit_amount)?;
+            }
+            indexed_count += 1;
+        }
+        budget = budget.saturating_sub(1);
+        last_proposal_id = Some(proposal_id);
+    }
+    GLOBAL_STATE.save(deps.storage, &global_state)?;
+
+    let mut response = Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("indexed_count", indexed_count.to_string()),
+    ]);
+    if let Some(last_proposal_id) = last_proposal_id {
+        response = response.add_attribute("last_proposal_id", last_proposal_id.to_string());
+    }
+    if let Some(migrating_proposal_id) = migrating_proposal_id {
+        response =
+            response.add_attribute("migrating_proposal_id", migrating_proposal_id.to_string());
+    }
+
+    Ok(response)
+}
+
+/// Major, minor and patch numbers of a semver version, ignoring pre-release and build metadata
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let core = version.split(['-', '+']).next()?;
+    let mut numbers = core.split('.').map(|number| number.parse::<u64>().ok());
+    let parsed = (numbers.next()??, numbers.next()??, numbers.next()??);
+    if numbers.next().is_some() {
+        return None;
+    }
+    Some(parsed)
+}
+
+/// Backfill the fields of a proposal stored before they were added, along with the weighted
+/// power and voter counts of its votes. Votes are backfilled within the budget, resuming after
+/// the last voter backfilled by a previous page. None if the budget ran out before all of them
+/// were backfilled
+fn migrate_legacy_proposal(
+    storage: &mut dyn Storage,
+    mut proposal: Proposal,
+    budget: &mut usize,
+) -> StdResult<Option<Proposal>> {
+    let proposal_key = U64Key::new(proposal.proposal_id);
+    let cursor = MIGRATION_VOTE_CURSORS.may_load(storage, proposal_key.clone())?;
+    if cursor.is_none() {
+        proposal.for_voter_count = 0;
+        proposal.against_voter_count = 0;
+    }
+
+    // One vote more than the budget tells whether any vote is left after this page
+    let start = cursor.map(|voter_address| Bound::exclusive(String::from(voter_address)));
+    let votes: StdResult<Vec<(Vec<u8>, ProposalVote)>> = PROPOSAL_VOTES
+        .prefix(proposal_key.clone())
+        .range(storage, start, None, Order::Ascending)
+        .take(*budget + 1)
+        .collect();
+    let mut votes = votes?;
+    let is_complete = votes.len() <= *budget;
+    votes.truncate(*budget);
+    *budget -= votes.len();
+
+    let mut last_voter_address = None;
+    for (k, mut vote) in votes {
+        match vote.option {
+            ProposalVoteOption::For => proposal.for_voter_count += 1,
+            ProposalVoteOption::Against => proposal.against_voter_count += 1,
+        }
+        vote.weighted_power = vote.power;
+        let voter_address = Addr::unchecked(String::from_utf8(k)?);
+        PROPOSAL_VOTES.save(storage, (proposal_key.clone(), &voter_address), &vote)?;
+        last_voter_address = Some(voter_address);
+    }
+
+    if !is_complete {
+        // The voter counts so far are kept with the proposal until its last vote is backfilled
+        if let Some(voter_address) = last_voter_address {
+            MIGRATION_VOTE_CURSORS.save(storage, proposal_key.clone(), &voter_address)?;
+        }
+        PROPOSALS.save(storage, proposal_key, &proposal)?;
+        return Ok(None);
+    }
+    MIGRATION_VOTE_CURSORS.remove(storage, proposal_key.clone());
+
+    // Proposals had no discussion period and measured voting power the block before submission
+    proposal.vote_start_height = proposal.start_height;
+    proposal.voting_power_snapshot_block = proposal.start_height.saturating_sub(1);
+    proposal.required_deposit_at_submission = proposal.deposit_amount;
+    // Deposits were refunded or slashed when proposals ended
+    proposal.deposit_status = match proposal.status {
+        ProposalStatus::Passed | ProposalStatus::Executed => DepositStatus::Refunded,
+        ProposalStatus::Rejected => DepositStatus::Slashed,
+        _ => DepositStatus::Locked,
+    };
+
+    PROPOSALS.save(storage, proposal_key, &proposal)?;
+
+    Ok(Some(proposal))
+}
+
+// QUERIES
+
+// Pagination defaults
+const PAGINATION_DEFAULT_LIMIT: u32 = 10;
+const PAGINATION_MAX_LIMIT: u32 = 30;
+// Proposals with more messages than this return them through ProposalMessages only
+const PROPOSAL_INFO_MAX_MESSAGES: usize = 10;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::ConfigDiff {} => to_binary(&query_config_diff(deps)?),
+        QueryMsg::ConfigHistory { start_after, limit } => {
+            to_binary(&query_config_history(deps, start_after, limit)?)
+        }
+        QueryMsg::TotalLockedDeposits {} => to_binary(&query_total_locked_deposits(deps)?),
+        QueryMsg::EscrowBalance {} => to_binary(&query_escrow_balance(deps, env)?),
+        QueryMsg::Stats {} => to_binary(&query_stats(deps)?),
+        QueryMsg::CurrentRequiredDeposit {} => {
+            to_binary(&query_current_required_deposit(deps, env)?)
+        }
+        QueryMsg::VoterPower { proposal_id, voter } => {
+            to_binary(&query_voter_power(deps, proposal_id, voter)?)
+        }
+        QueryMsg::PowerNeededToPass { proposal_id } => {
+            to_binary(&query_power_needed_to_pass(deps, proposal_id)?)
+        }
+        QueryMsg::Reservation { proposal_id } => to_binary(&query_reservation(deps, proposal_id)?),
+        QueryMsg::ResolvedAddresses {} => to_binary(&query_resolved_addresses(deps)?),
+        QueryMsg::Proposals {
+            start,
+            limit,
+            category,
+            include_archived,
+        } => to_binary(&query_proposals(
+            deps,
+            env,
+            start,
+            limit,
+            category,
+            include_archived.unwrap_or(false),
+        )?),
+        QueryMsg::Proposal {
+            proposal_id,
+            voter,
+            include_archived,
+        } => to_binary(&query_proposal(
+            deps,
+            env,
+            proposal_id,
+            voter,
+            include_archived.unwrap_or(false),
+        )?),
+        QueryMsg::ProposalHistory { proposal_id } => {
+            to_binary(&query_proposal_history(deps, proposal_id)?)
+        }
+        QueryMsg::ExecutionResults { proposal_id } => {
+            to_binary(&query_execution_results(deps, proposal_id)?)
+        }
+        QueryMsg::ProposalByHash { hash } => to_binary(&query_proposal_by_hash(deps, env, hash)?),
+        QueryMsg::ProposalMessages {
+            proposal_id,
+            start_after,
+            limit,
+        } => to_binary(&query_proposal_messages(
+            deps,
+            proposal_id,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::ProposalTimeRemaining { proposal_id } => {
+            to_binary(&query_proposal_time_remaining(deps, env, proposal_id)?)
+        }
+        QueryMsg::IsExecutable { proposal_id } => {
+            to_binary(&query_is_executable(deps, env, proposal_id)?)
+        }
+        QueryMsg::ExpiringExecutableProposals {
+            within_blocks,
+            limit,
+        } => to_binary(&query_expiring_executable_proposals(
+            deps,
+            env,
+            within_blocks,
+            limit,
+        )?),
+        QueryMsg::VotableProposalsFor { voter, limit } => {
+            to_binary(&query_votable_proposals_for(deps, env, voter, limit)?)
+        }
+        QueryMsg::ProposalTallies { ids } => to_binary(&query_proposal_tallies(deps, env, ids)?),
+        QueryMsg::ProposalVotes {
+            proposal_id,
+            start_after,
+            limit,
+            option,
+        } => to_binary(&query_proposal_votes(
+            deps,
+            proposal_id,
+            start_after,
+            limit,
+            option,
+        )?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    // Destructuring a struct’s fields into separate variables in order to force
+    // compile error if we add more params
+    let Config {
+        address_provider_address,
+        proposal_voting_period,
+        proposal_effective_delay,
+        proposal_expiration_period,
+        proposal_required_deposit,
+        proposal_required_quorum,
+        proposal_required_threshold,
+        submitter_cooldown,
+        emergency_owner,
+        discussion_period,
+        submissions_paused,
+        voting_paused,
+        title_max,
+        description_max,
+        link_max,
+        slashed_deposit_destination,
+        fast_track_targets,
+        reclaim_grace_period,
+        snapshot_offset,
+        end_proposal_reward,
+        quorum_basis,
+        quorum_excluded_addresses,
+        mint_per_proposal_cap,
+        mint_total_cap,
+        reservation_period,
+        allow_privileged_token_calls,
+        early_vote_bonus,
+        voting_token_override,
+        avg_block_time_seconds,
+        min_for_votes_absolute,
+        proposal_required_deposit_ratio,
+        min_vote_power,
+        dedupe_window,
+        executor_allowlist,
+        ica_controller_address,
+        surge_window,
+        surge_threshold,
+        surge_multiplier,
+        allowed_categories,
+        accepted_deposit_tokens,
+        refund_signaling_always,
+        auto_expire_grace,
+        require_call_simulation,
+    } = CONFIG.load(deps.storage)?;
+    let contract_version = get_contract_version(deps.storage)?.version;
+
+    // Resolved live so it follows the MARS token set in the address provider. The config stays
+    // queryable while the token cannot be resolved
+    let deposit_token_decimals = address_provider::helpers::query_address(
+        &deps.querier,
+        address_provider_address.clone(),
+        MarsContract::MarsToken,
+    )
+    .ok()
+    .and_then(|mars_token_address| cw20_get_info(&deps.querier, mars_token_address).ok())
+    .map(|token_info| token_info.decimals);
+
+    Ok(ConfigResponse {
+        contract_version,
+        address_provider_address,
+        proposal_voting_period,
+        proposal_effective_delay,
+        proposal_expiration_period,
+        proposal_required_deposit,
+        proposal_required_quorum,
+        proposal_required_threshold,
+        submitter_cooldown,
+        emergency_owner,
+        discussion_period,
+        submissions_paused,
+        voting_paused,
+        title_max,
+        description_max,
+        link_max,
+        slashed_deposit_destination,
+        fast_track_targets,
+        reclaim_grace_period,
+        snapshot_offset,
+        end_proposal_reward,
+        quorum_basis,
+        quorum_excluded_addresses,
+        mint_per_proposal_cap,
+        mint_total_cap,
+        reservation_period,
+        allow_privileged_token_calls,
+        early_vote_bonus,
+        voting_token_override,
+        avg_block_time_seconds,
+        min_for_votes_absolute,
+        proposal_required_deposit_ratio,
+        min_vote_power,
+        dedupe_window,
+        executor_allowlist,
+        ica_controller_address,
+        surge_window,
+        surge_threshold,
+        surge_multiplier,
+        allowed_categories,
+        accepted_deposit_tokens,
+        refund_signaling_always,
+        auto_expire_grace,
+        require_call_simulation,
+        deposit_token_decimals,
+    })
+}
+
+fn query_config_diff(deps: Deps) -> StdResult<ConfigDiffResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    Ok(ConfigDiffResponse {
+        fields: config.diff(&Config::recommended_defaults())?,
+    })
+}
+
+fn query_config_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    option_limit: Option<u32>,
+) -> StdResult<ConfigHistoryResponse> {
+    let limit = option_limit
+        .unwrap_or(PAGINATION_DEFAULT_LIMIT)
+        .min(PAGINATION_MAX_LIMIT) as usize;
+    let option_start = start_after.map(|height| Bound::exclusive(U64Key::new(height)));
+
+    // Pages end at a block boundary so `start_after` does not skip updates of the same block
+    let mut entries = vec![];
+    for item in CONFIG_HISTORY.range(deps.storage, option_start, None, Order::Ascending) {
+        if entries.len() >= limit {
+            break;
+        }
+        let (_k, block_entries) = item?;
+        entries.extend(block_entries);
+    }
+
+    Ok(ConfigHistoryResponse { entries })
+}
+
+fn query_proposals(
+    deps: Deps,
+    env: Env,
+    start_from: Option<u64>,
+    option_limit: Option<u32>,
+    option_category: Option<String>,
+    include_archived: bool,
+) -> StdResult<ProposalsListResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let global_state = GLOBAL_STATE.load(deps.storage)?;
+
+    let limit = option_limit
+        .unwrap_or(PAGINATION_DEFAULT_LIMIT)
+        .min(PAGINATION_MAX_LIMIT) as usize;
+
+    let range_proposals = |proposals: &Map<U64Key, Proposal>| -> StdResult<Vec<Proposal>> {
+        let option_start = start_from.map(|start| Bound::inclusive(U64Key::new(start)));
+        proposals
+            .range(deps.storage, option_start, None, Order::Ascending)
+            .map(|item| {
+                let (_k, v) = item?;
+                Ok(v)
+            })
+            .filter(
+                |proposal: &StdResult<Proposal>| match (&option_category, proposal) {
+                    (Some(category), Ok(proposal)) => proposal.category.as_ref() == Some(category),
+                    _ => true,
+                },
+            )
+            .take(limit)
+            .collect()
+    };
+
+    // Archived and non archived proposal ids do not overlap, so the first `limit` proposals of
+    // both maps hold the first `limit` proposals overall
+    let mut proposals_list = range_proposals(&PROPOSALS)?;
+    if include_archived {
+        proposals_list.extend(range_proposals(&ARCHIVED_PROPOSALS)?);
+        proposals_list.sort_by_key(|proposal| proposal.proposal_id);
+        proposals_list.truncate(limit);
+    }
+
+    for proposal in proposals_list.iter_mut() {
+        proposal.status = reported_status(&env, &config, proposal);
+    }
+
+    Ok(ProposalsListResponse {
+        proposal_count: global_state.proposal_count,
+        proposal_list: proposals_list,
+    })
+}
+
+fn query_proposal_history(deps: Deps, proposal_id: u64) -> StdResult<ProposalHistoryResponse> {
+    let history: StdResult<Vec<ProposalStatusChange>> = STATUS_HISTORY
+        .prefix(U64Key::new(proposal_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| Ok(item?.1))
+        .collect();
+
+    Ok(ProposalHistoryResponse {
+        proposal_id,
+        history: history?,
+    })
+}
+
+fn query_execution_results(deps: Deps, proposal_id: u64) -> StdResult<ExecutionResultsResponse> {
+    let results: StdResult<Vec<ExecutionResult>> = EXECUTION_RESULTS
+        .prefix(U64Key::new(proposal_id))
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| Ok(item?.1))
+        .collect();
+
+    Ok(ExecutionResultsResponse {
+        proposal_id,
+        results: results?,
+    })
+}
+
+fn query_proposal_by_hash(deps: Deps, env: Env, hash: Binary) -> StdResult<ProposalInfo> {
+    // The hash index keeps archived proposals, so they are looked up as well
+    let proposal_id = TITLE_HASH_INDEX.load(deps.storage, hash.as_slice())?;
+    query_proposal(deps, env, proposal_id, None, true)
+}
+
+fn query_proposal(
+    deps: Deps,
+    env: Env,
+    proposal_id: u64,
+    option_voter: Option<String>,
+    include_archived: bool,
+) -> StdResult<ProposalInfo> {
+    let proposal = match PROPOSALS.may_load(deps.storage, U64Key::new(proposal_id))? {
+        Some(proposal) => proposal,
+        None if include_archived => {
+            ARCHIVED_PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?
+        }
+        None => return Err(StdError::not_found("mars_core::council::Proposal")),
+    };
+
+    let caller_vote = match option_voter {
+        Some(voter) => {
+            let voter_address = deps.api.addr_validate(&voter)?;
+            PROPOSAL_VOTES
+                .may_load(deps.storage, (U64Key::new(proposal_id), &voter_address))?
+                .map(|vote| ProposalVoteResponse {
+                    voter_address: voter,
+                    option: vote.option,
+                    power: vote.power,
+                    weighted_power: vote.weighted_power,
+                })
+        }
+        None => None,
+    };
+
+    let config = CONFIG.load(deps.storage)?;
+    let status = reported_status(&env, &config, &proposal);
+    let voting_power_snapshot_block = proposal.voting_power_snapshot_block;
+    let message_count = proposal
+        .messages
+        .as_ref()
+        .map_or(0, |messages| messages.len());
+    let messages = if message_count > PROPOSAL_INFO_MAX_MESSAGES {
+        None
+    } else {
+        proposal.messages
+    };
+
+    Ok(ProposalInfo {
+        proposal_id: proposal.proposal_id,
+        proposal_id_str: proposal.proposal_id.to_string(),
+        submitter_address: proposal.submitter_address,
+        status,
+        for_votes: proposal.for_votes,
+        against_votes: proposal.against_votes,
+        for_voter_count: proposal.for_voter_count,
+        against_voter_count: proposal.against_voter_count,
+        start_height: proposal.start_height,
+        vote_start_height: proposal.vote_start_height,
+        end_height: proposal.end_height,
+        title: proposal.title,
+        description: proposal.description,
+        link: proposal.link,
+        messages,
+        message_count: message_count as u64,
+        deposit_amount: proposal.deposit_amount,
+        deposit_token: proposal.deposit_token,
+        required_deposit_at_submission: proposal.required_deposit_at_submission,
+        deposit_status: proposal.deposit_status,
+        co_sponsors: proposal.co_sponsors,
+        voting_power_snapshot_block,
+        final_quorum: proposal.final_quorum,
+        final_threshold: proposal.final_threshold,
+        final_total_voting_power: proposal.final_total_voting_power,
+        amended_at: proposal.amended_at,
+        auto_execute: proposal.auto_execute,
+        category: proposal.category,
+        min_quorum_power: proposal.min_quorum_power,
+        execution_deadline_blocks: proposal.execution_deadline_blocks,
+        implementation_note: proposal.implementation_note,
+        caller_vote,
+    })
+}
+
+fn query_proposal_messages(
+    deps: Deps,
+    proposal_id: u64,
+    start_after: Option<u64>,
+    option_limit: Option<u32>,
+) -> StdResult<ProposalMessagesResponse> {
+    let proposal = PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?;
+    let limit = option_limit
+        .unwrap_or(PAGINATION_DEFAULT_LIMIT)
+        .min(PAGINATION_MAX_LIMIT) as usize;
+    let start = start_after.map_or(0, |index| index as usize + 1);
+
+    let mut messages = proposal.messages.unwrap_or_default();
+    messages.sort_by_key(|message| message.execution_order);
+    let messages = messages.into_iter().skip(start).take(limit).collect();
+
+    Ok(ProposalMessagesResponse {
+        proposal_id,
+        messages,
+    })
+}
+
+fn query_proposal_votes(
+    deps: Deps,
+    proposal_id: u64,
+    start_after: Option<String>,
+    option_limit: Option<u32>,
+    option_filter: Option<ProposalVoteOption>,
+) -> StdResult<ProposalVotesResponse> {
+    let limit = option_limit
+        .unwrap_or(PAGINATION_DEFAULT_LIMIT)
+        .min(PAGINATION_MAX_LIMIT) as usize;
+    let option_start = start_after.map(Bound::exclusive);
+
+    let votes: StdResult<Vec<ProposalVoteResponse>> = PROPOSAL_VOTES
+        .prefix(U64Key::new(proposal_id))
+        .range(deps.storage, option_start, None, Order::Ascending)
+        .filter(|vote| match (vote, &option_filter) {
+            (Ok((_, v)), Some(option)) => v.option == *option,
+            _ => true,
+        })
+        .take(limit)
+        .map(|vote| {
+            let (k, v) = vote?;
+            let voter_address = String::from_utf8(k)?;
+
+            Ok(ProposalVoteResponse {
+                voter_address,
+                option: v.option,
+                power: v.power,
+                weighted_power: v.weighted_power,
+            })
+        })
+        .collect();
+
+    Ok(ProposalVotesResponse {
+        proposal_id,
+        votes: votes?,
+    })
+}
+
+fn query_total_locked_deposits(deps: Deps) -> StdResult<TotalLockedDepositsResponse> {
+    let global_state = GLOBAL_STATE.load(deps.storage)?;
+    Ok(TotalLockedDepositsResponse {
+        amount: global_state.locked_deposits,
+    })
+}
+
+fn query_escrow_balance(deps: Deps, env: Env) -> StdResult<EscrowBalanceResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let global_state = GLOBAL_STATE.load(deps.storage)?;
+    let mars_token_address = address_provider::helpers::query_address(
+        &deps.querier,
+        config.address_provider_address,
+        MarsContract::MarsToken,
+    )?;
+
+    Ok(EscrowBalanceResponse {
+        balance: cw20_get_balance(&deps.querier, mars_token_address, env.contract.address)?,
+        locked_deposits: global_state.locked_deposits,
+    })
+}
+
+fn query_current_required_deposit(
+    deps: Deps,
+    env: Env,
+) -> StdResult<CurrentRequiredDepositResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mars_token_address = address_provider::helpers::query_address(
+        &deps.querier,
+        config.address_provider_address.clone(),
+        MarsContract::MarsToken,
+    )?;
+
+    Ok(CurrentRequiredDepositResponse {
+        amount: get_required_deposit(deps, &env, &config, mars_token_address.clone())?,
+        token_address: mars_token_address,
+    })
+}
+
+fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let global_state = GLOBAL_STATE.load(deps.storage)?;
+    let status_count = |status: ProposalStatus| -> StdResult<u64> {
+        Ok(PROPOSAL_COUNT_BY_STATUS
+            .may_load(deps.storage, &status.to_string())?
+            .unwrap_or(0))
+    };
+
+    Ok(StatsResponse {
+        proposal_count: global_state.proposal_count,
+        active_proposal_count: status_count(ProposalStatus::Active)?,
+        passed_proposal_count: status_count(ProposalStatus::Passed)?,
+        rejected_proposal_count: status_count(ProposalStatus::Rejected)?,
+        executed_proposal_count: status_count(ProposalStatus::Executed)?,
+        cancelled_proposal_count: status_count(ProposalStatus::Cancelled)?,
+        implemented_proposal_count: status_count(ProposalStatus::Implemented)?,
+        locked_deposits: global_state.locked_deposits,
+        proposal_required_quorum: config.proposal_required_quorum,
+        proposal_required_threshold: config.proposal_required_threshold,
+    })
+}
+
+fn query_proposal_time_remaining(
+    deps: Deps,
+    env: Env,
+    proposal_id: u64,
+) -> StdResult<ProposalTimeRemainingResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let proposal = PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?;
+
+    let blocks_remaining = if proposal.status == ProposalStatus::Active {
+        proposal.end_height.saturating_sub(env.block.height)
+    } else {
+        0
+    };
+
+    Ok(ProposalTimeRemainingResponse {
+        proposal_id,
+        blocks_remaining,
+        estimated_seconds_remaining: blocks_remaining.saturating_mul(config.avg_block_time_seconds),
+    })
+}
+
+fn query_is_executable(deps: Deps, env: Env, proposal_id: u64) -> StdResult<IsExecutableResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let proposal = PROPOSALS.load(deps.storage, U64Key::new(proposal_id))?;
+    let reason = proposal_executable_reason(&env, &config, &proposal)?;
+
+    Ok(IsExecutableResponse {
+        proposal_id,
+        is_executable: reason == ProposalExecutableReason::Ready,
+        reason,
+    })
+}
+
+/// Proposals are not indexed by expiration, so all of them are scanned and the executable ones
+/// are sorted by expiration height
+fn query_expiring_executable_proposals(
+    deps: Deps,
+    env: Env,
+    within_blocks: u64,
+    option_limit: Option<u32>,
+) -> StdResult<ExpiringExecutableProposalsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = option_limit
+        .unwrap_or(PAGINATION_DEFAULT_LIMIT)
+        .min(PAGINATION_MAX_LIMIT) as usize;
+
+    let mut proposals = vec![];
+    for item in PROPOSALS.range(deps.storage, None, None, Order::Ascending) {
+        let (_k, proposal) = item?;
+        if proposal_executable_reason(&env, &config, &proposal)? != ProposalExecutableReason::Ready
+        {
+            continue;
+        }
+
+        let expiration_height = proposal_expiration_height(&config, &proposal)?;
+        if expiration_height - env.block.height <= within_blocks {
+            proposals.push(ExpiringExecutableProposal {
+                proposal_id: proposal.proposal_id,
+                expiration_height,
+            });
+        }
+    }
+
+    proposals.sort_by_key(|proposal| (proposal.expiration_height, proposal.proposal_id));
+    proposals.truncate(limit);
+
+    Ok(ExpiringExecutableProposalsResponse { proposals })
+}
+
+fn query_votable_proposals_for(
+    deps: Deps,
+    env: Env,
+    voter: String,
+    option_limit: Option<u32>,
+) -> StdResult<ProposalsListResponse> {
+    let voter_address = deps.api.addr_validate(&voter)?;
+    let global_state = GLOBAL_STATE.load(deps.storage)?;
+    let limit = option_limit
+        .unwrap_or(PAGINATION_DEFAULT_LIMIT)
+        .min(PAGINATION_MAX_LIMIT) as usize;
+
+    let mut proposals = vec![];
+    for item in PROPOSALS.range(deps.storage, None, None, Order::Ascending) {
+        if proposals.len() == limit {
+            break;
+        }
+
+        let (_k, proposal) = item?;
+        let is_open = proposal.status == ProposalStatus::Active
+            && env.block.height >= proposal.vote_start_height
+            && env.block.height <= proposal.end_height;
+        if !is_open {
+            continue;
+        }
+
+        let has_voted = PROPOSAL_VOTES
+            .may_load(
+                deps.storage,
+                (U64Key::new(proposal.proposal_id), &voter_address),
+            )?
+            .is_some();
+        if !has_voted {
+            proposals.push(proposal);
+        }
+    }
+
+    Ok(ProposalsListResponse {
+        proposal_count: global_state.proposal_count,
+        proposal_list: proposals,
+    })
+}
+
+fn query_proposal_tallies(
+    deps: Deps,
+    env: Env,
+    ids: Vec<u64>,
+) -> StdResult<ProposalTalliesResponse> {
+    if ids.len() > PAGINATION_MAX_LIMIT as usize {
+        return Err(StdError::generic_err(format!(
+            "Cannot query more than {} proposals",
+            PAGINATION_MAX_LIMIT
+        )));
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut tallies = vec![];
+    for proposal_id in ids {
+        let option_proposal = match PROPOSALS.may_load(deps.storage, U64Key::new(proposal_id))? {
+            Some(proposal) => Some(proposal),
+            None => ARCHIVED_PROPOSALS.may_load(deps.storage, U64Key::new(proposal_id))?,
+        };
+        if let Some(proposal) = option_proposal {
+            tallies.push(ProposalTally {
+                proposal_id,
+                for_votes: proposal.for_votes,
+                against_votes: proposal.against_votes,
+                status: reported_status(&env, &config, &proposal),
+            });
+        }
+    }
+
+    Ok(ProposalTalliesResponse { tallies })
+}
+
+fn query_reservation(deps: Deps, proposal_id: u64) -> StdResult<ProposalReservation> {
+    RESERVATIONS.load(deps.storage, U64Key::new(proposal_id))
+}
+
+fn query_resolved_addresses(deps: Deps) -> StdResult<ResolvedAddressesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut addresses_query = address_provider::helpers::query_addresses(
+        &deps.querier,
+        config.address_provider_address,
+        vec![
+            MarsContract::MarsToken,
+            MarsContract::XMarsToken,
+            MarsContract::Staking,
+        ],
+    )?;
+    let staking_address = addresses_query.pop().unwrap();
+    let xmars_token_address = addresses_query.pop().unwrap();
+    let mars_token_address = addresses_query.pop().unwrap();
+
+    Ok(ResolvedAddressesResponse {
+        mars_token_address,
+        xmars_token_address,
+        staking_address,
+    })
+}
+
+fn query_voter_power(deps: Deps, proposal_id: u64, voter: String) -> StdResult<VoterPowerResponse> {
+    let proposal = PROPOSALS
+        .may_load(deps.storage, U64Key::new(proposal_id))?
+        .ok_or_else(|| StdError::not_found(format!("Proposal {}", proposal_id)))?;
+    let voter_address = deps.api.addr_validate(&voter)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let voting_power_snapshot_block = proposal.voting_power_snapshot_block;
+    let voting_power = get_voting_power_at(
+        &deps.querier,
+        &config,
+        voter_address.clone(),
+        voting_power_snapshot_block,
+    )?;
+
+    Ok(VoterPowerResponse {
+        proposal_id,
+        voter_address,
+        voting_power_snapshot_block,
+        voting_power,
+    })
+}
+
+fn query_power_needed_to_pass(
+    deps: Deps,
+    proposal_id: u64,
+) -> StdResult<PowerNeededToPassResponse> {
+    let proposal = PROPOSALS
+        .may_load(deps.storage, U64Key::new(proposal_id))?
+        .ok_or_else(|| StdError::not_found(format!("Proposal {}", proposal_id)))?;
+    if proposal.status != ProposalStatus::Active {
+        return Err(StdError::generic_err("Proposal is not active"));
+    }
+    let config = CONFIG.load(deps.storage)?;
+
+    let total_voting_power =
+        get_total_voting_power_at(&deps.querier, &config, proposal.voting_power_snapshot_block)?
+            .ok_or_else(|| {
+                StdError::generic_err(
+                    ContractError::SnapshotUnavailable {
+                        block: proposal.voting_power_snapshot_block,
+                    }
+                    .to_string(),
+                )
+            })?;
+    if proposal_result(&config, &proposal, total_voting_power).passed {
+        return Ok(PowerNeededToPassResponse {
+            proposal_id,
+            total_voting_power,
+            power_needed: Uint128::zero(),
+        });
+    }
+
+    // Quorum: total votes without the early vote bonus / total voting power >= required quorum
+    let quorum_total_votes = Decimal::multiply_uint128_by_decimal_and_ceil(
+        total_voting_power,
+        config.proposal_required_quorum,
+    )?
+    .max(proposal.min_quorum_power.unwrap_or_else(Uint128::zero));
+    let quorum_power_needed = quorum_total_votes.saturating_sub(proposal_voted_power(&proposal));
+
+    // Threshold: for votes / total votes > required threshold, both including the early vote
+    // bonus. As the ratio is truncated to the
+    // decimal precision, this means for votes * (1 - t) >= t * against votes, with t being the
+    // required threshold plus the smallest decimal step
+    let threshold_numerator =
+        Uint256::from(config.proposal_required_threshold.numerator()) + Uint256::from(1_u32);
+    let threshold_denominator = Uint256::from(config.proposal_required_threshold.denominator())
+        .checked_sub(threshold_numerator)
+        .unwrap_or_else(|_| Uint256::zero());
+    if threshold_denominator.is_zero() {
+        return Err(StdError::generic_err(
+            "Proposal cannot pass with the configured threshold",
+        ));
+    }
+    let threshold_product = threshold_numerator * Uint256::from(proposal.against_votes);
+    let mut threshold_for_votes = threshold_product / threshold_denominator;
+    if !threshold_product
+        .checked_rem(threshold_denominator)?
+        .is_zero()
+    {
+        threshold_for_votes += Uint256::from(1_u32);
+    }
+    // Ties fail, so for votes also need to be above against votes (which covers a proposal
+    // without votes needing at least one For vote)
+    let threshold_for_votes: Uint128 = threshold_for_votes
+        .max(Uint256::from(proposal.against_votes) + Uint256::from(1_u32))
+        .try_into()?;
+    let threshold_power_needed = threshold_for_votes.saturating_sub(proposal.for_votes);
+
+    let absolute_power_needed = config
+        .min_for_votes_absolute
+        .saturating_sub(proposal.for_votes);
+
+    Ok(PowerNeededToPassResponse {
+        proposal_id,
+        total_voting_power,
+        power_needed: quorum_power_needed
+            .max(threshold_power_needed)
+            .max(absolute_power_needed),
+    })
+}
+
+// HELPERS
+
+/// Whether a proposal can be executed at the current block, or the reason it cannot. Shared by
+/// ExecuteProposal and the IsExecutable query so both always agree
+fn proposal_executable_reason(
+    env: &Env,
+    config: &Config,
+    proposal: &Proposal,
+) -> Result<ProposalExecutableReason, OverflowError> {
+    if proposal.status != ProposalStatus::Passed {
+        return Ok(ProposalExecutableReason::NotPassed);
+    }
+
+    let effective_delay = if is_fast_track_proposal(config, proposal) {
+        0
+    } else {
+        config.proposal_effective_delay
+    };
+    if env.block.height < height_checked_add(proposal.end_height, effective_delay)? {
+        return Ok(ProposalExecutableReason::DelayNotEnded);
+    }
+
+    if env.block.height > proposal_expiration_height(config, proposal)? {
+        return Ok(ProposalExecutableReason::Expired);
+    }
+
+    Ok(ProposalExecutableReason::Ready)
+}
+
+/// Quorum and threshold reached by a proposal's votes and whether they are enough to pass
+struct ProposalResult {
+    quorum: Decimal,
+    threshold: Decimal,
+    passed: bool,
+}
+
+/// Compute the proposal result from its current votes. Shared by EndProposal and the
+/// PowerNeededToPass query so both always agree
+fn proposal_result(
+    config: &Config,
+    proposal: &Proposal,
+    total_voting_power: Uint128,
+) -> ProposalResult {
+    let for_votes = proposal.for_votes;
+    let against_votes = proposal.against_votes;
+    let total_votes = for_votes + against_votes;
+    let voted_power = proposal_voted_power(proposal);
+
+    // The early vote bonus only weighs votes against each other, so the quorum is measured on
+    // the voting power without it
+    let mut quorum = Decimal::zero();
+    let mut threshold = Decimal::zero();
+    if total_voting_power > Uint128::zero() {
+        quorum = Decimal::from_ratio(voted_power, total_voting_power);
+    }
+    if total_votes > Uint128::zero() {
+        threshold = Decimal::from_ratio(for_votes, total_votes);
+    }
+
+    let passed = if for_votes == against_votes {
+        // Ties always fail. This does not rely on how a 50% threshold compares to the configured
+        // one after decimal truncation
+        false
+    } else {
+        // Proposal passes if quorum and threshold are met
+        quorum >= config.proposal_required_quorum
+            && threshold > config.proposal_required_threshold
+            && for_votes >= config.min_for_votes_absolute
+            && voted_power >= proposal.min_quorum_power.unwrap_or_else(Uint128::zero)
+    };
+
+    ProposalResult {
+        quorum,
+        threshold,
+        passed,
+    }
+}
+
+/// Voting power cast on a proposal, without the early vote bonus
+fn proposal_voted_power(proposal: &Proposal) -> Uint128 {
+    (proposal.for_votes + proposal.against_votes).saturating_sub(proposal.early_vote_bonus_votes)
+}
+
+/// Last block at which a passed proposal can be executed. Fast track proposals can be executed
+/// earlier but expire at the same height as any other proposal. A proposal execution deadline
+/// shorter than the config expiration period takes precedence
+fn proposal_expiration_height(config: &Config, proposal: &Proposal) -> Result<u64, OverflowError> {
+    let expiration_period = proposal
+        .execution_deadline_blocks
+        .map_or(config.proposal_expiration_period, |deadline_blocks| {
+            deadline_blocks.min(config.proposal_expiration_period)
+        });
+    height_checked_add(
+        height_checked_add(proposal.end_height, config.proposal_effective_delay)?,
+        expiration_period,
+    )
+}
+
+/// A proposal can skip the effective delay if it has messages and all of them are calls to fast
+/// track targets
+fn is_fast_track_proposal(config: &Config, proposal: &Proposal) -> bool {
+    are_fast_track_messages(config, &proposal.messages)
+}
+
+/// Whether there are messages and all of them execute fast track targets on this chain. A
+/// target address on another chain is not the same contract
+fn are_fast_track_messages(
+    config: &Config,
+    option_messages: &Option<Vec<ProposalMessage>>,
+) -> bool {
+    match option_messages {
+        Some(messages) if !messages.is_empty() => messages.iter().all(|message| {
+            message.route == ExecuteRoute::Local
+                && matches!(
+                    &message.msg,
+                    CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. })
+                        if config.fast_track_targets.iter().any(|target| target == contract_addr)
+                )
+        }),
+        _ => false,
+    }
+}
+
+fn is_proposal_indexed(storage: &dyn Storage, proposal: &Proposal) -> bool {
+    PROPOSALS_BY_SUBMITTER.has(
+        storage,
+        (
+            &proposal.submitter_address,
+            U64Key::new(proposal.proposal_id),
+        ),
+    )
+}
+
+/// Add proposal to the submitter and title hash indexes and to the count of its status
+fn index_proposal(storage: &mut dyn Storage, proposal: &Proposal) -> StdResult<()> {
+    PROPOSALS_BY_SUBMITTER.save(
+        storage,
+        (
+            &proposal.submitter_address,
+            U64Key::new(proposal.proposal_id),
+        ),
+        &Empty {},
+    )?;
+    // A duplicate hash keeps pointing to the first proposal
+    let title_hash = proposal_title_hash(proposal);
+    if !TITLE_HASH_INDEX.has(storage, &title_hash) {
+        TITLE_HASH_INDEX.save(storage, &title_hash, &proposal.proposal_id)?;
+    }
+    PROPOSAL_COUNT_BY_STATUS.update(
+        storage,
+        &proposal.status.to_string(),
+        |count| -> StdResult<_> { Ok(count.unwrap_or(0) + 1) },
+    )?;
+    PROPOSAL_COUNT_BY_START_HEIGHT.update(
+        storage,
+        U64Key::new(proposal.start_height),
+        |count| -> StdResult<_> { Ok(count.unwrap_or(0) + 1) },
+    )?;
+    Ok(())
+}
+
+/// sha256 of the proposal title, submitter address and start height (as big endian bytes)
+fn proposal_title_hash(proposal: &Proposal) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(proposal.title.as_bytes());
+    hasher.update(proposal.submitter_address.as_bytes());
+    hasher.update(proposal.start_height.to_be_bytes());
+    hasher.finalize().to_vec()
+}
+
+/// Set the new status on the proposal and move it between status counts. Proposals not indexed
+/// yet are counted with their current status once the migration reaches them
+fn set_proposal_status(
+    storage: &mut dyn Storage,
+    env: &Env,
+    proposal: &mut Proposal,
+    new_status: ProposalStatus,
+) -> StdResult<()> {
+    record_status_change(storage, env, proposal.proposal_id, new_status.clone())?;
+    if is_proposal_indexed(storage, proposal) {
+        PROPOSAL_COUNT_BY_STATUS.update(
+            storage,
+            &proposal.status.to_string(),
+            |count| -> StdResult<_> { Ok(count.unwrap_or(0).saturating_sub(1)) },
+        )?;
+        PROPOSAL_COUNT_BY_STATUS.update(
+            storage,
+            &new_status.to_string(),
+            |count| -> StdResult<_> { Ok(count.unwrap_or(0) + 1) },
+        )?;
+    }
+    proposal.status = new_status;
+    Ok(())
+}
+
+/// Append a status change to the proposal history, up to MAX_STATUS_HISTORY_ENTRIES
+fn record_status_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    proposal_id: u64,
+    status: ProposalStatus,
+) -> StdResult<()> {
+    let entry_count = STATUS_HISTORY
+        .prefix(U64Key::new(proposal_id))
+        .keys(storage, None, None, Order::Ascending)
+        .count() as u64;
+    if entry_count >= MAX_STATUS_HISTORY_ENTRIES {
+        return Ok(());
+    }
+
+    STATUS_HISTORY.save(
+        storage,
+        (U64Key::new(proposal_id), U64Key::new(entry_count)),
+        &ProposalStatusChange {
+            status,
+            height: env.block.height,
+        },
+    )
+}
+
+fn validate_slashed_deposit_destination(
+    api: &dyn Api,
+    destination: SlashedDepositDestination,
+) -> StdResult<SlashedDepositDestination> {
+    match destination {
+        SlashedDepositDestination::Treasury(address) => Ok(SlashedDepositDestination::Treasury(
+            api.addr_validate(address.as_str())?,
+        )),
+        destination => Ok(destination),
+    }
+}
+
+/// Proposal messages targeting the council itself can only be governance actions. Anything else
+/// (submitting proposals, voting) could be used to create proposal loops
+fn validate_council_self_call(env: &Env, msg: &CosmosMsg) -> Result<(), ContractError> {
+    if let CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr, msg, ..
+    }) = msg
+    {
+        if *contract_addr == env.contract.address {
+            let is_governance_action = matches!(
+                from_binary::<ExecuteMsg>(msg),
+                Ok(ExecuteMsg::UpdateConfig { .. })
+                    | Ok(ExecuteMsg::EmergencySetPaused { .. })
+                    | Ok(ExecuteMsg::TransferGuardianship { .. })
+                    | Ok(ExecuteMsg::RescueTokens { .. })
+                    | Ok(ExecuteMsg::MintTreasury { .. })
+                    | Ok(ExecuteMsg::ForceCancelProposal { .. })
+                    | Ok(ExecuteMsg::ArchiveProposals { .. })
+                    | Ok(ExecuteMsg::RotateGovernanceTokens { .. })
+                    | Ok(ExecuteMsg::MarkImplemented { .. })
+            );
+            if !is_governance_action {
+                return Err(ContractError::invalid_proposal(
+                    "messages to the council can only be governance actions",
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Best effort check that wasm message payloads decode as JSON so an encoding mistake is caught
+/// on submission instead of failing on execution after the vote. The payload contents are not
+/// checked against the target contract's messages
+fn validate_wasm_msg_payload(msg: &CosmosMsg) -> Result<(), ContractError> {
+    let payload = match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. })
+        | CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. })
+        | CosmosMsg::Wasm(WasmMsg::Migrate { msg, .. }) => msg,
+        _ => return Ok(()),
+    };
+
+    if from_binary::<IgnoredAny>(payload).is_err() {
+        return Err(ContractError::invalid_proposal(
+            "wasm message payload is not valid JSON",
+        ));
+    }
+
+    Ok(())
+}
+
+/// A new address provider has to resolve the contracts the council depends on, otherwise
+/// proposals could not be submitted, voted or ended
+fn validate_address_provider(
+    querier: &QuerierWrapper,
+    address_provider_address: &Addr,
+) -> Result<(), ContractError> {
+    address_provider::helpers::query_addresses(
+        querier,
+        address_provider_address.clone(),
+        vec![
+            MarsContract::MarsToken,
+            MarsContract::XMarsToken,
+            MarsContract::Staking,
+        ],
+    )?;
+    Ok(())
+}
+
+/// Validate the length of the proposal title, description and link
+fn validate_proposal_text(
+    config: &Config,
+    title: &str,
+    description: &str,
+    option_link: &Option<String>,
+) -> Result<(), ContractError> {
+    // Validate title
+    let title_length = title.len() as u64;
+    if title_length < MIN_TITLE_LENGTH {
+        return Err(ContractError::TitleTooShort {
+            min: MIN_TITLE_LENGTH,
+        });
+    }
+    if title_length > config.title_max {
+        return Err(ContractError::TitleTooLong {
+            max: config.title_max,
+        });
+    }
+
+    // Validate description
+    let description_length = description.len() as u64;
+    if description_length < MIN_DESC_LENGTH {
+        return Err(ContractError::DescriptionTooShort {
+            min: MIN_DESC_LENGTH,
+        });
+    }
+    if description_length > config.description_max {
+        return Err(ContractError::DescriptionTooLong {
+            max: config.description_max,
+        });
+    }
+
+    // Validate Link
+    if let Some(link) = option_link {
+        let link_length = link.len() as u64;
+        if link_length < MIN_LINK_LENGTH {
+            return Err(ContractError::LinkTooShort {
+                min: MIN_LINK_LENGTH,
+            });
+        }
+        if link_length > config.link_max {
+            return Err(ContractError::LinkTooLong {
+                max: config.link_max,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate title, description, link and messages of a proposal
+fn validate_proposal_content(
+    env: &Env,
+    config: &Config,
+    mars_token_address: &Addr,
+    title: &str,
+    description: &str,
+    option_link: &Option<String>,
+    option_messages: &Option<Vec<ProposalMessage>>,
+) -> Result<(), ContractError> {
+    validate_proposal_text(config, title, description, option_link)?;
+
+    // Validate messages calling back into the council
+    if let Some(messages) = option_messages {
+        if messages.is_empty() {
+            return Err(ContractError::invalid_proposal(
+                "messages cannot be empty; omit for signaling",
+            ));
+        }
+        for message in messages {
+            if let Some(memo) = &message.memo {
+                if memo.len() as u64 > MAX_MEMO_LENGTH {
+                    return Err(ContractError::MemoTooLong {
+                        max: MAX_MEMO_LENGTH,
+                    });
+                }
+            }
+            if let ExecuteRoute::Ica { .. } = message.route {
+                if config.ica_controller_address.is_none() {
+                    return Err(ContractError::IcaRouteNotSupported {});
+                }
+            }
+            validate_wasm_msg_payload(&message.msg)?;
+            validate_council_self_call(env, &message.msg)?;
+            if !config.allow_privileged_token_calls {
+                validate_mars_token_call(mars_token_address, &message.msg)?;
+            }
+        }
+
+        if proposal_mint_treasury_amount(env, messages)? > config.mint_per_proposal_cap {
+            return Err(ContractError::MintTreasuryProposalCapExceeded {
+                cap: config.mint_per_proposal_cap,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate the contracts executed by local proposal messages exist, probing each of them with an
+/// empty smart query. Any answer from the contract, including an error because it does not
+/// support the query, passes the probe. Only addresses without a contract fail it
+fn validate_call_targets(
+    querier: &QuerierWrapper,
+    option_messages: &Option<Vec<ProposalMessage>>,
+) -> Result<(), ContractError> {
+    for message in option_messages.iter().flatten() {
+        if !matches!(message.route, ExecuteRoute::Local) {
+            continue;
+        }
+        if let CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) = &message.msg {
+            let probe = QueryRequest::<Empty>::Wasm(WasmQuery::Smart {
+                contract_addr: contract_addr.clone(),
+                msg: to_binary(&Empty {})?,
+            });
+            if let SystemResult::Err(SystemError::NoSuchContract { .. }) =
+                querier.raw_query(&to_vec(&probe)?)
+            {
+                return Err(ContractError::invalid_proposal(format!(
+                    "message target {} is not a reachable contract",
+                    contract_addr
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Validate the deposit sent with a submission and the submitter cooldown, recording the
+/// submission height. Returns the submitter address, the deposit token and the required deposit
+fn validate_submission(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    config: &Config,
+    mars_token_address: &Addr,
+    submitter_address_unchecked: &str,
+    deposit_amount: Uint128,
+) -> Result<(Addr, Option<Addr>, Uint128), ContractError> {
+    let (deposit_token, required_deposit) =
+        deposit_requirement(deps.as_ref(), env, config, mars_token_address, &info.sender)?;
+    if deposit_amount < required_deposit {
+        return Err(ContractError::InsufficientDeposit {
+            required: required_deposit,
+        });
+    }
+
+    let submitter_address = validate_submitter(deps, env, config, submitter_address_unchecked)?;
+
+    Ok((submitter_address, deposit_token, required_deposit))
+}
+
+/// Deposit token (None for MARS) and required deposit for deposits made in the given token.
+/// Deposits in MARS need the required deposit, deposits in other accepted tokens the minimum
+/// configured for them
+fn deposit_requirement(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    mars_token_address: &Addr,
+    token_address: &Addr,
+) -> Result<(Option<Addr>, Uint128), ContractError> {
+    let mars_required_deposit =
+        || get_required_deposit(deps, env, config, mars_token_address.clone());
+    if token_address == mars_token_address {
+        Ok((None, mars_required_deposit()?))
+    } else if let Some((token, minimum)) = config
+        .accepted_deposit_tokens
+        .iter()
+        .find(|(token, _)| token == token_address)
+    {
+        Ok((Some(token.clone()), *minimum))
+    } else {
+        Err(ContractError::InsufficientDeposit {
+            required: mars_required_deposit()?,
+        })
+    }
+}
+
+/// Validate the submitter is not within its cooldown period and record the submission
+fn validate_submitter(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    submitter_address_unchecked: &str,
+) -> Result<Addr, ContractError> {
+    let submitter_address = deps.api.addr_validate(submitter_address_unchecked)?;
+    if config.submitter_cooldown > 0 {
+        if let Some(last_submission_height) =
+            LAST_SUBMISSION.may_load(deps.storage, &submitter_address)?
+        {
+            let next_submission_height =
+                height_checked_add(last_submission_height, config.submitter_cooldown)?;
+            if env.block.height < next_submission_height {
+                return Err(ContractError::SubmitProposalCooldownNotEnded {
+                    next_submission_height,
+                });
+            }
+        }
+    }
+    LAST_SUBMISSION.save(deps.storage, &submitter_address, &env.block.height)?;
+
+    Ok(submitter_address)
+}
+
+/// Reject a proposal with the same title as an active proposal the submitter created within the
+/// dedupe window
+fn validate_not_duplicate(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    submitter_address: &Addr,
+    title: &str,
+) -> Result<(), ContractError> {
+    if config.dedupe_window == 0 {
+        return Ok(());
+    }
+
+    let window_start = env.block.height.saturating_sub(config.dedupe_window);
+    let submitter_proposal_keys = PROPOSALS_BY_SUBMITTER.prefix(submitter_address).keys(
+        deps.storage,
+        None,
+        None,
+        Order::Descending,
+    );
+    for key in submitter_proposal_keys {
+        let proposal_id = u64::from_be_bytes(
+            key.as_slice()
+                .try_into()
+                .map_err(|_| StdError::generic_err("Invalid proposal id key"))?,
+        );
+        // Archived proposals are finalized, so they cannot be duplicates
+        let proposal = match PROPOSALS.may_load(deps.storage, U64Key::new(proposal_id))? {
+            Some(proposal) => proposal,
+            None => continue,
+        };
+        if proposal.status == ProposalStatus::Active
+            && proposal.start_height >= window_start
+            && proposal.title == title
+        {
+            return Err(ContractError::DuplicateProposal { proposal_id });
+        }
+    }
+
+    Ok(())
+}
+
+/// Active proposal starting at the current block, without content (title, description, link
+/// and messages)
+fn new_active_proposal(
+    env: &Env,
+    config: &Config,
+    proposal_id: u64,
+    submitter_address: Addr,
+    deposit_amount: Uint128,
+    deposit_token: Option<Addr>,
+    required_deposit: Uint128,
+) -> Result<Proposal, OverflowError> {
+    // Voting opens after the discussion period, and voting power is measured relative to that
+    // block instead of the submission one
+    let vote_start_height = height_checked_add(env.block.height, config.discussion_period)?;
+
+    Ok(Proposal {
+        proposal_id,
+        submitter_address,
+        status: ProposalStatus::Active,
+        for_votes: Uint128::zero(),
+        against_votes: Uint128::zero(),
+        for_voter_count: 0,
+        against_voter_count: 0,
+        early_vote_bonus_votes: Uint128::zero(),
+        start_height: env.block.height,
+        vote_start_height,
+        end_height: height_checked_add(vote_start_height, config.proposal_voting_period)?,
+        voting_power_snapshot_block: vote_start_height.saturating_sub(config.snapshot_offset),
+        title: String::new(),
+        description: String::new(),
+        link: None,
+        messages: None,
+        deposit_amount,
+        deposit_token,
+        required_deposit_at_submission: required_deposit,
+        deposit_status: DepositStatus::Locked,
+        co_sponsors: vec![],
+        final_quorum: None,
+        final_threshold: None,
+        final_total_voting_power: None,
+        amended_at: None,
+        auto_execute: false,
+        category: None,
+        min_quorum_power: None,
+        execution_deadline_blocks: None,
+        implementation_note: None,
+    })
+}
+
+/// Message to the MARS token slashing a deposit according to the configured destination
+fn slashed_deposit_msg(
+    destination: SlashedDepositDestination,
+    staking_address: Addr,
+    amount: Uint128,
+) -> Cw20ExecuteMsg {
+    match destination {
+        SlashedDepositDestination::Staking => Cw20ExecuteMsg::Transfer {
+            recipient: staking_address.into(),
+            amount,
+        },
+        SlashedDepositDestination::Burn => Cw20ExecuteMsg::Burn { amount },
+        SlashedDepositDestination::Treasury(treasury_address) => Cw20ExecuteMsg::Transfer {
+            recipient: treasury_address.into(),
+            amount,
+        },
+    }
+}
+
+/// Total MARS the proposal messages mint through MintTreasury
+fn proposal_mint_treasury_amount(
+    env: &Env,
+    messages: &[ProposalMessage],
+) -> Result<Uint128, ContractError> {
+    let mut total = Uint128::zero();
+    for message in messages {
+        if let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &message.msg
+        {
+            if *contract_addr == env.contract.address {
+                if let Ok(ExecuteMsg::MintTreasury { amount, .. }) = from_binary(msg) {
+                    total = total.checked_add(amount)?;
+                }
+            }
+        }
+    }
+    Ok(total)
+}
+
+/// Add a number of blocks to a height, returning an error instead of overflowing (config allows
+/// arbitrarily large periods)
+fn height_checked_add(height: u64, blocks: u64) -> Result<u64, OverflowError> {
+    height
+        .checked_add(blocks)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Add, height, blocks))
+}
+
+/// Proposal count after taking one more proposal id
+fn proposal_count_checked_add(proposal_count: u64) -> Result<u64, OverflowError> {
+    proposal_count
+        .checked_add(1)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Add, proposal_count, 1))
+}
+
+/// Status reported by queries: Active proposals nobody ended within auto_expire_grace blocks
+/// after their voting period are reported as Expired
+fn reported_status(env: &Env, config: &Config, proposal: &Proposal) -> ProposalStatus {
+    let is_expired = proposal.status == ProposalStatus::Active
+        && config.auto_expire_grace > 0
+        && env.block.height > proposal.end_height.saturating_add(config.auto_expire_grace);
+    if is_expired {
+        ProposalStatus::Expired
+    } else {
+        proposal.status.clone()
+    }
+}
+
+/// Whether the deposit of an ended proposal is slashed: it is if the proposal was cancelled, or if
+/// it was rejected unless it is a signaling proposal and those are always refunded
+fn is_deposit_slashed(config: &Config, proposal: &Proposal) -> bool {
+    let is_signaling = proposal.messages.is_none();
+    match proposal.status {
+        ProposalStatus::Cancelled => true,
+        ProposalStatus::Rejected => !(is_signaling && config.refund_signaling_always),
+        _ => false,
+    }
+}
+
+/// Status of the deposit of an ended proposal once its transfer went through
+fn settled_deposit_status(config: &Config, proposal: &Proposal) -> DepositStatus {
+    if is_deposit_slashed(config, proposal) {
+        DepositStatus::Slashed
+    } else {
+        DepositStatus::Refunded
+    }
+}
+
+/// Deposits the submitter adds are its own, the rest is tracked per co-sponsor so it can be
+/// refunded to each of them
+fn record_sponsor_deposit(
+    submitter_address: &Addr,
+    co_sponsors: &mut Vec<(Addr, Uint128)>,
+    sponsor_address: Addr,
+    deposit_amount: Uint128,
+) -> StdResult<()> {
+    if sponsor_address == *submitter_address {
+        return Ok(());
+    }
+    match co_sponsors
+        .iter_mut()
+        .find(|(co_sponsor, _)| *co_sponsor == sponsor_address)
+    {
+        Some((_, co_sponsor_deposit)) => {
+            *co_sponsor_deposit = co_sponsor_deposit.checked_add(deposit_amount)?
+        }
+        None => co_sponsors.push((sponsor_address, deposit_amount)),
+    }
+    Ok(())
+}
+
+/// Part of the proposal deposit made by the submitter, without the co-sponsor deposits
+/// Amount of the deposit transferred when a proposal ends: the whole deposit if slashed, the
+/// submitter's part otherwise
+fn settled_deposit_amount(config: &Config, proposal: &Proposal) -> StdResult<Uint128> {
+    if is_deposit_slashed(config, proposal) {
+        Ok(proposal.deposit_amount)
+    } else {
+        submitter_deposit(proposal)
+    }
+}
+
+fn submitter_deposit(proposal: &Proposal) -> StdResult<Uint128> {
+    proposal
+        .co_sponsors
+        .iter()
+        .try_fold(
+            proposal.deposit_amount,
+            |amount, (_, co_sponsor_deposit)| amount.checked_sub(*co_sponsor_deposit),
+        )
+        .map_err(StdError::from)
+}
+
+/// Transfer of the deposit of an ended proposal, in the token it was made in: refunded to the
+/// submitter (without the co-sponsor deposits, which are claimed separately) or slashed (see
+/// `is_deposit_slashed`)
+fn deposit_settlement_msg(
+    config: &Config,
+    proposal: &Proposal,
+    mars_token_address: &Addr,
+    staking_address: Addr,
+) -> StdResult<CosmosMsg> {
+    let amount = settled_deposit_amount(config, proposal)?;
+    let cw20_msg = if is_deposit_slashed(config, proposal) {
+        slashed_deposit_msg(
+            config.slashed_deposit_destination.clone(),
+            staking_address,
+            amount,
+        )
+    } else {
+        Cw20ExecuteMsg::Transfer {
+            recipient: proposal.submitter_address.to_string(),
+            amount,
+        }
+    };
+
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: proposal
+            .deposit_token
+            .as_ref()
+            .unwrap_or(mars_token_address)
+            .to_string(),
+        msg: to_binary(&cw20_msg)?,
+        funds: vec![],
+    }))
+}
+
+/// Deposit required to submit a proposal: the flat deposit, or the configured share of the MARS
+/// supply if greater. Multiplied by the surge multiplier while more proposals than the surge
+/// threshold were submitted within the surge window
+fn get_required_deposit(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    mars_token_address: Addr,
+) -> StdResult<Uint128> {
+    let mut required_deposit = config.proposal_required_deposit;
+    if !config.proposal_required_deposit_ratio.is_zero() {
+        let mars_total_supply = cw20_get_total_supply(&deps.querier, mars_token_address)?;
+        let supply_deposit = mars_total_supply * config.proposal_required_deposit_ratio;
+        required_deposit = required_deposit.max(supply_deposit);
+    }
+
+    if config.surge_window > 0
+        && count_recent_proposals(deps.storage, env, config.surge_window)? > config.surge_threshold
+    {
+        required_deposit = required_deposit * config.surge_multiplier;
+    }
+
+    Ok(required_deposit)
+}
+
+/// Number of proposals submitted within the given number of past blocks. Proposal ids do not
+/// follow start heights (reserved proposals start when they are finalized), so proposals are
+/// counted by start height
+fn count_recent_proposals(storage: &dyn Storage, env: &Env, window: u64) -> StdResult<u64> {
+    let window_start = env.block.height.saturating_sub(window);
+    PROPOSAL_COUNT_BY_START_HEIGHT
+        .range(
+            storage,
+            Some(Bound::inclusive(U64Key::new(window_start))),
+            None,
+            Order::Ascending,
+        )
+        .try_fold(0, |total, item| {
+            let (_k, count) = item?;
+            Ok(total + count)
+        })
+}
+
+/// MARS token execute messages changing who can mint. Not part of the cw20 version in use but
+/// supported by newer cw20 token implementations
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum MarsTokenPrivilegedMsg {
+    UpdateMinter {},
+}
+
+/// Proposal messages cannot change the MARS token admin, code or minter, which could give an
+/// arbitrary address minting rights (unless privileged token calls are allowed in config)
+fn validate_mars_token_call(
+    mars_token_address: &Addr,
+    msg: &CosmosMsg,
+) -> Result<(), ContractError> {
+    let is_privileged_call = match msg {
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) => {
+            *contract_addr == *mars_token_address
+                && from_binary::<MarsTokenPrivilegedMsg>(msg).is_ok()
+        }
+        CosmosMsg::Wasm(WasmMsg::Migrate { contract_addr, .. })
+        | CosmosMsg::Wasm(WasmMsg::UpdateAdmin { contract_addr, .. })
+        | CosmosMsg::Wasm(WasmMsg::ClearAdmin { contract_addr }) => {
+            *contract_addr == *mars_token_address
+        }
+        _ => false,
+    };
+
+    if is_privileged_call {
+        return Err(ContractError::invalid_proposal(
+            "privileged calls to the MARS token are not allowed",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Voting power counted towards the proposal tally. Votes cast when voting opens are multiplied
+/// by (1 + early_vote_bonus), decaying linearly to no bonus at the end of the voting period
+fn early_vote_weighted_power(
+    block_height: u64,
+    proposal: &Proposal,
+    early_vote_bonus: Decimal,
+    voting_power: Uint128,
+) -> Uint128 {
+    if early_vote_bonus.is_zero() {
+        return voting_power;
+    }
+
+    let max_bonus_power = voting_power * early_vote_bonus;
+    let voting_blocks = proposal.end_height - proposal.vote_start_height;
+    let bonus_power = if voting_blocks == 0 {
+        max_bonus_power
+    } else {
+        let remaining_blocks = proposal.end_height - block_height;
+        max_bonus_power.multiply_ratio(remaining_blocks, voting_blocks)
+    };
+
+    voting_power + bonus_power
+}
+
+/// Voting power of a user at the given block
+fn get_voting_power_at(
+    querier: &QuerierWrapper,
+    config: &Config,
+    user_address: Addr,
+    block: u64,
+) -> StdResult<Uint128> {
+    let voting_token_address = get_voting_token_address(querier, config)?;
+
+    // The voting power of a user for a proposal is defined as the sum of two parts:
+    //
+    // - Free voting power: the amount of xMARS token in the user's wallet, at the block before the
+    //   proposal was created
+    // - Locked voting power: the amount of MARS locked in the vesting contract owned by the user,
+    //   at the block before the proposal was created
+    //
+    // The reason we can use the amount of MARS (instead of xMARS) for locked voting power is that,
+    // since vesting allocations can only be created when 1 MARS == 1 xMARS, these MARS tokens would
+    // have produced the same amount of xMARS if they were staked.
+    //
+    // Locked MARS is not measured in a voting token override, so it only counts towards xMARS
+    // voting power.
+    let voting_power_free =
+        xmars_get_balance_at(querier, voting_token_address, user_address.clone(), block)?;
+    let voting_power_locked = match config.voting_token_override {
+        Some(_) => Uint128::zero(),
+        None => {
+            let vesting_address = address_provider::helpers::query_address(
+                querier,
+                config.address_provider_address.clone(),
+                MarsContract::Vesting,
+            )?;
+            vesting_get_voting_power_at(querier, vesting_address, user_address, block)?
+        }
+    };
+
+    Ok(voting_power_free + voting_power_locked)
+}
+
+/// Total voting power at the given block. None if the voting token has no total supply snapshot
+/// at the block, which is not the same as having no supply
+fn get_total_voting_power_at(
+    querier: &QuerierWrapper,
+    config: &Config,
+    block: u64,
+) -> StdResult<Option<Uint128>> {
+    let voting_token_address = get_voting_token_address(querier, config)?;
+
+    // The total voting power of a proposal is defined as the sum of two parts:
+    //
+    // - Free voting power: the total supply of xMARS token at the block before the proposal was
+    //   created
+    // - Locked voting power: the total amount of MARS token locked in the vesting contract, at the
+    //   block before the proposal was created
+    //
+    // The reason we can use the amount of MARS (instead of xMARS) for locked voting power is that,
+    // since vesting allocations can only be created when 1 MARS == 1 xMARS, these MARS tokens would
+    // have produced the same amount of xMARS if they were staked.
+    //
+    // As for the voting power of a user, locked MARS only counts towards xMARS voting power.
+    let mut total_voting_power_free =
+        match xmars_get_total_supply_at(querier, voting_token_address.clone(), block)? {
+            Some(total_supply) => total_supply,
+            None => return Ok(None),
+        };
+    let total_voting_power_locked = match config.voting_token_override {
+        Some(_) => Uint128::zero(),
+        None => {
+            let vesting_address = address_provider::helpers::query_address(
+                querier,
+                config.address_provider_address.clone(),
+                MarsContract::Vesting,
+            )?;
+            vesting_get_total_voting_power_at(querier, vesting_address, block)?
+        }
+    };
+
+    // When measuring quorum against circulating supply, xMARS held by the excluded addresses
+    // (e.g. protocol contracts) is not part of the total voting power
+    if config.quorum_basis == QuorumBasis::CirculatingSupply {
+        for excluded_address in &config.quorum_excluded_addresses {
+            let excluded_balance = xmars_get_balance_at(
+                querier,
+                voting_token_address.clone(),
+                excluded_address.clone(),
+                block,
+            )?;
+            total_voting_power_free = total_voting_power_free.saturating_sub(excluded_balance);
+        }
+    }
+
+    Ok(Some(total_voting_power_free + total_voting_power_locked))
+}
+
+/// Token free voting power is measured with: the configured override if any, xMARS otherwise
+fn get_voting_token_address(querier: &QuerierWrapper, config: &Config) -> StdResult<Addr> {
+    match &config.voting_token_override {
+        Some(voting_token_address) => Ok(voting_token_address.clone()),
+        None => Ok(address_provider::helpers::query_address(
+            querier,
+            config.address_provider_address.clone(),
+            MarsContract::XMarsToken,
+        )?),
+    }
+}
+
+/// Total supply of the token at the given block, or None if the token has no snapshot at it
+fn xmars_get_total_supply_at(
+    querier: &QuerierWrapper,
+    xmars_address: Addr,
+    block: u64,
+) -> StdResult<Option<Uint128>> {
+    let query: xmars_token::TotalSupplyResponse =
+        querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: xmars_address.into(),
+            msg: to_binary(&xmars_token::msg::QueryMsg::TotalSupplyAt { block })?,
+        }))?;
+
+    if !query.snapshot_found {
+        return Ok(None);
+    }
+    Ok(Some(query.total_supply))
+}
+
+fn xmars_get_balance_at(
+    querier: &QuerierWrapper,
+    xmars_address: Addr,
+    user_address: Addr,
+    block: u64,
+) -> StdResult<Uint128> {
+    let query: cw20::BalanceResponse = querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: xmars_address.into(),
+        msg: to_binary(&xmars_token::msg::QueryMsg::BalanceAt {
+            address: user_address.to_string(),
+            block,
+        })?,
+    }))?;
+
+    Ok(query.balance)
+}
+
+fn vesting_get_total_voting_power_at(
+    querier: &QuerierWrapper,
+    vesting_address: Addr,
+    block: u64,
+) -> StdResult<Uint128> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: vesting_address.into(),
+        msg: to_binary(&vesting::msg::QueryMsg::TotalVotingPowerAt { block })?,
+    }))
+}
+
+fn vesting_get_voting_power_at(
+    querier: &QuerierWrapper,
+    vesting_address: Addr,
+    user_address: Addr,
+    block: u64,
+) -> StdResult<Uint128> {
+    querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: vesting_address.into(),
+        msg: to_binary(&vesting::msg::QueryMsg::VotingPowerAt {
+            user_address: user_address.to_string(),
+            block,
+        })?,
+    }))
+}
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{Coin, OwnedDeps, ReplyOn, StdError, SubMsg, SubMsgExecutionResponse};
+    use mars_core::council::MINIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE;
+    use mars_core::math::decimal::Decimal;
+    use mars_core::testing::{
+        mock_dependencies, mock_env, mock_env_at_block_height, mock_info, MarsMockQuerier,
+        MockEnvParams,
+    };
+
+    use crate::msg::ExecuteMsg::UpdateConfig;
+
+    const TEST_PROPOSAL_VOTING_PERIOD: u64 = 2000;
+    const TEST_PROPOSAL_EFFECTIVE_DELAY: u64 = 200;
+    const TEST_PROPOSAL_EXPIRATION_PERIOD: u64 = 300;
+    const TEST_PROPOSAL_REQUIRED_DEPOSIT: Uint128 = Uint128::new(10000);
+
+    #[test]
+    fn test_proper_initialization() {
+        let mut deps = mock_dependencies(&[]);
+        let env = cosmwasm_std::testing::mock_env();
+        let info = mock_info("someone");
+
+        // init config with empty params
+        {
+            let empty_config = CreateOrUpdateConfig {
+                address_provider_address: None,
+
+                proposal_voting_period: None,
+                proposal_effective_delay: None,
+                proposal_expiration_period: None,
+                proposal_required_deposit: None,
+                proposal_required_threshold: None,
+                proposal_required_quorum: None,
+                submitter_cooldown: None,
+                emergency_owner: None,
+                title_max: None,
+                description_max: None,
+                link_max: None,
+                slashed_deposit_destination: None,
+                end_proposal_reward: None,
+                snapshot_offset: None,
+                reclaim_grace_period: None,
+                fast_track_targets: None,
+                quorum_basis: None,
+                quorum_excluded_addresses: None,
+                discussion_period: None,
+                mint_per_proposal_cap: None,
+                mint_total_cap: None,
+                reservation_period: None,
+                allow_privileged_token_calls: None,
+                early_vote_bonus: None,
+                voting_token_override: None,
+                avg_block_time_seconds: None,
+                min_for_votes_absolute: None,
+                proposal_required_deposit_ratio: None,
+                min_vote_power: None,
+                dedupe_window: None,
+                executor_allowlist: None,
+                ica_controller_address: None,
+                surge_window: None,
+                surge_threshold: None,
+                surge_multiplier: None,
+                allowed_categories: None,
+                accepted_deposit_tokens: None,
+                refund_signaling_always: None,
+                auto_expire_grace: None,
+                require_call_simulation: None,
+            };
+            let msg = InstantiateMsg {
+                config: empty_config,
+            };
+            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(error_res, MarsError::InstantiateParamsUnavailable {}.into());
+        }
+
+        let init_config = CreateOrUpdateConfig {
+            address_provider_address: Some(String::from("address_provider")),
+            proposal_voting_period: Some(1),
+            proposal_effective_delay: Some(1),
+            proposal_expiration_period: Some(1),
+            proposal_required_deposit: Some(Uint128::new(1)),
+            proposal_required_quorum: Some(Decimal::percent(75)),
+            proposal_required_threshold: Some(Decimal::percent(
+                MINIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE,
+            )),
+            submitter_cooldown: None,
+            emergency_owner: None,
+            title_max: None,
+            description_max: None,
+            link_max: None,
+            slashed_deposit_destination: None,
+            end_proposal_reward: None,
+            snapshot_offset: None,
+            reclaim_grace_period: None,
+            fast_track_targets: None,
+            quorum_basis: None,
+            quorum_excluded_addresses: None,
+            discussion_period: None,
+            mint_per_proposal_cap: None,
+            mint_total_cap: None,
+            reservation_period: None,
+            allow_privileged_token_calls: None,
+            early_vote_bonus: None,
+            voting_token_override: None,
+            avg_block_time_seconds: None,
+            min_for_votes_absolute: None,
+            proposal_required_deposit_ratio: None,
+            min_vote_power: None,
+            dedupe_window: None,
+            executor_allowlist: None,
+            ica_controller_address: None,
+            surge_window: None,
+            surge_threshold: None,
+            surge_multiplier: None,
+            allowed_categories: None,
+            accepted_deposit_tokens: None,
+            refund_signaling_always: None,
+            auto_expire_grace: None,
+            require_call_simulation: None,
+        };
+
+        // *
+        // init with invalid params
+        // *
+        {
+            // init with proposal_required_quorum greater than 1
+            let config = CreateOrUpdateConfig {
+                proposal_required_quorum: Some(Decimal::percent(101)),
+                ..init_config.clone()
+            };
+            let msg = InstantiateMsg { config };
+            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_quorum".to_string(),
+                    invalid_value: "1.01".to_string(),
+                    predicate: "<= 1".to_string(),
+                }
+                .into()
+            );
+
+            // init with zero proposal_required_quorum
+            let config = CreateOrUpdateConfig {
+                proposal_required_quorum: Some(Decimal::zero()),
+                ..init_config.clone()
+            };
+            let msg = InstantiateMsg { config };
+            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_quorum".to_string(),
+                    invalid_value: "0".to_string(),
+                    predicate: "> 0".to_string(),
+                }
+                .into()
+            );
+
+            // init with zero proposal_required_threshold
+            let config = CreateOrUpdateConfig {
+                proposal_required_threshold: Some(Decimal::zero()),
+                ..init_config.clone()
+            };
+            let msg = InstantiateMsg { config };
+            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_threshold".to_string(),
+                    invalid_value: "0".to_string(),
+                    predicate: ">= 0.5 and <= 1".to_string(),
+                }
+                .into()
+            );
+
+            // init with proposal_required_threshold less than 50%
+            let config = CreateOrUpdateConfig {
+                proposal_required_threshold: Some(Decimal::percent(49)),
+                ..init_config.clone()
+            };
+            let msg = InstantiateMsg { config };
+            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_threshold".to_string(),
+                    invalid_value: "0.49".to_string(),
+                    predicate: ">= 0.5 and <= 1".to_string(),
+                }
+                .into()
+            );
+
+            // init with proposal_required_threshold greater than 100%
+            let config = CreateOrUpdateConfig {
+                proposal_required_threshold: Some(Decimal::percent(101)),
+                ..init_config.clone()
+            };
+            let msg = InstantiateMsg { config };
+            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_threshold".to_string(),
+                    invalid_value: "1.01".to_string(),
+                    predicate: ">= 0.5 and <= 1".to_string(),
+                }
+                .into()
+            );
+
+            // init with zero snapshot_offset
+            let config = CreateOrUpdateConfig {
+                snapshot_offset: Some(0),
+                ..init_config.clone()
+            };
+            let msg = InstantiateMsg { config };
+            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "snapshot_offset".to_string(),
+                    invalid_value: "0".to_string(),
+                    predicate: "> 0".to_string(),
+                }
+                .into()
+            );
+
+            // init with description_max lower than the minimum description length
+            let config = CreateOrUpdateConfig {
+                description_max: Some(3),
+                ..init_config.clone()
+            };
+            let msg = InstantiateMsg { config };
+            let error_res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "description_max".to_string(),
+                    invalid_value: "3".to_string(),
+                    predicate: ">= 4".to_string(),
+                }
+                .into()
+            );
+        }
+
+        // Successful Init
+        {
+            let msg = InstantiateMsg {
+                config: init_config,
+            };
+            let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+            assert_eq!(0, res.messages.len());
+
+            let config = CONFIG.load(&deps.storage).unwrap();
+            assert_eq!(
+                Addr::unchecked("address_provider"),
+                config.address_provider_address
+            );
+
+            assert_eq!(config.title_max, DEFAULT_TITLE_MAX_LENGTH);
+            assert_eq!(config.description_max, DEFAULT_DESC_MAX_LENGTH);
+            assert_eq!(config.link_max, DEFAULT_LINK_MAX_LENGTH);
+
+            let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+            assert_eq!(global_state.proposal_count, 0);
+        }
+    }
+
+    #[test]
+    fn test_update_config() {
+        let mut deps = mock_dependencies(&[]);
+
+        // *
         // init config with valid params
         // *
-        let init_config = CreateOrUpdateConfig {
-            address_provider_address: Some(String::from("address_provider")),
+        let init_config = CreateOrUpdateConfig {
+            address_provider_address: Some(String::from("old_address_provider")),
+
+            proposal_voting_period: Some(10),
+            proposal_effective_delay: Some(11),
+            proposal_expiration_period: Some(12),
+            proposal_required_deposit: Some(Uint128::new(111)),
+            proposal_required_threshold: Some(Decimal::percent(
+                MINIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE,
+            )),
+            proposal_required_quorum: Some(Decimal::one()),
+            submitter_cooldown: None,
+            emergency_owner: None,
+            title_max: None,
+            description_max: None,
+            link_max: None,
+            slashed_deposit_destination: None,
+            end_proposal_reward: None,
+            snapshot_offset: None,
+            reclaim_grace_period: None,
+            fast_track_targets: None,
+            quorum_basis: None,
+            quorum_excluded_addresses: None,
+            discussion_period: None,
+            mint_per_proposal_cap: None,
+            mint_total_cap: None,
+            reservation_period: None,
+            allow_privileged_token_calls: None,
+            early_vote_bonus: None,
+            voting_token_override: None,
+            avg_block_time_seconds: None,
+            min_for_votes_absolute: None,
+            proposal_required_deposit_ratio: None,
+            min_vote_power: None,
+            dedupe_window: None,
+            executor_allowlist: None,
+            ica_controller_address: None,
+            surge_window: None,
+            surge_threshold: None,
+            surge_multiplier: None,
+            allowed_categories: None,
+            accepted_deposit_tokens: None,
+            refund_signaling_always: None,
+            auto_expire_grace: None,
+            require_call_simulation: None,
+        };
+        let msg = InstantiateMsg {
+            config: init_config.clone(),
+        };
+        let env = cosmwasm_std::testing::mock_env();
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        // *
+        // update config with invalid params
+        // *
+        {
+            let env = cosmwasm_std::testing::mock_env();
+            let info = mock_info(MOCK_CONTRACT_ADDR);
+
+            // proposal_required_quorum greater than 1
+            let config = CreateOrUpdateConfig {
+                proposal_required_quorum: Some(Decimal::percent(101)),
+                ..init_config.clone()
+            };
+            let msg = UpdateConfig { config };
+            let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_quorum".to_string(),
+                    invalid_value: "1.01".to_string(),
+                    predicate: "<= 1".to_string(),
+                }
+                .into()
+            );
+
+            // zero proposal_required_quorum
+            let config = CreateOrUpdateConfig {
+                proposal_required_quorum: Some(Decimal::zero()),
+                ..init_config.clone()
+            };
+            let msg = UpdateConfig { config };
+            let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_quorum".to_string(),
+                    invalid_value: "0".to_string(),
+                    predicate: "> 0".to_string(),
+                }
+                .into()
+            );
+
+            // zero proposal_required_threshold
+            let config = CreateOrUpdateConfig {
+                proposal_required_threshold: Some(Decimal::zero()),
+                ..init_config.clone()
+            };
+            let msg = UpdateConfig { config };
+            let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_threshold".to_string(),
+                    invalid_value: "0".to_string(),
+                    predicate: ">= 0.5 and <= 1".to_string(),
+                }
+                .into()
+            );
+
+            // proposal_required_threshold less than 50%
+            let config = CreateOrUpdateConfig {
+                proposal_required_threshold: Some(Decimal::percent(49)),
+                ..init_config.clone()
+            };
+            let msg = UpdateConfig { config };
+            let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_threshold".to_string(),
+                    invalid_value: "0.49".to_string(),
+                    predicate: ">= 0.5 and <= 1".to_string(),
+                }
+                .into()
+            );
+
+            // proposal_required_threshold greater than 100%
+            let config = CreateOrUpdateConfig {
+                proposal_required_threshold: Some(Decimal::percent(101)),
+                ..init_config.clone()
+            };
+            let msg = UpdateConfig { config };
+            let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                MarsError::InvalidParam {
+                    param_name: "proposal_required_threshold".to_string(),
+                    invalid_value: "1.01".to_string(),
+                    predicate: ">= 0.5 and <= 1".to_string(),
+                }
+                .into()
+            );
+        }
+
+        // *
+        // only council itself is authorized
+        // *
+        {
+            let msg = UpdateConfig {
+                config: init_config,
+            };
+            let info = mock_info("somebody");
+            let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+            assert_eq!(error_res, MarsError::Unauthorized {}.into());
+        }
+
+        // *
+        // update config with all new params
+        // *
+        {
+            let config = CreateOrUpdateConfig {
+                address_provider_address: Some(String::from("address_provider")),
+
+                proposal_voting_period: Some(101),
+                proposal_effective_delay: Some(111),
+                proposal_expiration_period: Some(121),
+                proposal_required_deposit: Some(Uint128::new(1111)),
+                proposal_required_threshold: Some(Decimal::from_ratio(4u128, 5u128)),
+                proposal_required_quorum: Some(Decimal::from_ratio(1u128, 5u128)),
+                submitter_cooldown: Some(100),
+                emergency_owner: Some(String::from("emergency_owner")),
+                title_max: Some(128),
+                description_max: Some(2048),
+                link_max: Some(256),
+                slashed_deposit_destination: Some(SlashedDepositDestination::Burn),
+                end_proposal_reward: Some(Uint128::new(100)),
+                snapshot_offset: Some(10),
+                reclaim_grace_period: Some(500),
+                fast_track_targets: Some(vec![String::from("oracle")]),
+                quorum_basis: Some(QuorumBasis::CirculatingSupply),
+                quorum_excluded_addresses: Some(vec![String::from("treasury")]),
+                discussion_period: Some(5),
+                mint_per_proposal_cap: Some(Uint128::new(1_000)),
+                mint_total_cap: Some(Uint128::new(10_000)),
+                reservation_period: Some(50),
+                allow_privileged_token_calls: Some(true),
+                early_vote_bonus: Some(Decimal::percent(50)),
+                voting_token_override: Some(String::from("gov_token")),
+                avg_block_time_seconds: Some(7),
+                min_for_votes_absolute: Some(Uint128::new(1_000)),
+                proposal_required_deposit_ratio: Some(Decimal::permille(1)),
+                min_vote_power: Some(Uint128::new(10)),
+                dedupe_window: Some(50),
+                executor_allowlist: Some(vec![String::from("executor")]),
+                ica_controller_address: Some(String::from("ica_controller")),
+                surge_window: Some(100),
+                surge_threshold: Some(3),
+                surge_multiplier: Some(Decimal::percent(200)),
+                allowed_categories: Some(vec![String::from("treasury")]),
+                accepted_deposit_tokens: Some(vec![(
+                    String::from("usdc_token"),
+                    Uint128::new(500),
+                )]),
+                refund_signaling_always: Some(true),
+                auto_expire_grace: Some(200),
+                require_call_simulation: Some(true),
+            };
+            let msg = UpdateConfig {
+                config: config.clone(),
+            };
+            let info = mock_info(MOCK_CONTRACT_ADDR);
+            let res = execute(deps.as_mut(), env, info, msg).unwrap();
+            assert_eq!(0, res.messages.len());
+
+            // Read config from state
+            let new_config = CONFIG.load(&deps.storage).unwrap();
+
+            assert_eq!(
+                new_config.address_provider_address,
+                Addr::unchecked("address_provider")
+            );
+            assert_eq!(
+                new_config.proposal_voting_period,
+                config.proposal_voting_period.unwrap()
+            );
+            assert_eq!(
+                new_config.proposal_effective_delay,
+                config.proposal_effective_delay.unwrap()
+            );
+            assert_eq!(
+                new_config.proposal_expiration_period,
+                config.proposal_expiration_period.unwrap()
+            );
+            assert_eq!(
+                new_config.proposal_required_deposit,
+                config.proposal_required_deposit.unwrap()
+            );
+            assert_eq!(
+                new_config.proposal_required_threshold,
+                config.proposal_required_threshold.unwrap()
+            );
+            assert_eq!(
+                new_config.proposal_required_quorum,
+                config.proposal_required_quorum.unwrap()
+            );
+            assert_eq!(
+                new_config.submitter_cooldown,
+                config.submitter_cooldown.unwrap()
+            );
+            assert_eq!(
+                new_config.discussion_period,
+                config.discussion_period.unwrap()
+            );
+            assert_eq!(
+                new_config.emergency_owner,
+                Some(Addr::unchecked("emergency_owner"))
+            );
+            assert_eq!(new_config.title_max, config.title_max.unwrap());
+            assert_eq!(new_config.description_max, config.description_max.unwrap());
+            assert_eq!(new_config.link_max, config.link_max.unwrap());
+            assert_eq!(
+                new_config.slashed_deposit_destination,
+                SlashedDepositDestination::Burn
+            );
+            assert_eq!(
+                new_config.end_proposal_reward,
+                config.end_proposal_reward.unwrap()
+            );
+            assert_eq!(new_config.snapshot_offset, config.snapshot_offset.unwrap());
+            assert_eq!(
+                new_config.reclaim_grace_period,
+                config.reclaim_grace_period.unwrap()
+            );
+            assert_eq!(
+                new_config.fast_track_targets,
+                vec![Addr::unchecked("oracle")]
+            );
+            assert_eq!(new_config.quorum_basis, QuorumBasis::CirculatingSupply);
+            assert_eq!(
+                new_config.mint_per_proposal_cap,
+                config.mint_per_proposal_cap.unwrap()
+            );
+            assert_eq!(new_config.mint_total_cap, config.mint_total_cap.unwrap());
+            assert_eq!(
+                new_config.reservation_period,
+                config.reservation_period.unwrap()
+            );
+            assert_eq!(
+                new_config.allow_privileged_token_calls,
+                config.allow_privileged_token_calls.unwrap()
+            );
+            assert_eq!(
+                new_config.early_vote_bonus,
+                config.early_vote_bonus.unwrap()
+            );
+            assert_eq!(
+                new_config.voting_token_override,
+                Some(Addr::unchecked("gov_token"))
+            );
+            assert_eq!(
+                new_config.avg_block_time_seconds,
+                config.avg_block_time_seconds.unwrap()
+            );
+            assert_eq!(
+                new_config.min_for_votes_absolute,
+                config.min_for_votes_absolute.unwrap()
+            );
+            assert_eq!(
+                new_config.proposal_required_deposit_ratio,
+                config.proposal_required_deposit_ratio.unwrap()
+            );
+            assert_eq!(new_config.min_vote_power, config.min_vote_power.unwrap());
+            assert_eq!(new_config.dedupe_window, config.dedupe_window.unwrap());
+            assert_eq!(
+                new_config.executor_allowlist,
+                vec![Addr::unchecked("executor")]
+            );
+            assert_eq!(
+                new_config.ica_controller_address,
+                Some(Addr::unchecked("ica_controller"))
+            );
+            assert_eq!(new_config.surge_window, 100);
+            assert_eq!(new_config.surge_threshold, 3);
+            assert_eq!(new_config.surge_multiplier, Decimal::percent(200));
+            assert_eq!(
+                new_config.allowed_categories,
+                vec![String::from("treasury")]
+            );
+            assert_eq!(
+                new_config.accepted_deposit_tokens,
+                vec![(Addr::unchecked("usdc_token"), Uint128::new(500))]
+            );
+            assert!(new_config.refund_signaling_always);
+            assert_eq!(new_config.auto_expire_grace, 200);
+            assert!(new_config.require_call_simulation);
+            assert_eq!(
+                new_config.quorum_excluded_addresses,
+                vec![Addr::unchecked("treasury")]
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_config_address_provider() {
+        let mut deps = th_setup(&[]);
+
+        let update_msg = |address_provider_address: &str| UpdateConfig {
+            config: CreateOrUpdateConfig {
+                address_provider_address: Some(String::from(address_provider_address)),
+                ..Default::default()
+            },
+        };
+
+        // provider that does not resolve the contracts the council depends on is rejected
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            update_msg("unconfigured_address_provider"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            MarsError::EmptyAddresses {
+                empty_addresses: vec![
+                    MarsContract::MarsToken,
+                    MarsContract::XMarsToken,
+                    MarsContract::Staking,
+                ]
+            }
+            .into()
+        );
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            config.address_provider_address,
+            Addr::unchecked("address_provider")
+        );
+
+        // provider resolving them is accepted
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.address_provider_address = Addr::unchecked("old_address_provider");
+                Ok(config)
+            })
+            .unwrap();
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        execute(deps.as_mut(), env, info, update_msg("address_provider")).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            config.address_provider_address,
+            Addr::unchecked("address_provider")
+        );
+    }
+
+    #[test]
+    fn test_query_config() {
+        let mut deps = mock_dependencies(&[]);
+        let env = cosmwasm_std::testing::mock_env();
+
+        let init_config = CreateOrUpdateConfig {
+            address_provider_address: Some(String::from("address_provider")),
+            proposal_voting_period: Some(1),
+            proposal_effective_delay: Some(1),
+            proposal_expiration_period: Some(1),
+            proposal_required_deposit: Some(Uint128::new(1)),
+            proposal_required_quorum: Some(Decimal::percent(75)),
+            proposal_required_threshold: Some(Decimal::percent(75)),
+            submitter_cooldown: None,
+            emergency_owner: None,
+            title_max: None,
+            description_max: None,
+            link_max: None,
+            slashed_deposit_destination: None,
+            end_proposal_reward: None,
+            snapshot_offset: None,
+            reclaim_grace_period: None,
+            fast_track_targets: None,
+            quorum_basis: None,
+            quorum_excluded_addresses: None,
+            discussion_period: None,
+            mint_per_proposal_cap: None,
+            mint_total_cap: None,
+            reservation_period: None,
+            allow_privileged_token_calls: None,
+            early_vote_bonus: None,
+            voting_token_override: None,
+            avg_block_time_seconds: None,
+            min_for_votes_absolute: None,
+            proposal_required_deposit_ratio: None,
+            min_vote_power: None,
+            dedupe_window: None,
+            executor_allowlist: None,
+            ica_controller_address: None,
+            surge_window: None,
+            surge_threshold: None,
+            surge_multiplier: None,
+            allowed_categories: None,
+            accepted_deposit_tokens: None,
+            refund_signaling_always: None,
+            auto_expire_grace: None,
+            require_call_simulation: None,
+        };
+        let msg = InstantiateMsg {
+            config: init_config,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("someone"), msg).unwrap();
+
+        let all_fields = [
+            "contract_version",
+            "address_provider_address",
+            "proposal_voting_period",
+            "proposal_effective_delay",
+            "proposal_expiration_period",
+            "proposal_required_deposit",
+            "proposal_required_quorum",
+            "proposal_required_threshold",
+            "submitter_cooldown",
+            "discussion_period",
+            "emergency_owner",
+            "submissions_paused",
+            "voting_paused",
+            "title_max",
+            "description_max",
+            "link_max",
+            "slashed_deposit_destination",
+            "fast_track_targets",
+            "reclaim_grace_period",
+            "snapshot_offset",
+            "end_proposal_reward",
+            "quorum_basis",
+            "quorum_excluded_addresses",
+            "mint_per_proposal_cap",
+            "mint_total_cap",
+            "reservation_period",
+            "allow_privileged_token_calls",
+            "early_vote_bonus",
+            "voting_token_override",
+            "avg_block_time_seconds",
+            "min_for_votes_absolute",
+            "proposal_required_deposit_ratio",
+            "min_vote_power",
+            "dedupe_window",
+            "executor_allowlist",
+            "ica_controller_address",
+            "surge_window",
+            "surge_threshold",
+            "surge_multiplier",
+            "allowed_categories",
+            "accepted_deposit_tokens",
+            "refund_signaling_always",
+            "auto_expire_grace",
+            "require_call_simulation",
+            "deposit_token_decimals",
+        ];
+
+        // freshly instantiated config serializes every field, unset ones as null
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let json = String::from_utf8(res.to_vec()).unwrap();
+        for field in all_fields.iter() {
+            assert!(
+                json.contains(&format!("\"{}\":", field)),
+                "missing {}",
+                field
+            );
+        }
+        assert!(json.contains("\"emergency_owner\":null"));
+
+        let config_response: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config_response.contract_version, CONTRACT_VERSION);
+        assert_eq!(config_response.emergency_owner, None);
+        assert_eq!(config_response.quorum_basis, QuorumBasis::TotalSupply);
+        // MARS token info is not mocked yet
+        assert_eq!(config_response.deposit_token_decimals, None);
+
+        deps.querier
+            .set_cw20_decimals(Addr::unchecked("mars_token"), 6);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+        let config_response: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config_response.deposit_token_decimals, Some(6));
+
+        // updated config
+        let update_config = CreateOrUpdateConfig {
+            address_provider_address: None,
+            proposal_voting_period: None,
+            proposal_effective_delay: None,
+            proposal_expiration_period: None,
+            proposal_required_deposit: None,
+            proposal_required_quorum: None,
+            proposal_required_threshold: None,
+            submitter_cooldown: Some(10),
+            emergency_owner: Some(String::from("guardian")),
+            title_max: None,
+            description_max: None,
+            link_max: None,
+            slashed_deposit_destination: Some(SlashedDepositDestination::Burn),
+            end_proposal_reward: None,
+            snapshot_offset: None,
+            reclaim_grace_period: None,
+            fast_track_targets: None,
+            quorum_basis: Some(QuorumBasis::CirculatingSupply),
+            quorum_excluded_addresses: None,
+            discussion_period: None,
+            mint_per_proposal_cap: None,
+            mint_total_cap: None,
+            reservation_period: None,
+            allow_privileged_token_calls: None,
+            early_vote_bonus: None,
+            voting_token_override: None,
+            avg_block_time_seconds: None,
+            min_for_votes_absolute: None,
+            proposal_required_deposit_ratio: None,
+            min_vote_power: None,
+            dedupe_window: None,
+            executor_allowlist: None,
+            ica_controller_address: None,
+            surge_window: None,
+            surge_threshold: None,
+            surge_multiplier: None,
+            allowed_categories: None,
+            accepted_deposit_tokens: None,
+            refund_signaling_always: None,
+            auto_expire_grace: None,
+            require_call_simulation: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            UpdateConfig {
+                config: update_config,
+            },
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), env, QueryMsg::Config {}).unwrap();
+        let json = String::from_utf8(res.to_vec()).unwrap();
+        for field in all_fields.iter() {
+            assert!(
+                json.contains(&format!("\"{}\":", field)),
+                "missing {}",
+                field
+            );
+        }
+
+        let config_response: ConfigResponse = from_binary(&res).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config_response.submitter_cooldown, 10);
+        assert_eq!(
+            config_response.emergency_owner,
+            Some(Addr::unchecked("guardian"))
+        );
+        assert_eq!(
+            config_response.slashed_deposit_destination,
+            SlashedDepositDestination::Burn
+        );
+        assert_eq!(config_response.quorum_basis, config.quorum_basis);
+        assert_eq!(
+            config_response.proposal_required_quorum,
+            config.proposal_required_quorum
+        );
+    }
+
+    #[test]
+    fn test_sudo_update_config() {
+        let mut deps = th_setup(&[]);
+
+        let msg = SudoMsg::UpdateConfig {
+            config: CreateOrUpdateConfig {
+                proposal_voting_period: Some(5_000),
+                proposal_required_quorum: Some(Decimal::percent(20)),
+                ..Default::default()
+            },
+        };
+        let res = sudo(deps.as_mut(), mock_env(MockEnvParams::default()), msg).unwrap();
+        assert_eq!(res.attributes, vec![attr("action", "update_config")]);
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.proposal_voting_period, 5_000);
+        assert_eq!(config.proposal_required_quorum, Decimal::percent(20));
+
+        // new config is still validated
+        let msg = SudoMsg::UpdateConfig {
+            config: CreateOrUpdateConfig {
+                proposal_required_quorum: Some(Decimal::percent(101)),
+                ..Default::default()
+            },
+        };
+        let error_res = sudo(deps.as_mut(), mock_env(MockEnvParams::default()), msg).unwrap_err();
+        assert_eq!(
+            error_res,
+            MarsError::InvalidParam {
+                param_name: "proposal_required_quorum".to_string(),
+                invalid_value: "1.01".to_string(),
+                predicate: "<= 1".to_string(),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_transfer_guardianship() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.emergency_owner = Some(Addr::unchecked("guardian"));
+                Ok(config)
+            })
+            .unwrap();
+
+        // nothing to accept before a transfer is proposed
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("new_guardian");
+        let error_res =
+            execute(deps.as_mut(), env, info, ExecuteMsg::AcceptGuardianship {}).unwrap_err();
+        assert_eq!(error_res, ContractError::NoPendingGuardianship {});
+
+        // only the guardian or the council can propose a new guardian
+        let transfer_msg = |new_guardian: &str| ExecuteMsg::TransferGuardianship {
+            new_guardian: String::from(new_guardian),
+        };
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("someone");
+        let error_res = execute(deps.as_mut(), env, info, transfer_msg("someone")).unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("guardian");
+        let res = execute(deps.as_mut(), env, info, transfer_msg("new_guardian")).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "transfer_guardianship"),
+                attr("new_guardian", "new_guardian"),
+            ]
+        );
+
+        // the current guardian keeps the role until the transfer is accepted
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.emergency_owner, Some(Addr::unchecked("guardian")));
+
+        // only the proposed guardian can accept
+        for sender in ["guardian", "someone"] {
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info(sender);
+            let error_res =
+                execute(deps.as_mut(), env, info, ExecuteMsg::AcceptGuardianship {}).unwrap_err();
+            assert_eq!(error_res, MarsError::Unauthorized {}.into());
+        }
+
+        // the council can propose another guardian, replacing the pending one
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        execute(deps.as_mut(), env, info, transfer_msg("multisig")).unwrap();
+
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("new_guardian");
+        let error_res =
+            execute(deps.as_mut(), env, info, ExecuteMsg::AcceptGuardianship {}).unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("multisig");
+        let res = execute(deps.as_mut(), env, info, ExecuteMsg::AcceptGuardianship {}).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "accept_guardianship"),
+                attr("guardian", "multisig"),
+            ]
+        );
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.emergency_owner, Some(Addr::unchecked("multisig")));
+        assert!(PENDING_GUARDIAN.may_load(&deps.storage).unwrap().is_none());
+
+        // transfer can only be accepted once
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("multisig");
+        let error_res =
+            execute(deps.as_mut(), env, info, ExecuteMsg::AcceptGuardianship {}).unwrap_err();
+        assert_eq!(error_res, ContractError::NoPendingGuardianship {});
+    }
+
+    #[test]
+    fn test_emergency_set_paused() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.emergency_owner = Some(Addr::unchecked("emergency_owner"));
+                Ok(config)
+            })
+            .unwrap();
+
+        // only the emergency owner or the council can pause
+        {
+            let msg = ExecuteMsg::EmergencySetPaused {
+                submissions: true,
+                voting: true,
+            };
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("someone");
+            let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(error_res, MarsError::Unauthorized {}.into());
+        }
+
+        // emergency owner cannot change economic parameters
+        {
+            let msg = ExecuteMsg::UpdateConfig {
+                config: CreateOrUpdateConfig {
+                    proposal_required_quorum: Some(Decimal::zero()),
+                    ..Default::default()
+                },
+            };
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("emergency_owner");
+            let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(error_res, MarsError::Unauthorized {}.into());
+        }
+
+        // emergency owner pauses submissions and voting
+        let msg = ExecuteMsg::EmergencySetPaused {
+            submissions: true,
+            voting: true,
+        };
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("emergency_owner");
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "emergency_set_paused"),
+                attr("submissions_paused", "true"),
+                attr("voting_paused", "true"),
+            ]
+        );
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert!(config.submissions_paused);
+        assert!(config.voting_paused);
+        assert_eq!(config.proposal_required_quorum, Decimal::one());
+
+        // submissions are rejected while paused
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(error_res, ContractError::SubmissionsPaused {});
+        }
+
+        // votes are rejected while paused
+        let active_proposal_id = 1_u64;
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: active_proposal_id,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+        let vote_msg = ExecuteMsg::CastVote {
+            proposal_id: active_proposal_id,
+            vote: ProposalVoteOption::For,
+        };
+        {
+            let env = mock_env_at_block_height(100_001);
+            let info = mock_info("voter");
+            let error_res = execute(deps.as_mut(), env, info, vote_msg).unwrap_err();
+            assert_eq!(error_res, ContractError::VotingPaused {});
+        }
+
+        // the council itself can unpause
+        let msg = ExecuteMsg::EmergencySetPaused {
+            submissions: false,
+            voting: false,
+        };
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert!(!config.submissions_paused);
+        assert!(!config.voting_paused);
+    }
+
+    #[test]
+    fn test_rescue_tokens() {
+        let mut deps = th_setup(&[]);
+        let env = mock_env(MockEnvParams::default());
+
+        let rescue_msg = |token: &str| ExecuteMsg::RescueTokens {
+            token: String::from(token),
+            recipient: String::from("recipient"),
+            amount: Uint128::new(1_000),
+        };
+
+        // only the council itself can rescue tokens
+        let info = mock_info("anyone");
+        let error_res =
+            execute(deps.as_mut(), env.clone(), info, rescue_msg("stray_token")).unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        // MARS cannot be rescued
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res =
+            execute(deps.as_mut(), env.clone(), info, rescue_msg("mars_token")).unwrap_err();
+        assert_eq!(error_res, ContractError::RescueTokensMarsToken {});
+
+        // neither can the other tokens accepted as deposit
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.accepted_deposit_tokens =
+                    vec![(Addr::unchecked("deposit_token"), Uint128::new(500))];
+                Ok(config)
+            })
+            .unwrap();
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            rescue_msg("deposit_token"),
+        )
+        .unwrap_err();
+        assert_eq!(error_res, ContractError::RescueTokensDepositToken {});
+
+        // any other token is transferred to the recipient
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let res = execute(deps.as_mut(), env, info, rescue_msg("stray_token")).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("stray_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("recipient"),
+                    amount: Uint128::new(1_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "rescue_tokens"),
+                attr("token", "stray_token"),
+                attr("recipient", "recipient"),
+                attr("amount", "1000"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rotate_governance_tokens() {
+        let mut deps = th_setup(&[]);
+        let env = mock_env_at_block_height(1_000);
+
+        deps.querier.set_xmars_address(Addr::unchecked("gov_token"));
+        deps.querier.set_xmars_balance_at(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            1_000,
+            Uint128::zero(),
+        );
+
+        let rotate_msg = |voting_token_override: Option<&str>| ExecuteMsg::RotateGovernanceTokens {
+            address_provider: String::from("address_provider"),
+            voting_token_override: voting_token_override.map(String::from),
+        };
+
+        // only the council itself can rotate the governance tokens
+        let info = mock_info("anyone");
+        let error_res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            rotate_msg(Some("gov_token")),
+        )
+        .unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        // MARS is not a valid voting token
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            rotate_msg(Some("mars_token")),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::IncompatibleVotingToken {
+                voting_token: String::from("mars_token")
+            }
+        );
+
+        // neither is a token not answering the xMARS BalanceAt query, which here is the xMARS
+        // token resolved by the address provider
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res = execute(deps.as_mut(), env.clone(), info, rotate_msg(None)).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::IncompatibleVotingToken {
+                voting_token: String::from("xmars_token")
+            }
+        );
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.voting_token_override, None);
+
+        // a compatible voting token is set along with the address provider
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let res = execute(deps.as_mut(), env, info, rotate_msg(Some("gov_token"))).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "rotate_governance_tokens"),
+                attr("address_provider", "address_provider"),
+                attr("voting_token", "gov_token"),
+            ]
+        );
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            config.address_provider_address,
+            Addr::unchecked("address_provider")
+        );
+        assert_eq!(
+            config.voting_token_override,
+            Some(Addr::unchecked("gov_token"))
+        );
+    }
+
+    #[test]
+    fn test_rotate_governance_tokens_through_proposal() {
+        let mut deps = th_setup(&[]);
+
+        let rotate_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from(MOCK_CONTRACT_ADDR),
+            msg: to_binary(&ExecuteMsg::RotateGovernanceTokens {
+                address_provider: String::from("address_provider"),
+                voting_token_override: Some(String::from("gov_token")),
+            })
+            .unwrap(),
+            funds: vec![],
+        });
+        let proposal_id = th_submit_and_pass_proposal(
+            &mut deps,
+            1_000,
+            Some(vec![ProposalMessage {
+                execution_order: 0,
+                msg: rotate_msg.clone(),
+                memo: None,
+                route: ExecuteRoute::Local,
+            }]),
+        );
+
+        let proposal = PROPOSALS
+            .load(&deps.storage, U64Key::new(proposal_id))
+            .unwrap();
+        let env = mock_env_at_block_height(proposal.end_height + TEST_PROPOSAL_EFFECTIVE_DELAY + 1);
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("executor"), msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, rotate_msg);
+
+        // the council dispatching the proposal message rotates the tokens
+        deps.querier.set_xmars_address(Addr::unchecked("gov_token"));
+        deps.querier.set_xmars_balance_at(
+            Addr::unchecked(MOCK_CONTRACT_ADDR),
+            env.block.height,
+            Uint128::zero(),
+        );
+        let msg = ExecuteMsg::RotateGovernanceTokens {
+            address_provider: String::from("address_provider"),
+            voting_token_override: Some(String::from("gov_token")),
+        };
+        execute(deps.as_mut(), env, mock_info(MOCK_CONTRACT_ADDR), msg).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            config.voting_token_override,
+            Some(Addr::unchecked("gov_token"))
+        );
+    }
+
+    #[test]
+    fn test_submit_proposal_invalid_params() {
+        let mut deps = th_setup(&[]);
+
+        // *
+        // Invalid title
+        // *
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "a".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: Uint128::new(2_000_000),
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                response,
+                ContractError::TitleTooShort {
+                    min: MIN_TITLE_LENGTH
+                }
+            );
+        }
+
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: (0..100).map(|_| "a").collect::<String>(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: Uint128::new(2_000_000),
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                response,
+                ContractError::TitleTooLong {
+                    max: DEFAULT_TITLE_MAX_LENGTH
+                }
+            );
+        }
+
+        // *
+        // Invalid description
+        // *
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: "a".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: Uint128::new(2_000_000),
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                response,
+                ContractError::DescriptionTooShort {
+                    min: MIN_DESC_LENGTH
+                }
+            );
+        }
+
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: (0..1030).map(|_| "a").collect::<String>(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: Uint128::new(2_000_000),
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                response,
+                ContractError::DescriptionTooLong {
+                    max: DEFAULT_DESC_MAX_LENGTH
+                }
+            );
+        }
+
+        // *
+        // Invalid link
+        // *
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: Some("a".to_string()),
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: Uint128::new(2_000_000),
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                response,
+                ContractError::LinkTooShort {
+                    min: MIN_LINK_LENGTH
+                }
+            );
+        }
+
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: Some((0..150).map(|_| "a").collect::<String>()),
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: Uint128::new(2_000_000),
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                response,
+                ContractError::LinkTooLong {
+                    max: DEFAULT_LINK_MAX_LENGTH
+                }
+            );
+        }
+
+        // *
+        // Invalid deposit amount
+        // *
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT - Uint128::new(100),
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                response,
+                ContractError::InsufficientDeposit {
+                    required: TEST_PROPOSAL_REQUIRED_DEPOSIT
+                }
+            );
+        }
+
+        // *
+        // Invalid deposit currency
+        // *
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("other_token");
+            let res_error = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                res_error,
+                ContractError::InsufficientDeposit {
+                    required: TEST_PROPOSAL_REQUIRED_DEPOSIT
+                }
+            );
+        }
+
+        // *
+        // Memo too long
+        // *
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: Some(vec![ProposalMessage {
+                        execution_order: 0,
+                        msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                            contract_addr: String::from("other"),
+                            msg: Binary::from(br#"{"key": 123}"#),
+                            funds: vec![],
+                        }),
+                        memo: Some("a".repeat(MAX_MEMO_LENGTH as usize + 1)),
+                        route: ExecuteRoute::Local,
+                    }]),
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let res_error = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                res_error,
+                ContractError::MemoTooLong {
+                    max: MAX_MEMO_LENGTH
+                }
+            );
+        }
+
+        // *
+        // Empty messages (signaling proposals should omit them)
+        // *
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: Some(vec![]),
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let res_error = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                res_error,
+                ContractError::invalid_proposal("messages cannot be empty; omit for signaling")
+            );
+
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid Title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_submit_proposal() {
+        let mut deps = th_setup(&[]);
+        let submitter_address = Addr::unchecked("submitter");
+
+        // Submit Proposal without link or call data
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: submitter_address.to_string(),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env(MockEnvParams {
+            block_height: 100_000,
+            ..Default::default()
+        });
+        let info = mock_info("mars_token");
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let expected_end_height = 100_000 + TEST_PROPOSAL_VOTING_PERIOD;
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "submit_proposal"),
+                attr("submitter", "submitter"),
+                attr("proposal_id", 1.to_string()),
+                attr("proposal_end_height", expected_end_height.to_string()),
+            ]
+        );
+
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.proposal_count, 1);
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1_u64)).unwrap();
+        assert_eq!(proposal.proposal_id, 1);
+        assert_eq!(proposal.submitter_address, submitter_address);
+        assert_eq!(proposal.status, ProposalStatus::Active);
+        assert_eq!(proposal.for_votes, Uint128::new(0));
+        assert_eq!(proposal.against_votes, Uint128::new(0));
+        assert_eq!(proposal.for_voter_count, 0);
+        assert_eq!(proposal.against_voter_count, 0);
+        assert_eq!(proposal.start_height, 100_000);
+        assert_eq!(proposal.end_height, expected_end_height);
+        assert_eq!(proposal.title, "A valid title");
+        assert_eq!(proposal.description, "A valid description");
+        assert_eq!(proposal.link, None);
+        assert_eq!(proposal.messages, None);
+        assert_eq!(proposal.deposit_amount, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+
+        // Submit Proposal with link and call data
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: Some("https://www.avalidlink.com".to_string()),
+                messages: Some(vec![ProposalMessage {
+                    execution_order: 0,
+                    msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                        msg: to_binary(&ExecuteMsg::UpdateConfig {
+                            config: CreateOrUpdateConfig::default(),
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }),
+                    memo: None,
+                    route: ExecuteRoute::Local,
+                }]),
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: submitter_address.to_string(),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env(MockEnvParams {
+            block_height: 100_000,
+            ..Default::default()
+        });
+        let info = mock_info("mars_token");
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let expected_end_height = 100_000 + TEST_PROPOSAL_VOTING_PERIOD;
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "submit_proposal"),
+                attr("submitter", "submitter"),
+                attr("proposal_id", 2.to_string()),
+                attr("proposal_end_height", expected_end_height.to_string()),
+            ]
+        );
+
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.proposal_count, 2);
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(2_u64)).unwrap();
+        assert_eq!(
+            proposal.link,
+            Some("https://www.avalidlink.com".to_string())
+        );
+        assert_eq!(
+            proposal.messages,
+            Some(vec![ProposalMessage {
+                execution_order: 0,
+                msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    msg: to_binary(&ExecuteMsg::UpdateConfig {
+                        config: CreateOrUpdateConfig::default(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                memo: None,
+                route: ExecuteRoute::Local,
+            }])
+        );
+    }
+
+    #[test]
+    fn test_required_deposit_at_submission() {
+        let mut deps = th_setup(&[]);
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT + Uint128::new(500),
+        });
+        let env = mock_env_at_block_height(100_000);
+        execute(deps.as_mut(), env.clone(), mock_info("mars_token"), msg).unwrap();
+
+        // the requirement changes after the proposal was submitted
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_deposit = Uint128::new(50_000);
+                Ok(config)
+            })
+            .unwrap();
+
+        let proposal_info = query_proposal(deps.as_ref(), env, 1, None, false).unwrap();
+        assert_eq!(
+            proposal_info.deposit_amount,
+            TEST_PROPOSAL_REQUIRED_DEPOSIT + Uint128::new(500)
+        );
+        assert_eq!(
+            proposal_info.required_deposit_at_submission,
+            TEST_PROPOSAL_REQUIRED_DEPOSIT
+        );
+    }
+
+    #[test]
+    fn test_require_call_simulation() {
+        let mut deps = th_setup(&[]);
+        deps.querier
+            .set_contract(Addr::unchecked("target_contract"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.require_call_simulation = true;
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = |target: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: Some(vec![ProposalMessage {
+                        execution_order: 0,
+                        msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                            contract_addr: String::from(target),
+                            msg: Binary::from(br#"{"some":123}"#),
+                            funds: vec![],
+                        }),
+                        memo: None,
+                        route: ExecuteRoute::Local,
+                    }]),
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+
+        // a target without a contract is rejected
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        let error_res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            submit_msg("typo_contract"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::invalid_proposal(
+                "message target typo_contract is not a reachable contract"
+            )
+        );
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.proposal_count, 0);
+
+        // a target answering the probe is accepted
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg("target_contract")).unwrap();
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.proposal_count, 1);
+    }
+
+    #[test]
+    fn test_submit_proposal_configured_length_limits() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.description_max = 2048;
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = |description: String| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description,
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+
+        // description over the default limit but under the configured one is accepted
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg("a".repeat(2048))).unwrap();
+
+        // description over the configured limit is rejected
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("mars_token");
+        let error_res =
+            execute(deps.as_mut(), env, info, submit_msg("a".repeat(2049))).unwrap_err();
+        assert_eq!(error_res, ContractError::DescriptionTooLong { max: 2048 });
+    }
+
+    #[test]
+    fn test_reserve_and_finalize_proposal() {
+        let mut deps = th_setup(&[]);
+
+        let reserve_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::ReserveProposal {}).unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+
+        // reservations are disabled by default
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        let error_res = execute(deps.as_mut(), env, info, reserve_msg.clone()).unwrap_err();
+        assert_eq!(error_res, ContractError::ReservationsDisabled {});
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.reservation_period = 100;
+                Ok(config)
+            })
+            .unwrap();
+
+        // reserving takes a proposal id and locks the deposit
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        let res = execute(deps.as_mut(), env, info, reserve_msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "reserve_proposal"),
+                attr("submitter", "submitter"),
+                attr("proposal_id", "1"),
+                attr("reservation_expiration_height", "100100"),
+            ]
+        );
+
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.proposal_count, 1);
+        assert_eq!(global_state.locked_deposits, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+
+        let reservation: ProposalReservation = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(MockEnvParams::default()),
+                QueryMsg::Reservation { proposal_id: 1 },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            reservation,
+            ProposalReservation {
+                submitter_address: Addr::unchecked("submitter"),
+                deposit_amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                deposit_token: None,
+                co_sponsors: vec![],
+                expiration_height: 100_100,
+            }
+        );
+
+        let finalize_msg = ExecuteMsg::FinalizeProposal {
+            proposal_id: 1,
+            title: "A valid title".to_string(),
+            description: "A valid description".to_string(),
+            link: None,
+            messages: None,
+        };
+
+        // only the submitter can finalize
+        let env = mock_env_at_block_height(100_050);
+        let info = mock_info("someone_else");
+        let error_res = execute(deps.as_mut(), env, info, finalize_msg.clone()).unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        // finalized proposal keeps the reserved id and starts at finalization
+        let env = mock_env_at_block_height(100_050);
+        let info = mock_info("submitter");
+        let res = execute(deps.as_mut(), env, info, finalize_msg.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "finalize_proposal"),
+                attr("submitter", "submitter"),
+                attr("proposal_id", "1"),
+                attr(
+                    "proposal_end_height",
+                    (100_050 + TEST_PROPOSAL_VOTING_PERIOD).to_string()
+                ),
+            ]
+        );
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Active);
+        assert_eq!(proposal.submitter_address, Addr::unchecked("submitter"));
+        assert_eq!(proposal.title, "A valid title");
+        assert_eq!(proposal.start_height, 100_050);
+        assert_eq!(proposal.deposit_amount, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+        assert!(!RESERVATIONS.has(&deps.storage, U64Key::new(1)));
+
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+
+        // reservation cannot be finalized twice
+        let env = mock_env_at_block_height(100_051);
+        let info = mock_info("submitter");
+        execute(deps.as_mut(), env, info, finalize_msg).unwrap_err();
+    }
+
+    #[test]
+    fn test_reserve_proposal_expire() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.reservation_period = 100;
+                Ok(config)
+            })
+            .unwrap();
+
+        let reserve_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::ReserveProposal {}).unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, reserve_msg).unwrap();
+
+        let expire_msg = ExecuteMsg::ExpireReservation { proposal_id: 1 };
+
+        // cannot expire before the reservation period ends
+        let env = mock_env_at_block_height(100_100);
+        let info = mock_info("anyone");
+        let error_res = execute(deps.as_mut(), env, info, expire_msg.clone()).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::ExpireReservationNotExpired {
+                expiration_height: 100_100
+            }
+        );
+
+        // cannot finalize after the reservation period ends
+        let env = mock_env_at_block_height(100_101);
+        let info = mock_info("submitter");
+        let error_res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::FinalizeProposal {
+                proposal_id: 1,
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::FinalizeProposalReservationExpired {
+                expiration_height: 100_100
+            }
+        );
+
+        // anyone can expire the reservation, slashing the deposit
+        let env = mock_env_at_block_height(100_101);
+        let info = mock_info("anyone");
+        let res = execute(deps.as_mut(), env, info, expire_msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("mars_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("staking"),
+                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                })
+                .unwrap(),
+                funds: vec![],
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "expire_reservation"),
+                attr("proposal_id", "1"),
+            ]
+        );
+
+        assert!(!RESERVATIONS.has(&deps.storage, U64Key::new(1)));
+        assert!(!PROPOSALS.has(&deps.storage, U64Key::new(1)));
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::zero());
+    }
+
+    #[test]
+    fn test_co_sponsored_proposal() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(100_049, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.reservation_period = 100;
+                config.proposal_required_threshold = Decimal::from_ratio(51_u128, 100_u128);
+                config.proposal_required_quorum = Decimal::from_ratio(2_u128, 100_u128);
+                Ok(config)
+            })
+            .unwrap();
+
+        // the submitter reserves with part of the required deposit
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::ReserveProposal {}).unwrap(),
+                sender: String::from("submitter"),
+                amount: Uint128::new(6_000),
+            }),
+        )
+        .unwrap();
+
+        let finalize_msg = ExecuteMsg::FinalizeProposal {
+            proposal_id: 1,
+            title: "A valid title".to_string(),
+            description: "A valid description".to_string(),
+            link: None,
+            messages: None,
+        };
+
+        // cannot finalize until the deposit reaches the required deposit
+        let env = mock_env_at_block_height(100_010);
+        let info = mock_info("submitter");
+        let error_res = execute(deps.as_mut(), env, info, finalize_msg.clone()).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::InsufficientDeposit {
+                required: TEST_PROPOSAL_REQUIRED_DEPOSIT
+            }
+        );
+
+        let co_sponsor_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::CoSponsor { proposal_id: 1 }).unwrap(),
+            sender: String::from("co_sponsor"),
+            amount: Uint128::new(4_000),
+        });
+
+        // co-sponsor deposit needs to be in the reservation deposit token
+        let env = mock_env_at_block_height(100_020);
+        let info = mock_info("other_token");
+        let error_res = execute(deps.as_mut(), env, info, co_sponsor_msg.clone()).unwrap_err();
+        assert_eq!(error_res, ContractError::CoSponsorInvalidToken {});
+
+        let env = mock_env_at_block_height(100_020);
+        let info = mock_info("mars_token");
+        let res = execute(deps.as_mut(), env, info, co_sponsor_msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "co_sponsor_proposal"),
+                attr("sponsor", "co_sponsor"),
+                attr("proposal_id", "1"),
+                attr("amount", "4000"),
+                attr("deposit_amount", "10000"),
+            ]
+        );
+
+        let reservation = RESERVATIONS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(reservation.deposit_amount, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+        assert_eq!(
+            reservation.co_sponsors,
+            vec![(Addr::unchecked("co_sponsor"), Uint128::new(4_000))]
+        );
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+
+        // proposal activates once the deposit is complete
+        let env = mock_env_at_block_height(100_050);
+        let info = mock_info("submitter");
+        execute(deps.as_mut(), env, info, finalize_msg).unwrap();
+
+        let mut proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Active);
+        assert_eq!(proposal.deposit_amount, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+        assert_eq!(
+            proposal.co_sponsors,
+            vec![(Addr::unchecked("co_sponsor"), Uint128::new(4_000))]
+        );
+
+        proposal.for_votes = Uint128::new(11_000);
+        proposal.against_votes = Uint128::new(10_000);
+        PROPOSALS
+            .save(&mut deps.storage, U64Key::new(1), &proposal)
+            .unwrap();
+
+        // passed proposal refunds the submitter its own part of the deposit
+        let env = mock_env_at_block_height(proposal.end_height + 1);
+        let info = mock_info("sender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mars_token"),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: String::from("submitter"),
+                        amount: Uint128::new(6_000),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                1
+            )]
+        );
+
+        // co-sponsor part stays locked until it is claimed
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::new(4_000));
+
+        let claim_msg = ExecuteMsg::ClaimSponsorRefund { proposal_id: 1 };
+
+        let env = mock_env_at_block_height(proposal.end_height + 2);
+        let info = mock_info("submitter");
+        let error_res = execute(deps.as_mut(), env, info, claim_msg.clone()).unwrap_err();
+        assert_eq!(error_res, ContractError::NoSponsorRefund {});
+
+        let env = mock_env_at_block_height(proposal.end_height + 2);
+        let info = mock_info("co_sponsor");
+        let res = execute(deps.as_mut(), env, info, claim_msg.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("mars_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("co_sponsor"),
+                    amount: Uint128::new(4_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "claim_sponsor_refund"),
+                attr("proposal_id", "1"),
+                attr("sponsor", "co_sponsor"),
+                attr("amount", "4000"),
+            ]
+        );
+
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::zero());
+
+        // refund can only be claimed once
+        let env = mock_env_at_block_height(proposal.end_height + 3);
+        let info = mock_info("co_sponsor");
+        let error_res = execute(deps.as_mut(), env, info, claim_msg).unwrap_err();
+        assert_eq!(error_res, ContractError::NoSponsorRefund {});
+    }
+
+    #[test]
+    fn test_top_up_deposit() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(99_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_threshold = Decimal::from_ratio(51_u128, 100_u128);
+                config.proposal_required_quorum = Decimal::from_ratio(2_u128, 100_u128);
+                Ok(config)
+            })
+            .unwrap();
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(11_000),
+                against_votes: Uint128::new(10_000),
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        let top_up_msg = |sponsor: &str, amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::TopUpDeposit { proposal_id: 1 }).unwrap(),
+                sender: String::from(sponsor),
+                amount: Uint128::new(amount),
+            })
+        };
+
+        // top up needs to be in the proposal deposit token
+        let env = mock_env_at_block_height(100_010);
+        let info = mock_info("other_token");
+        let error_res = execute(deps.as_mut(), env, info, top_up_msg("alice", 3_000)).unwrap_err();
+        assert_eq!(error_res, ContractError::TopUpInvalidToken {});
+
+        let env = mock_env_at_block_height(100_010);
+        let info = mock_info("mars_token");
+        let res = execute(deps.as_mut(), env, info, top_up_msg("alice", 3_000)).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "top_up_deposit"),
+                attr("sponsor", "alice"),
+                attr("proposal_id", "1"),
+                attr("amount", "3000"),
+                attr("deposit_amount", "13000"),
+            ]
+        );
+
+        for (sponsor, amount) in [("bob", 1_000), ("alice", 1_000), ("submitter", 500)] {
+            let env = mock_env_at_block_height(100_020);
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, top_up_msg(sponsor, amount)).unwrap();
+        }
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.deposit_amount, Uint128::new(15_500));
+        assert_eq!(
+            proposal.co_sponsors,
+            vec![
+                (Addr::unchecked("alice"), Uint128::new(4_000)),
+                (Addr::unchecked("bob"), Uint128::new(1_000)),
+            ]
+        );
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::new(15_500));
+
+        // passed proposal refunds the submitter its own part of the deposit
+        let env = mock_env_at_block_height(100_101);
+        let info = mock_info("sender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mars_token"),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: String::from("submitter"),
+                        amount: Uint128::new(10_500),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                1
+            )]
+        );
+
+        // each contributor claims what it added
+        for (sponsor, amount) in [("alice", 4_000), ("bob", 1_000)] {
+            let env = mock_env_at_block_height(100_102);
+            let info = mock_info(sponsor);
+            let res = execute(
+                deps.as_mut(),
+                env,
+                info,
+                ExecuteMsg::ClaimSponsorRefund { proposal_id: 1 },
+            )
+            .unwrap();
+            assert_eq!(
+                res.messages,
+                vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mars_token"),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: String::from(sponsor),
+                        amount: Uint128::new(amount),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }))]
+            );
+        }
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::zero());
+
+        // ended proposals cannot be topped up
+        let env = mock_env_at_block_height(100_103);
+        let info = mock_info("mars_token");
+        let error_res = execute(deps.as_mut(), env, info, top_up_msg("alice", 1_000)).unwrap_err();
+        assert_eq!(error_res, ContractError::ProposalNotActive {});
+    }
+
+    #[test]
+    fn test_submit_proposal_council_self_calls() {
+        let mut deps = th_setup(&[]);
+
+        let submit_msg = |council_msg: ExecuteMsg| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: Some(vec![ProposalMessage {
+                        execution_order: 0,
+                        msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                            contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                            msg: to_binary(&council_msg).unwrap(),
+                            funds: vec![],
+                        }),
+                        memo: None,
+                        route: ExecuteRoute::Local,
+                    }]),
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+
+        // proposal submitting another proposal is rejected
+        {
+            let nested_submit_msg = submit_msg(ExecuteMsg::EndProposal { proposal_id: 1 });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let error_res =
+                execute(deps.as_mut(), env, info, submit_msg(nested_submit_msg)).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::invalid_proposal(
+                    "messages to the council can only be governance actions"
+                )
+            );
+        }
+
+        // proposal voting on another proposal is rejected
+        {
+            let vote_msg = ExecuteMsg::CastVote {
+                proposal_id: 1,
+                vote: ProposalVoteOption::For,
+            };
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let error_res = execute(deps.as_mut(), env, info, submit_msg(vote_msg)).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::invalid_proposal(
+                    "messages to the council can only be governance actions"
+                )
+            );
+        }
+
+        // proposal updating the council config is accepted
+        {
+            let update_config_msg = ExecuteMsg::UpdateConfig {
+                config: CreateOrUpdateConfig {
+                    proposal_voting_period: Some(5_000),
+                    ..Default::default()
+                },
+            };
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, submit_msg(update_config_msg)).unwrap();
+
+            let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+            assert_eq!(global_state.proposal_count, 1);
+        }
+
+        // proposal rescuing tokens is accepted
+        {
+            let rescue_tokens_msg = ExecuteMsg::RescueTokens {
+                token: String::from("stray_token"),
+                recipient: String::from("recipient"),
+                amount: Uint128::new(1_000),
+            };
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, submit_msg(rescue_tokens_msg)).unwrap();
+
+            let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+            assert_eq!(global_state.proposal_count, 2);
+        }
+
+        // proposal minting over the per proposal cap is rejected
+        {
+            let mint_treasury_msg = ExecuteMsg::MintTreasury {
+                recipient: String::from("recipient"),
+                amount: Uint128::new(1),
+            };
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let error_res =
+                execute(deps.as_mut(), env, info, submit_msg(mint_treasury_msg)).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::MintTreasuryProposalCapExceeded {
+                    cap: Uint128::zero()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_submit_proposal_wasm_msg_payload() {
+        let mut deps = th_setup(&[]);
+
+        let submit_msg = |payload: &[u8]| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: Some(vec![ProposalMessage {
+                        execution_order: 0,
+                        msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                            contract_addr: String::from("oracle"),
+                            msg: Binary::from(payload),
+                            funds: vec![],
+                        }),
+                        memo: None,
+                        route: ExecuteRoute::Local,
+                    }]),
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+
+        // non JSON payload is rejected
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("mars_token");
+        let error_res =
+            execute(deps.as_mut(), env, info, submit_msg(b"set_asset: uluna")).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::invalid_proposal("wasm message payload is not valid JSON")
+        );
+
+        // truncated JSON payload is rejected
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("mars_token");
+        let error_res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            submit_msg(br#"{"set_asset": {"asset": "#),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::invalid_proposal("wasm message payload is not valid JSON")
+        );
+
+        // valid JSON payload is accepted
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("mars_token");
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            submit_msg(br#"{"set_asset": {"asset": {"native": {"denom": "uluna"}}}}"#),
+        )
+        .unwrap();
+
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.proposal_count, 1);
+    }
+
+    #[test]
+    fn test_submit_proposal_category() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.allowed_categories = vec![String::from("treasury"), String::from("risk")];
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = |title: &str, category: Option<&str>| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: title.to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: category.map(String::from),
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+
+        // category not in the allowed set is rejected
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("mars_token");
+        let error_res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            submit_msg("A valid title", Some("marketing")),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::invalid_proposal("category is not allowed")
+        );
+
+        // allowed and missing categories are accepted
+        let submissions = [
+            ("Treasury proposal", Some("treasury")),
+            ("Uncategorized proposal", None),
+            ("Risk proposal", Some("risk")),
+            ("Another treasury proposal", Some("treasury")),
+        ];
+        for (title, category) in submissions {
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, submit_msg(title, category)).unwrap();
+        }
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.category, Some(String::from("treasury")));
+        let proposal_info = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(proposal_info.category, Some(String::from("treasury")));
+        let proposal_info = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            2,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(proposal_info.category, None);
+
+        // filtering by category only returns matching proposals
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            None,
+            None,
+            Some(String::from("treasury")),
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.proposal_count, 4);
+        let ids: Vec<u64> = res.proposal_list.iter().map(|p| p.proposal_id).collect();
+        assert_eq!(ids, vec![1, 4]);
+
+        // limit counts matching proposals only
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            Some(2),
+            Some(1),
+            Some(String::from("treasury")),
+            false,
+        )
+        .unwrap();
+        let ids: Vec<u64> = res.proposal_list.iter().map(|p| p.proposal_id).collect();
+        assert_eq!(ids, vec![4]);
+
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            None,
+            None,
+            Some(String::from("marketing")),
+            false,
+        )
+        .unwrap();
+        assert!(res.proposal_list.is_empty());
+    }
+
+    #[test]
+    fn test_min_quorum_power() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_xmars_total_supply_at(100_000, Uint128::new(80_000));
+        deps.querier
+            .set_vesting_total_voting_power_at(100_000, Uint128::new(20_000));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_threshold = Decimal::from_ratio(51_u128, 100_u128);
+                config.proposal_required_quorum = Decimal::from_ratio(2_u128, 100_u128);
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = |min_quorum_power: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: Some(Uint128::new(min_quorum_power)),
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+
+        // cannot require more votes than the current total voting power
+        let env = mock_env_at_block_height(100_000);
+        let error_res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("mars_token"),
+            submit_msg(100_001),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::invalid_proposal(
+                "min quorum power cannot exceed the total voting power"
+            )
+        );
+
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("mars_token"),
+            submit_msg(100_000),
+        )
+        .unwrap();
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.min_quorum_power, Some(Uint128::new(100_000)));
+
+        // both proposals meet the ratio quorum (21%) and threshold, only the second one meets
+        // its absolute quorum
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        for (id, min_quorum_power) in [(1, 25_000), (2, 21_000)] {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    for_votes: Uint128::new(11_000),
+                    against_votes: Uint128::new(10_000),
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
+            );
+            PROPOSALS
+                .update(
+                    &mut deps.storage,
+                    U64Key::new(id),
+                    |proposal| -> StdResult<_> {
+                        let mut proposal = proposal.unwrap();
+                        proposal.min_quorum_power = Some(Uint128::new(min_quorum_power));
+                        Ok(proposal)
+                    },
+                )
+                .unwrap();
+        }
+
+        let res: PowerNeededToPassResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env_at_block_height(100_000),
+                QueryMsg::PowerNeededToPass { proposal_id: 1 },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.power_needed, Uint128::new(4_000));
+
+        let env = mock_env_at_block_height(100_001);
+        for (id, expected_result) in [(1, "rejected"), (2, "passed")] {
+            let res = execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("sender"),
+                ExecuteMsg::EndProposal { proposal_id: id },
+            )
+            .unwrap();
+            assert_eq!(res.attributes[2], attr("proposal_result", expected_result));
+        }
+    }
+
+    #[test]
+    fn test_execution_deadline_blocks() {
+        let mut deps = th_setup(&[]);
+
+        let submit_msg = |execution_deadline_blocks: u64| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: Some(execution_deadline_blocks),
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+
+        // deadline needs to be positive
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        let error_res = execute(deps.as_mut(), env, info, submit_msg(0)).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::invalid_proposal("execution deadline blocks must be greater than zero")
+        );
+
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg(50)).unwrap();
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.execution_deadline_blocks, Some(50));
+
+        // passed proposals with and without a deadline tighter than the config expiration period
+        for (id, execution_deadline_blocks) in [(2, Some(50)), (3, None)] {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Passed,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
+            );
+            PROPOSALS
+                .update(
+                    &mut deps.storage,
+                    U64Key::new(id),
+                    |proposal| -> StdResult<Proposal> {
+                        let mut proposal = proposal.unwrap();
+                        proposal.execution_deadline_blocks = execution_deadline_blocks;
+                        Ok(proposal)
+                    },
+                )
+                .unwrap();
+        }
+
+        // the deadline is still open at its last block
+        let deadline_height = 100_000 + TEST_PROPOSAL_EFFECTIVE_DELAY + 50;
+        let res = query_is_executable(deps.as_ref(), mock_env_at_block_height(deadline_height), 2)
+            .unwrap();
+        assert_eq!(res.reason, ProposalExecutableReason::Ready);
+
+        // proposal with a deadline expires before the config expiration period ends
+        let env = mock_env_at_block_height(deadline_height + 1);
+        let info = mock_info("executer");
+        let error_res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::ExecuteProposal { proposal_id: 2 },
+        )
+        .unwrap_err();
+        assert_eq!(error_res, ContractError::ExecuteProposalExpired {});
+
+        let env = mock_env_at_block_height(deadline_height + 1);
+        let info = mock_info("executer");
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::ExecuteProposal { proposal_id: 3 },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_accepted_deposit_tokens() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(50);
+                config.proposal_required_threshold = Decimal::percent(50);
+                config.accepted_deposit_tokens = vec![
+                    (Addr::unchecked("usdc_token"), Uint128::new(500)),
+                    (Addr::unchecked("atom_token"), Uint128::new(200)),
+                ];
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = |submitter: &str, amount: u128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: format!("Proposal by {}", submitter),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from(submitter),
+                amount: Uint128::new(amount),
+            })
+        };
+
+        // accepted tokens need their own minimum
+        let env = mock_env_at_block_height(100_000);
+        let error_res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("usdc_token"),
+            submit_msg("submitter", 499),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::InsufficientDeposit {
+                required: Uint128::new(500)
+            }
+        );
+
+        // tokens that are not accepted are rejected
+        let error_res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("other_token"),
+            submit_msg("submitter", 100_000),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::InsufficientDeposit {
+                required: TEST_PROPOSAL_REQUIRED_DEPOSIT
+            }
+        );
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("usdc_token"),
+            submit_msg("submitter", 500),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("atom_token"),
+            submit_msg("other_submitter", 200),
+        )
+        .unwrap();
+
+        let mut proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.deposit_token, Some(Addr::unchecked("usdc_token")));
+        let proposal_info = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            2,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            proposal_info.deposit_token,
+            Some(Addr::unchecked("atom_token"))
+        );
+
+        // locked deposits only count MARS
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::zero());
+
+        // first proposal passes, second one is rejected
+        proposal.for_votes = Uint128::new(800);
+        PROPOSALS
+            .save(&mut deps.storage, U64Key::new(1), &proposal)
+            .unwrap();
+        deps.querier
+            .set_xmars_total_supply_at(proposal.voting_power_snapshot_block, Uint128::new(1_000));
+
+        let env = mock_env_at_block_height(proposal.end_height + 1);
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("ender"),
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "passed"));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("usdc_token"),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: String::from("submitter"),
+                        amount: Uint128::new(500),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                1
+            )]
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("ender"),
+            ExecuteMsg::EndProposal { proposal_id: 2 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "rejected"));
+        let slash_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("atom_token"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("staking"),
+                amount: Uint128::new(200),
+            })
+            .unwrap(),
+            funds: vec![],
+        });
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(slash_msg.clone(), 2)]
+        );
+
+        // failed slashes in tokens other than MARS are kept as pending deposits
+        reply(
+            deps.as_mut(),
+            env,
+            Reply {
+                id: 2,
+                result: ContractResult::Err("transfer failed".to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            PENDING_DEPOSITS
+                .load(&deps.storage, U64Key::new(2))
+                .unwrap(),
+            slash_msg
+        );
+        assert!(PENDING_SLASHES.may_load(&deps.storage).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_submit_proposal_privileged_token_calls() {
+        let mut deps = th_setup(&[]);
+
+        let submit_msg = |msg: CosmosMsg| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: Some(vec![ProposalMessage {
+                        execution_order: 0,
+                        msg,
+                        memo: None,
+                        route: ExecuteRoute::Local,
+                    }]),
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+        let update_minter_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("mars_token"),
+            msg: Binary::from(br#"{"update_minter":{"new_minter":"attacker"}}"#.to_vec()),
+            funds: vec![],
+        });
+
+        // proposal changing the MARS token minter is rejected
+        {
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let error_res = execute(
+                deps.as_mut(),
+                env,
+                info,
+                submit_msg(update_minter_msg.clone()),
+            )
+            .unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::invalid_proposal(
+                    "privileged calls to the MARS token are not allowed"
+                )
+            );
+        }
+
+        // proposal changing the MARS token admin is rejected
+        {
+            let update_admin_msg = CosmosMsg::Wasm(WasmMsg::UpdateAdmin {
+                contract_addr: String::from("mars_token"),
+                admin: String::from("attacker"),
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            let error_res =
+                execute(deps.as_mut(), env, info, submit_msg(update_admin_msg)).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::invalid_proposal(
+                    "privileged calls to the MARS token are not allowed"
+                )
+            );
+        }
+
+        // proposal transferring MARS is accepted
+        {
+            let transfer_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("mars_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("recipient"),
+                    amount: Uint128::new(1_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            });
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, submit_msg(transfer_msg)).unwrap();
+
+            let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+            assert_eq!(global_state.proposal_count, 1);
+        }
+
+        // proposal changing the MARS token minter is accepted when allowed in config
+        {
+            let mut config = CONFIG.load(&deps.storage).unwrap();
+            config.allow_privileged_token_calls = true;
+            CONFIG.save(&mut deps.storage, &config).unwrap();
+
+            let env = mock_env(MockEnvParams::default());
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, submit_msg(update_minter_msg)).unwrap();
+
+            let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+            assert_eq!(global_state.proposal_count, 2);
+        }
+    }
+
+    #[test]
+    fn test_submit_proposal_submitter_cooldown() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.submitter_cooldown = 100;
+                Ok(config)
+            })
+            .unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+
+        // first submission succeeds
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, msg.clone()).unwrap();
+
+        let last_submission = LAST_SUBMISSION
+            .load(&deps.storage, &Addr::unchecked("submitter"))
+            .unwrap();
+        assert_eq!(last_submission, 100_000);
+
+        // rapid second submission from the same submitter is rejected
+        let env = mock_env_at_block_height(100_099);
+        let info = mock_info("mars_token");
+        let error_res = execute(deps.as_mut(), env, info, msg.clone()).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::SubmitProposalCooldownNotEnded {
+                next_submission_height: 100_100
+            }
+        );
+
+        // another submitter is not affected
+        let other_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("other_submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env_at_block_height(100_099);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, other_msg).unwrap();
+
+        // submission succeeds once the cooldown has elapsed
+        let env = mock_env_at_block_height(100_100);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, msg.clone()).unwrap();
+
+        let last_submission = LAST_SUBMISSION
+            .load(&deps.storage, &Addr::unchecked("submitter"))
+            .unwrap();
+        assert_eq!(last_submission, 100_100);
+
+        // a cooldown too large to add to the last submission height is an error, not a panic
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.submitter_cooldown = u64::MAX;
+                Ok(config)
+            })
+            .unwrap();
+        let env = mock_env_at_block_height(100_200);
+        let info = mock_info("mars_token");
+        let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::Overflow(OverflowError::new(
+                OverflowOperation::Add,
+                100_100,
+                u64::MAX
+            ))
+        );
+    }
+
+    #[test]
+    fn test_submit_proposal_height_overflow() {
+        let mut deps = th_setup(&[]);
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+
+        // periods too large to add to the submission height are an error, not a panic
+        let periods = [(u64::MAX, TEST_PROPOSAL_VOTING_PERIOD), (0, u64::MAX)];
+        for (discussion_period, proposal_voting_period) in periods {
+            CONFIG
+                .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                    config.discussion_period = discussion_period;
+                    config.proposal_voting_period = proposal_voting_period;
+                    Ok(config)
+                })
+                .unwrap();
+            let env = mock_env_at_block_height(100_000);
+            let info = mock_info("mars_token");
+            let error_res = execute(deps.as_mut(), env, info, msg.clone()).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::Overflow(OverflowError::new(
+                    OverflowOperation::Add,
+                    100_000,
+                    u64::MAX
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn test_submit_proposal_dedupe_window() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.dedupe_window = 100;
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = |title: &str| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: title.to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+        };
+
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg("A valid title")).unwrap();
+
+        // same title within the window is rejected
+        let env = mock_env_at_block_height(100_100);
+        let info = mock_info("mars_token");
+        let error_res = execute(deps.as_mut(), env, info, submit_msg("A valid title")).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::DuplicateProposal { proposal_id: 1 }
+        );
+
+        // another title within the window is accepted
+        let env = mock_env_at_block_height(100_100);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg("Another valid title")).unwrap();
+
+        // same title is accepted again once the window has passed
+        let env = mock_env_at_block_height(100_101);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg("A valid title")).unwrap();
+
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.proposal_count, 3);
+    }
+
+    #[test]
+    fn test_amend_proposal() {
+        let mut deps = th_setup(&[]);
+
+        let mut proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+        // discussion period until voting opens
+        proposal.vote_start_height = 100_050;
+        PROPOSALS
+            .save(&mut deps.storage, U64Key::new(1), &proposal)
+            .unwrap();
+
+        let msg = ExecuteMsg::AmendProposal {
+            proposal_id: 1,
+            description: Some(String::from("An amended description")),
+            link: Some(String::from("https://spec.example.com")),
+        };
+
+        // only the submitter can amend
+        {
+            let env = mock_env_at_block_height(100_010);
+            let error_res =
+                execute(deps.as_mut(), env, mock_info("someone"), msg.clone()).unwrap_err();
+            assert_eq!(error_res, MarsError::Unauthorized {}.into());
+        }
+
+        // amended content is validated
+        {
+            let invalid_msg = ExecuteMsg::AmendProposal {
+                proposal_id: 1,
+                description: Some(String::from("a")),
+                link: None,
+            };
+            let env = mock_env_at_block_height(100_010);
+            let error_res =
+                execute(deps.as_mut(), env, mock_info("submitter"), invalid_msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::DescriptionTooShort {
+                    min: MIN_DESC_LENGTH
+                }
+            );
+        }
+
+        // amend during the discussion period
+        let env = mock_env_at_block_height(100_010);
+        let res = execute(deps.as_mut(), env, mock_info("submitter"), msg.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "amend_proposal"), attr("proposal_id", "1")]
+        );
+
+        let amended_proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(amended_proposal.description, "An amended description");
+        assert_eq!(
+            amended_proposal.link,
+            Some(String::from("https://spec.example.com"))
+        );
+        assert_eq!(amended_proposal.title, proposal.title);
+        assert_eq!(amended_proposal.amended_at, Some(100_010));
+
+        // cannot amend once voting opens
+        let env = mock_env_at_block_height(100_050);
+        let error_res = execute(deps.as_mut(), env, mock_info("submitter"), msg).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::AmendProposalVotingStarted {
+                vote_start_height: 100_050
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_proposal_history() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_threshold = Decimal::percent(50);
+                config.proposal_required_quorum = Decimal::percent(50);
+                Ok(config)
+            })
+            .unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env_at_block_height(100_000);
+        execute(deps.as_mut(), env, mock_info("mars_token"), msg).unwrap();
+
+        let mut proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        proposal.for_votes = Uint128::new(100);
+        PROPOSALS
+            .save(&mut deps.storage, U64Key::new(1), &proposal)
+            .unwrap();
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(proposal.voting_power_snapshot_block, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        let end_height = proposal.end_height + 1;
+        let msg = ExecuteMsg::EndProposal { proposal_id: 1 };
+        let env = mock_env_at_block_height(end_height);
+        execute(deps.as_mut(), env, mock_info("sender"), msg).unwrap();
+
+        let execute_height = proposal.end_height + TEST_PROPOSAL_EFFECTIVE_DELAY + 1;
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let env = mock_env_at_block_height(execute_height);
+        execute(deps.as_mut(), env, mock_info("executer"), msg).unwrap();
+
+        let res = query_proposal_history(deps.as_ref(), 1).unwrap();
+        assert_eq!(
+            res,
+            ProposalHistoryResponse {
+                proposal_id: 1,
+                history: vec![
+                    ProposalStatusChange {
+                        status: ProposalStatus::Active,
+                        height: 100_000,
+                    },
+                    ProposalStatusChange {
+                        status: ProposalStatus::Passed,
+                        height: end_height,
+                    },
+                    ProposalStatusChange {
+                        status: ProposalStatus::Executed,
+                        height: execute_height,
+                    },
+                ],
+            }
+        );
+
+        // history is bounded per proposal
+        for i in 3..(MAX_STATUS_HISTORY_ENTRIES + 5) {
+            record_status_change(
+                &mut deps.storage,
+                &mock_env_at_block_height(execute_height + i),
+                1,
+                ProposalStatus::Executed,
+            )
+            .unwrap();
+        }
+        let res = query_proposal_history(deps.as_ref(), 1).unwrap();
+        assert_eq!(res.history.len() as u64, MAX_STATUS_HISTORY_ENTRIES);
+
+        // unknown proposal has no history
+        let res = query_proposal_history(deps.as_ref(), 2).unwrap();
+        assert_eq!(res.history, vec![]);
+    }
+
+    #[test]
+    fn test_query_proposal_by_hash() {
+        let mut deps = th_setup(&[]);
+
+        for submitter in ["submitter", "other_submitter"] {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from(submitter),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            });
+            let env = mock_env_at_block_height(100_000);
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+
+        let first_proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        let second_proposal = PROPOSALS.load(&deps.storage, U64Key::new(2)).unwrap();
+
+        let mut hasher = Sha256::new();
+        hasher.update(b"A valid title");
+        hasher.update(b"submitter");
+        hasher.update(first_proposal.start_height.to_be_bytes());
+        let first_hash = hasher.finalize().to_vec();
+        assert_eq!(proposal_title_hash(&first_proposal), first_hash);
+
+        // same title from another submitter gets a distinct hash
+        let second_hash = proposal_title_hash(&second_proposal);
+        assert_ne!(first_hash, second_hash);
+
+        let res = query_proposal_by_hash(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            Binary::from(first_hash),
+        )
+        .unwrap();
+        assert_eq!(res.proposal_id, 1);
+        assert_eq!(res.submitter_address, Addr::unchecked("submitter"));
+
+        let res = query_proposal_by_hash(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            Binary::from(second_hash),
+        )
+        .unwrap();
+        assert_eq!(res.proposal_id, 2);
+        assert_eq!(res.submitter_address, Addr::unchecked("other_submitter"));
+
+        // unknown hash
+        query_proposal_by_hash(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            Binary::from(vec![0u8; 32]),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_invalid_cast_votes() {
+        let mut deps = th_setup(&[]);
+        let voter_address = Addr::unchecked("valid_voter");
+        let invalid_voter_address = Addr::unchecked("invalid_voter");
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_balance_at(voter_address, 99_999, Uint128::new(100));
+        deps.querier
+            .set_xmars_balance_at(invalid_voter_address, 99_999, Uint128::zero());
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        let active_proposal_id = 1_u64;
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: active_proposal_id,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        let executed_proposal_id = 2_u64;
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: executed_proposal_id,
+                status: ProposalStatus::Executed,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        // *
+        // voting on a non-existent proposal should fail
+        // *
+        {
+            let msg = ExecuteMsg::CastVote {
+                proposal_id: 3,
+                vote: ProposalVoteOption::For,
+            };
+            let env = mock_env(MockEnvParams {
+                block_height: 100_001,
+                ..Default::default()
+            });
+            let info = mock_info("valid_voter");
+            let res_error = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(
+                res_error,
+                StdError::NotFound {
+                    kind: "mars_core::council::Proposal".to_string(),
+                }
+                .into()
+            );
+        }
+
+        // *
+        // voting on an inactive proposal should fail
+        // *
+        {
+            let msg = ExecuteMsg::CastVote {
+                proposal_id: executed_proposal_id,
+                vote: ProposalVoteOption::For,
+            };
+            let env = mock_env(MockEnvParams {
+                block_height: 100_001,
+                ..Default::default()
+            });
+            let info = mock_info("valid_voter");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(response, ContractError::ProposalNotActive {});
+        }
+
+        // *
+        // voting after proposal end should fail
+        // *
+        {
+            let msg = ExecuteMsg::CastVote {
+                proposal_id: active_proposal_id,
+                vote: ProposalVoteOption::For,
+            };
+            let env = mock_env(MockEnvParams {
+                block_height: 100_200,
+                ..Default::default()
+            });
+            let info = mock_info("valid_voter");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(response, ContractError::VoteVotingPeriodEnded {});
+        }
+
+        // *
+        // voting without any voting power should fail
+        // *
+        {
+            let msg = ExecuteMsg::CastVote {
+                proposal_id: active_proposal_id,
+                vote: ProposalVoteOption::For,
+            };
+            let env = mock_env(MockEnvParams {
+                block_height: 100_001,
+                ..Default::default()
+            });
+            let info = mock_info("invalid_voter");
+            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(response, ContractError::VoteNoVotingPower { block: 99_999 });
+        }
+    }
+
+    #[test]
+    fn test_cast_vote() {
+        // setup
+        let mut deps = th_setup(&[]);
+        let voter_address = Addr::unchecked("voter");
+
+        let active_proposal_id = 1_u64;
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_balance_at(voter_address.clone(), 99_999, Uint128::new(100));
+
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_vesting_voting_power_at(voter_address.clone(), 99_999, Uint128::new(23));
+
+        let active_proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: active_proposal_id,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        // Add another vote on an extra proposal to voter to validate voting on multiple proposals
+        // is valid
+        PROPOSAL_VOTES
+            .save(
+                &mut deps.storage,
+                (U64Key::new(4_u64), &voter_address),
+                &ProposalVote {
+                    option: ProposalVoteOption::Against,
+                    power: Uint128::new(100),
+                    weighted_power: Uint128::new(100),
+                },
+            )
+            .unwrap();
+
+        // Valid vote for
+        let msg = ExecuteMsg::CastVote {
+            proposal_id: active_proposal_id,
+            vote: ProposalVoteOption::For,
+        };
+
+        let env = mock_env(MockEnvParams {
+            block_height: active_proposal.start_height + 1,
+            ..Default::default()
+        });
+        let info = mock_info("voter");
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            vec![
+                attr("action", "cast_vote"),
+                attr("proposal_id", active_proposal_id.to_string()),
+                attr("voter", "voter"),
+                attr("vote", "for"),
+                attr("voting_power", 123.to_string()), // 100 (free) + 23 (locked)
+                attr("weighted_voting_power", 123.to_string()),
+            ],
+            res.attributes
+        );
+
+        let proposal = PROPOSALS
+            .load(&deps.storage, U64Key::new(active_proposal_id))
+            .unwrap();
+        assert_eq!(proposal.for_votes, Uint128::new(123));
+        assert_eq!(proposal.against_votes, Uint128::new(0));
+
+        let proposal_vote = PROPOSAL_VOTES
+            .load(
+                &deps.storage,
+                (U64Key::new(active_proposal_id), &voter_address),
+            )
+            .unwrap();
+
+        assert_eq!(proposal_vote.option, ProposalVoteOption::For);
+        assert_eq!(proposal_vote.power, Uint128::new(123));
+
+        // Voting again with same address should fail
+        let msg = ExecuteMsg::CastVote {
+            proposal_id: active_proposal_id,
+            vote: ProposalVoteOption::For,
+        };
+
+        let env = mock_env(MockEnvParams {
+            block_height: active_proposal.start_height + 1,
+            ..Default::default()
+        });
+        let info = mock_info("voter");
+        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(response, ContractError::VoteUserAlreadyVoted {});
+
+        // Valid against vote
+        {
+            let msg = ExecuteMsg::CastVote {
+                proposal_id: active_proposal_id,
+                vote: ProposalVoteOption::Against,
+            };
+
+            deps.querier.set_xmars_balance_at(
+                Addr::unchecked("voter2"),
+                active_proposal.start_height - 1,
+                Uint128::new(200),
+            );
+
+            let env = mock_env(MockEnvParams {
+                block_height: active_proposal.start_height + 1,
+                ..Default::default()
+            });
+            let info = mock_info("voter2");
+            let res = execute(deps.as_mut(), env, info, msg).unwrap();
+            assert_eq!(
+                vec![
+                    attr("action", "cast_vote"),
+                    attr("proposal_id", active_proposal_id.to_string()),
+                    attr("voter", "voter2"),
+                    attr("vote", "against"),
+                    attr("voting_power", 200.to_string()),
+                    attr("weighted_voting_power", 200.to_string()),
+                ],
+                res.attributes
+            );
+        }
+
+        // Extra for and against votes to check aggregates are computed correctly
+        deps.querier.set_xmars_balance_at(
+            Addr::unchecked("voter3"),
+            active_proposal.start_height - 1,
+            Uint128::new(300),
+        );
+
+        deps.querier.set_xmars_balance_at(
+            Addr::unchecked("voter4"),
+            active_proposal.start_height - 1,
+            Uint128::new(400),
+        );
+
+        {
+            let msg = ExecuteMsg::CastVote {
+                proposal_id: active_proposal_id,
+                vote: ProposalVoteOption::For,
+            };
+            let env = mock_env(MockEnvParams {
+                block_height: active_proposal.start_height + 1,
+                ..Default::default()
+            });
+            let info = mock_info("voter3");
+            execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+
+        {
+            let msg = ExecuteMsg::CastVote {
+                proposal_id: active_proposal_id,
+                vote: ProposalVoteOption::Against,
+            };
+            let env = mock_env(MockEnvParams {
+                block_height: active_proposal.start_height + 1,
+                ..Default::default()
+            });
+            let info = mock_info("voter4");
+            execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+
+        let proposal = PROPOSALS
+            .load(&deps.storage, U64Key::new(active_proposal_id))
+            .unwrap();
+        assert_eq!(proposal.for_votes, Uint128::new(123 + 300));
+        assert_eq!(proposal.against_votes, Uint128::new(200 + 400));
+        assert_eq!(proposal.for_voter_count, 2);
+        assert_eq!(proposal.against_voter_count, 2);
+    }
+
+    #[test]
+    fn test_cast_votes() {
+        let mut deps = th_setup(&[]);
+        let voter_address = Addr::unchecked("voter");
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_balance_at(voter_address.clone(), 99_999, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        for id in 1..=3 {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    start_height: 100_000,
+                    end_height: 100_100,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let env = mock_env(MockEnvParams {
+            block_height: 100_001,
+            ..Default::default()
+        });
+
+        // batch larger than the max should fail
+        {
+            let msg = ExecuteMsg::CastVotes {
+                votes: (1..=(MAX_VOTES_PER_BATCH as u64 + 1))
+                    .map(|id| (id, ProposalVoteOption::For))
+                    .collect(),
+            };
+            let info = mock_info("voter");
+            let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::BatchTooLarge {
+                    max: MAX_VOTES_PER_BATCH
+                }
+            );
+        }
+
+        // vote on all active proposals in one message
+        let msg = ExecuteMsg::CastVotes {
+            votes: vec![
+                (1, ProposalVoteOption::For),
+                (2, ProposalVoteOption::Against),
+                (3, ProposalVoteOption::For),
+            ],
+        };
+        let info = mock_info("voter");
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "cast_vote"),
+                attr("proposal_id", "1"),
+                attr("voter", "voter"),
+                attr("vote", "for"),
+                attr("voting_power", "100"),
+                attr("weighted_voting_power", "100"),
+                attr("action", "cast_vote"),
+                attr("proposal_id", "2"),
+                attr("voter", "voter"),
+                attr("vote", "against"),
+                attr("voting_power", "100"),
+                attr("weighted_voting_power", "100"),
+                attr("action", "cast_vote"),
+                attr("proposal_id", "3"),
+                attr("voter", "voter"),
+                attr("vote", "for"),
+                attr("voting_power", "100"),
+                attr("weighted_voting_power", "100"),
+            ]
+        );
+
+        let proposal_1 = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal_1.for_votes, Uint128::new(100));
+        let proposal_2 = PROPOSALS.load(&deps.storage, U64Key::new(2)).unwrap();
+        assert_eq!(proposal_2.against_votes, Uint128::new(100));
+        let proposal_3 = PROPOSALS.load(&deps.storage, U64Key::new(3)).unwrap();
+        assert_eq!(proposal_3.for_votes, Uint128::new(100));
+
+        // a single invalid vote fails the whole batch
+        let msg = ExecuteMsg::CastVotes {
+            votes: vec![(1, ProposalVoteOption::For)],
+        };
+        let info = mock_info("voter");
+        let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(error_res, ContractError::VoteUserAlreadyVoted {});
+    }
+
+    #[test]
+    fn test_cast_vote_early_vote_bonus() {
+        let mut deps = th_setup(&[]);
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.early_vote_bonus = Decimal::percent(50);
+        CONFIG.save(&mut deps.storage, &config).unwrap();
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        for voter in ["early_voter", "late_voter", "last_voter"] {
+            deps.querier
+                .set_xmars_balance_at(Addr::unchecked(voter), 99_999, Uint128::new(100));
+        }
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        // (voter, vote height, option, expected weighted power)
+        let votes = vec![
+            // voting opens: full bonus
+            ("early_voter", 100_000, ProposalVoteOption::For, 150),
+            // three quarters through the voting period: a quarter of the bonus
+            ("late_voter", 100_075, ProposalVoteOption::Against, 112),
+            // voting period end: no bonus
+            ("last_voter", 100_100, ProposalVoteOption::Against, 100),
+        ];
+        for (voter, block_height, vote, expected_weighted_power) in votes {
+            let msg = ExecuteMsg::CastVote {
+                proposal_id: 1,
+                vote,
+            };
+            let env = mock_env(MockEnvParams {
+                block_height,
+                ..Default::default()
+            });
+            execute(deps.as_mut(), env, mock_info(voter), msg).unwrap();
+
+            let proposal_vote = PROPOSAL_VOTES
+                .load(&deps.storage, (U64Key::new(1), &Addr::unchecked(voter)))
+                .unwrap();
+            assert_eq!(proposal_vote.power, Uint128::new(100));
+            assert_eq!(
+                proposal_vote.weighted_power,
+                Uint128::new(expected_weighted_power)
+            );
+        }
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.for_votes, Uint128::new(150));
+        assert_eq!(proposal.against_votes, Uint128::new(212));
+    }
+
+    #[test]
+    fn test_end_proposal_early_vote_bonus() {
+        let mut deps = th_setup(&[]);
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(50);
+                config.proposal_required_threshold = Decimal::percent(50);
+                config.early_vote_bonus = Decimal::percent(100);
+                Ok(config)
+            })
+            .unwrap();
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(99_999, Uint128::new(1_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier.set_xmars_balance_at(
+            Addr::unchecked("early_voter"),
+            99_999,
+            Uint128::new(400),
+        );
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("late_voter"), 99_999, Uint128::new(450));
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        let msg = ExecuteMsg::CastVote {
+            proposal_id: 1,
+            vote: ProposalVoteOption::For,
+        };
+        let env = mock_env_at_block_height(100_000);
+        execute(deps.as_mut(), env, mock_info("early_voter"), msg).unwrap();
+
+        // 800 weighted votes would meet the quorum, but only 400 of the 1_000 voting power voted
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.for_votes, Uint128::new(800));
+        assert_eq!(proposal.early_vote_bonus_votes, Uint128::new(400));
+        let res = query_power_needed_to_pass(deps.as_ref(), 1).unwrap();
+        assert_eq!(res.power_needed, Uint128::new(100));
+
+        // the bonus still counts towards the threshold: 800 for against 450 passes even though
+        // the raw for votes are fewer than the against votes
+        let msg = ExecuteMsg::CastVote {
+            proposal_id: 1,
+            vote: ProposalVoteOption::Against,
+        };
+        let env = mock_env_at_block_height(100_100);
+        execute(deps.as_mut(), env, mock_info("late_voter"), msg).unwrap();
+        let res = query_power_needed_to_pass(deps.as_ref(), 1).unwrap();
+        assert_eq!(res.power_needed, Uint128::zero());
+
+        let env = mock_env_at_block_height(100_101);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("ender"),
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "passed"));
+    }
+
+    #[test]
+    fn test_cast_vote_min_vote_power() {
+        let mut deps = th_setup(&[]);
+        let mut config = CONFIG.load(&deps.storage).unwrap();
+        config.min_vote_power = Uint128::new(100);
+        CONFIG.save(&mut deps.storage, &config).unwrap();
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("dust_voter"), 99_999, Uint128::new(99));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("voter"), 99_999, Uint128::new(100));
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        let msg = ExecuteMsg::CastVote {
+            proposal_id: 1,
+            vote: ProposalVoteOption::For,
+        };
+        let env = mock_env_at_block_height(100_001);
+
+        // voter below the floor is rejected
+        let error_res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("dust_voter"),
+            msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::VoteBelowMinimumPower {
+                min_vote_power: Uint128::new(100)
+            }
+        );
+
+        // voter at the floor is accepted
+        execute(deps.as_mut(), env, mock_info("voter"), msg).unwrap();
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.for_votes, Uint128::new(100));
+        assert!(PROPOSAL_VOTES
+            .may_load(
+                &deps.storage,
+                (U64Key::new(1), &Addr::unchecked("dust_voter"))
+            )
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = th_setup(&[]);
+
+        // seed config, global state, proposals and votes in the format stored by a council
+        // deployed before they had any of their current fields
+        deps.storage.set(
+            CONFIG.as_slice(),
+            br#"{
+                "address_provider_address":"address_provider",
+                "proposal_voting_period":2000,
+                "proposal_effective_delay":200,
+                "proposal_expiration_period":300,
+                "proposal_required_deposit":"10000",
+                "proposal_required_quorum":"0.1",
+                "proposal_required_threshold":"0.05"
+            }"#,
+        );
+        deps.storage
+            .set(GLOBAL_STATE.as_slice(), br#"{"proposal_count":5}"#);
+        let proposals = vec![
+            (1, "active", "10000"),
+            (2, "passed", "10000"),
+            (3, "rejected", "10000"),
+            (4, "executed", "10000"),
+            (5, "active", "12000"),
+        ];
+        for (id, status, deposit_amount) in proposals {
+            let proposal = format!(
+                r#"{{
+                    "proposal_id":{},
+                    "submitter_address":"submitter",
+                    "status":"{}",
+                    "for_votes":"150",
+                    "against_votes":"50",
+                    "start_height":100,
+                    "end_height":2100,
+                    "title":"A valid title",
+                    "description":"A valid description",
+                    "link":null,
+                    "messages":null,
+                    "deposit_amount":"{}"
+                }}"#,
+                id, status, deposit_amount
+            );
+            deps.storage
+                .set(&PROPOSALS.key(U64Key::new(id)), proposal.as_bytes());
+        }
+        let votes = vec![
+            ("voter1", r#"{"option":"for","power":"100"}"#),
+            ("voter2", r#"{"option":"for","power":"50"}"#),
+            ("voter3", r#"{"option":"against","power":"50"}"#),
+        ];
+        for (voter, vote) in votes {
+            deps.storage.set(
+                &PROPOSAL_VOTES.key((U64Key::new(1), &Addr::unchecked(voter))),
+                vote.as_bytes(),
+            );
+        }
+
+        let status_count = |deps: Deps, status: ProposalStatus| -> u64 {
+            PROPOSAL_COUNT_BY_STATUS
+                .may_load(deps.storage, &status.to_string())
+                .unwrap()
+                .unwrap_or(0)
+        };
+
+        // migrate from another contract fails
+        {
+            set_contract_version(&mut deps.storage, "crates.io:other-contract", "1.0.0").unwrap();
+            let msg = MigrateMsg {
+                start_after: None,
+                limit: None,
+            };
+            let error_res =
+                migrate(deps.as_mut(), mock_env(MockEnvParams::default()), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::MigrateInvalidContract {
+                    contract: "crates.io:other-contract".to_string()
+                }
+            );
+            set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+        }
+
+        // migrate from a newer version fails
+        {
+            set_contract_version(&mut deps.storage, CONTRACT_NAME, "999.0.0").unwrap();
+            let msg = MigrateMsg {
+                start_after: None,
+                limit: None,
+            };
+            let error_res =
+                migrate(deps.as_mut(), mock_env(MockEnvParams::default()), msg).unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::MigrateInvalidVersion {
+                    version: "999.0.0".to_string()
+                }
+            );
+            set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+        }
+
+        // the limit counts votes, so the votes of proposal 1 take two pages and it is only
+        // indexed once all of them are backfilled
+        let msg = MigrateMsg {
+            start_after: None,
+            limit: Some(2),
+        };
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(MockEnvParams::default()),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("indexed_count", "0"),
+                attr("migrating_proposal_id", "1"),
+            ]
+        );
+        assert_eq!(status_count(deps.as_ref(), ProposalStatus::Active), 0);
+        assert_eq!(
+            MIGRATION_VOTE_CURSORS
+                .load(&deps.storage, U64Key::new(1))
+                .unwrap(),
+            Addr::unchecked("voter2")
+        );
+
+        let res = migrate(deps.as_mut(), mock_env(MockEnvParams::default()), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("indexed_count", "1"),
+                attr("last_proposal_id", "1"),
+            ]
+        );
+        assert_eq!(status_count(deps.as_ref(), ProposalStatus::Active), 1);
+        assert!(!MIGRATION_VOTE_CURSORS.has(&deps.storage, U64Key::new(1)));
+
+        // config fields missing from the stored config take their defaults and are stored
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.proposal_voting_period, 2000);
+        assert_eq!(config.proposal_required_deposit, Uint128::new(10000));
+        assert_eq!(config.title_max, DEFAULT_TITLE_MAX_LENGTH);
+        assert_eq!(config.description_max, DEFAULT_DESC_MAX_LENGTH);
+        assert_eq!(config.snapshot_offset, 1);
+        assert_eq!(config.surge_multiplier, Decimal::one());
+        assert_eq!(
+            config.slashed_deposit_destination,
+            SlashedDepositDestination::Staking
+        );
+        assert_eq!(config.quorum_basis, QuorumBasis::TotalSupply);
+        assert!(!config.require_call_simulation);
+        let stored_config =
+            String::from_utf8(deps.storage.get(CONFIG.as_slice()).unwrap()).unwrap();
+        assert!(stored_config.contains("\"require_call_simulation\":false"));
+
+        // only the deposit of the migrated active proposal is locked
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.proposal_count, 5);
+        assert_eq!(global_state.locked_deposits, Uint128::new(10000));
+        assert_eq!(global_state.total_minted, Uint128::zero());
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.vote_start_height, 100);
+        assert_eq!(proposal.voting_power_snapshot_block, 99);
+        assert_eq!(proposal.required_deposit_at_submission, Uint128::new(10000));
+        assert_eq!(proposal.deposit_status, DepositStatus::Locked);
+        assert_eq!(proposal.deposit_token, None);
+        assert_eq!(proposal.for_voter_count, 2);
+        assert_eq!(proposal.against_voter_count, 1);
+        assert!(proposal.co_sponsors.is_empty());
+        assert!(!proposal.auto_execute);
+        for voter in ["voter1", "voter2", "voter3"] {
+            let vote = PROPOSAL_VOTES
+                .load(&deps.storage, (U64Key::new(1), &Addr::unchecked(voter)))
+                .unwrap();
+            assert_eq!(vote.weighted_power, vote.power);
+        }
+
+        // second page
+        let msg = MigrateMsg {
+            start_after: Some(1),
+            limit: Some(3),
+        };
+        let res = migrate(
+            deps.as_mut(),
+            mock_env(MockEnvParams::default()),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("indexed_count", "3"),
+                attr("last_proposal_id", "4"),
+            ]
+        );
+        assert_eq!(status_count(deps.as_ref(), ProposalStatus::Active), 1);
+        assert_eq!(status_count(deps.as_ref(), ProposalStatus::Passed), 1);
+        assert_eq!(status_count(deps.as_ref(), ProposalStatus::Rejected), 1);
+        assert_eq!(status_count(deps.as_ref(), ProposalStatus::Executed), 1);
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(2)).unwrap();
+        assert_eq!(proposal.deposit_status, DepositStatus::Refunded);
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(3)).unwrap();
+        assert_eq!(proposal.deposit_status, DepositStatus::Slashed);
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(4)).unwrap();
+        assert_eq!(proposal.deposit_status, DepositStatus::Refunded);
+
+        // running the same page again does not count proposals or deposits twice
+        let res = migrate(deps.as_mut(), mock_env(MockEnvParams::default()), msg).unwrap();
+        assert_eq!(res.attributes[1], attr("indexed_count", "0"));
+        assert_eq!(status_count(deps.as_ref(), ProposalStatus::Passed), 1);
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::new(10000));
+
+        // last page
+        let msg = MigrateMsg {
+            start_after: Some(4),
+            limit: Some(3),
+        };
+        let res = migrate(deps.as_mut(), mock_env(MockEnvParams::default()), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "migrate"),
+                attr("indexed_count", "1"),
+                attr("last_proposal_id", "5"),
+            ]
+        );
+        assert_eq!(status_count(deps.as_ref(), ProposalStatus::Active), 2);
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::new(22000));
+
+        for proposal_id in 1..=5 {
+            assert!(PROPOSALS_BY_SUBMITTER.has(
+                &deps.storage,
+                (&Addr::unchecked("submitter"), U64Key::new(proposal_id))
+            ));
+        }
+
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+    }
+
+    #[test]
+    fn test_query_proposals() {
+        // Arrange
+        let mut deps = th_setup(&[]);
+
+        let active_proposal_1_id = 1_u64;
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: active_proposal_1_id,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        let active_proposal_2_id = 2_u64;
+        let msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("test_address"),
+            msg: Binary::from(br#"{"some":123}"#),
+            funds: vec![],
+        });
+        let messages = Option::from(vec![ProposalMessage {
+            execution_order: 0,
+            msg: msg.clone(),
+            memo: None,
+            route: ExecuteRoute::Local,
+        }]);
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: active_proposal_2_id,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                messages,
+                ..Default::default()
+            },
+        );
+
+        let global_state = GlobalState {
+            proposal_count: 2_u64,
+            locked_deposits: Uint128::zero(),
+            total_minted: Uint128::zero(),
+        };
+        GLOBAL_STATE.save(&mut deps.storage, &global_state).unwrap();
+        // Assert corectly sorts asc
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            None,
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.proposal_count, 2);
+        assert_eq!(res.proposal_list.len(), 2);
+        assert_eq!(res.proposal_list[0].proposal_id, active_proposal_1_id);
+        assert_eq!(res.proposal_list[1].proposal_id, active_proposal_2_id);
+        assert_eq!(res.proposal_list[1].messages.clone().unwrap()[0].msg, msg);
+
+        // Assert start != 0
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            Some(2),
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.proposal_count, 2);
+        assert_eq!(res.proposal_list.len(), 1);
+        assert_eq!(res.proposal_list[0].proposal_id, active_proposal_2_id);
+
+        // Assert start > length of collection
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            Some(99),
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.proposal_count, 2);
+        assert_eq!(res.proposal_list.len(), 0);
+
+        // Assert limit
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            None,
+            Some(1),
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.proposal_count, 2);
+        assert_eq!(res.proposal_list.len(), 1);
+        assert_eq!(res.proposal_list[0].proposal_id, active_proposal_1_id);
+
+        // Assert limit greater than length of collection
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            None,
+            Some(99),
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.proposal_count, 2);
+        assert_eq!(res.proposal_list.len(), 2);
+    }
+
+    #[test]
+    fn test_archive_proposals() {
+        let mut deps = th_setup(&[]);
+
+        let proposals = [
+            (1, ProposalStatus::Executed, 1_000),
+            (2, ProposalStatus::Rejected, 1_000),
+            (3, ProposalStatus::Cancelled, 1_000),
+            // passed and expired
+            (4, ProposalStatus::Passed, 1_000),
+            // passed and still executable later
+            (5, ProposalStatus::Passed, 9_900),
+            (6, ProposalStatus::Active, 10_100),
+        ];
+        for (id, status, end_height) in proposals {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status,
+                    end_height,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let env = mock_env_at_block_height(10_000);
+        let msg = ExecuteMsg::ArchiveProposals {
+            before_id: 7,
+            start_after: None,
+            limit: Some(2),
+        };
+
+        // only the emergency owner or the council can archive
+        let error_res =
+            execute(deps.as_mut(), env.clone(), mock_info("anyone"), msg.clone()).unwrap_err();
+        assert_eq!(error_res, ContractError::Mars(MarsError::Unauthorized {}));
+
+        // proposals are scanned one page at a time
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_CONTRACT_ADDR),
+            msg,
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "archive_proposals"),
+                attr("before_id", "7"),
+                attr("archived_count", "2"),
+                attr("last_proposal_id", "2"),
+            ]
+        );
+
+        let msg = ExecuteMsg::ArchiveProposals {
+            before_id: 7,
+            start_after: Some(2),
+            limit: None,
+        };
+        let res = execute(deps.as_mut(), env, mock_info(MOCK_CONTRACT_ADDR), msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "archive_proposals"),
+                attr("before_id", "7"),
+                attr("archived_count", "2"),
+                attr("last_proposal_id", "6"),
+            ]
+        );
+
+        let ids = |response: ProposalsListResponse| -> Vec<u64> {
+            response
+                .proposal_list
+                .iter()
+                .map(|proposal| proposal.proposal_id)
+                .collect()
+        };
+
+        // archived proposals are only returned when asked for
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            None,
+            None,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(ids(res), vec![5, 6]);
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            None,
+            None,
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(ids(res), vec![1, 2, 3, 4, 5, 6]);
+        let res = query_proposals(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            Some(3),
+            Some(3),
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(ids(res), vec![3, 4, 5]);
+
+        query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            None,
+            false,
+        )
+        .unwrap_err();
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            None,
+            true,
+        )
+        .unwrap();
+        assert_eq!(res.status, ProposalStatus::Executed);
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            5,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.status, ProposalStatus::Passed);
+    }
+
+    #[test]
+    fn test_query_proposal_messages() {
+        let mut deps = th_setup(&[]);
+
+        let proposal_message = |execution_order: u64| ProposalMessage {
+            execution_order,
+            msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: format!("contract_{}", execution_order),
+                msg: Binary::from(br#"{"key": 123}"#),
+                funds: vec![],
+            }),
+            memo: None,
+            route: ExecuteRoute::Local,
+        };
+        // messages stored in reverse execution order
+        let message_count = PROPOSAL_INFO_MAX_MESSAGES as u64 + 5;
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                messages: Some((0..message_count).rev().map(proposal_message).collect()),
+                ..Default::default()
+            },
+        );
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 2,
+                messages: Some(vec![proposal_message(0)]),
+                ..Default::default()
+            },
+        );
+
+        // proposal info omits messages when there are many
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.messages, None);
+        assert_eq!(res.message_count, message_count);
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            2,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.messages, Some(vec![proposal_message(0)]));
+        assert_eq!(res.message_count, 1);
+
+        // paginate messages in execution order
+        let res = query_proposal_messages(deps.as_ref(), 1, None, Some(6)).unwrap();
+        assert_eq!(res.proposal_id, 1);
+        assert_eq!(
+            res.messages,
+            (0..6).map(proposal_message).collect::<Vec<_>>()
+        );
+
+        let res = query_proposal_messages(deps.as_ref(), 1, Some(5), Some(6)).unwrap();
+        assert_eq!(
+            res.messages,
+            (6..12).map(proposal_message).collect::<Vec<_>>()
+        );
+
+        let res = query_proposal_messages(deps.as_ref(), 1, Some(11), None).unwrap();
+        assert_eq!(
+            res.messages,
+            (12..message_count)
+                .map(proposal_message)
+                .collect::<Vec<_>>()
+        );
+
+        let res = query_proposal_messages(deps.as_ref(), 1, Some(message_count), None).unwrap();
+        assert_eq!(res.messages, vec![]);
+    }
+
+    #[test]
+    fn test_query_proposal() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("voter"), 99_999, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        let proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        let msg = ExecuteMsg::CastVote {
+            proposal_id: 1,
+            vote: ProposalVoteOption::Against,
+        };
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("voter");
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        // without voter
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.proposal_id, 1);
+        assert_eq!(res.proposal_id_str, "1");
+        assert_eq!(res.title, proposal.title);
+        assert_eq!(res.voting_power_snapshot_block, proposal.start_height - 1);
+        assert_eq!(res.against_votes, Uint128::new(100));
+        assert_eq!(res.for_voter_count, 0);
+        assert_eq!(res.against_voter_count, 1);
+        assert_eq!(res.caller_vote, None);
+
+        // with a voter who voted
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            Some(String::from("voter")),
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            res.caller_vote,
+            Some(ProposalVoteResponse {
+                voter_address: String::from("voter"),
+                option: ProposalVoteOption::Against,
+                power: Uint128::new(100),
+                weighted_power: Uint128::new(100),
+            })
+        );
+
+        // with a voter who didn't vote
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            Some(String::from("non_voter")),
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.caller_vote, None);
+    }
+
+    #[test]
+    fn test_query_auto_expired_proposal() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        // proposal 1 is still active after its voting period, 2 passed
+        for (id, status) in [(1, ProposalStatus::Active), (2, ProposalStatus::Passed)] {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status,
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let reported_statuses = |deps: Deps, block_height: u64| -> Vec<ProposalStatus> {
+            let env = mock_env_at_block_height(block_height);
+            let proposal_info = query_proposal(deps, env.clone(), 1, None, false).unwrap();
+            let res = query_proposals(deps, env, None, None, None, false).unwrap();
+            let mut statuses = vec![proposal_info.status];
+            statuses.extend(
+                res.proposal_list
+                    .into_iter()
+                    .map(|proposal| proposal.status),
+            );
+            statuses
+        };
+
+        // disabled by default
+        assert_eq!(
+            reported_statuses(deps.as_ref(), 200_000),
+            vec![
+                ProposalStatus::Active,
+                ProposalStatus::Active,
+                ProposalStatus::Passed
+            ]
+        );
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.auto_expire_grace = 100;
+                Ok(config)
+            })
+            .unwrap();
+
+        // still reported active during the grace period
+        assert_eq!(
+            reported_statuses(deps.as_ref(), 100_100),
+            vec![
+                ProposalStatus::Active,
+                ProposalStatus::Active,
+                ProposalStatus::Passed
+            ]
+        );
+
+        // reported expired after it, stored status is unchanged
+        assert_eq!(
+            reported_statuses(deps.as_ref(), 100_101),
+            vec![
+                ProposalStatus::Expired,
+                ProposalStatus::Expired,
+                ProposalStatus::Passed
+            ]
+        );
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Active);
+        assert_eq!(proposal.deposit_status, DepositStatus::Locked);
+
+        // the proposal can still be ended, which settles its deposit
+        execute(
+            deps.as_mut(),
+            mock_env_at_block_height(100_101),
+            mock_info("sender"),
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(
+            reported_statuses(deps.as_ref(), 100_101),
+            vec![
+                ProposalStatus::Rejected,
+                ProposalStatus::Rejected,
+                ProposalStatus::Passed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snapshot_offset() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.snapshot_offset = 100;
+                config.proposal_required_quorum = Decimal::percent(50);
+                config.proposal_required_threshold = Decimal::percent(50);
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg.clone()).unwrap();
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.voting_power_snapshot_block, 99_900);
+
+        // voting power is only set at the snapshot block, so both vote and end succeed only if
+        // they measure voting power there
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("voter"), 99_900, Uint128::new(100));
+        deps.querier
+            .set_xmars_total_supply_at(99_900, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("voter");
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::CastVote {
+                proposal_id: 1,
+                vote: ProposalVoteOption::For,
+            },
+        )
+        .unwrap();
+
+        let env = mock_env_at_block_height(proposal.end_height + 1);
+        let info = mock_info("sender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "passed"));
+
+        // snapshot block does not underflow for a proposal submitted before the offset
+        let env = mock_env_at_block_height(50);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg).unwrap();
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(2)).unwrap();
+        assert_eq!(proposal.voting_power_snapshot_block, 0);
+    }
+
+    #[test]
+    fn test_discussion_period() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.discussion_period = 500;
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg).unwrap();
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.start_height, 100_000);
+        assert_eq!(proposal.vote_start_height, 100_500);
+        assert_eq!(proposal.end_height, 100_500 + TEST_PROPOSAL_VOTING_PERIOD);
+        assert_eq!(proposal.voting_power_snapshot_block, 100_499);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("voter"), 100_499, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        let cast_vote_msg = ExecuteMsg::CastVote {
+            proposal_id: 1,
+            vote: ProposalVoteOption::For,
+        };
+
+        // voting is rejected during the discussion period
+        let env = mock_env_at_block_height(100_499);
+        let info = mock_info("voter");
+        let error_res = execute(deps.as_mut(), env, info, cast_vote_msg.clone()).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::VoteVotingNotStarted {
+                vote_start_height: 100_500
+            }
+        );
+
+        // voting is accepted once the discussion period is over
+        let env = mock_env_at_block_height(100_500);
+        let info = mock_info("voter");
+        execute(deps.as_mut(), env, info, cast_vote_msg).unwrap();
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.for_votes, Uint128::new(100));
+    }
+
+    #[test]
+    fn test_query_voter_power() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("voter"), 99_999, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier.set_vesting_voting_power_at(
+            Addr::unchecked("voter"),
+            99_999,
+            Uint128::new(50),
+        );
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        let res = query_voter_power(deps.as_ref(), 1, String::from("voter")).unwrap();
+        assert_eq!(
+            res,
+            VoterPowerResponse {
+                proposal_id: 1,
+                voter_address: Addr::unchecked("voter"),
+                voting_power_snapshot_block: 99_999,
+                voting_power: Uint128::new(150),
+            }
+        );
+
+        // non-existent proposal
+        let error_res = query_voter_power(deps.as_ref(), 2, String::from("voter")).unwrap_err();
+        assert_eq!(error_res, StdError::not_found("Proposal 2"));
+    }
+
+    #[test]
+    fn test_query_power_needed_to_pass() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_threshold = Decimal::percent(60);
+                config.proposal_required_quorum = Decimal::percent(30);
+                Ok(config)
+            })
+            .unwrap();
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(99_999, Uint128::new(80_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_vesting_total_voting_power_at(99_999, Uint128::new(20_000));
+
+        let mut proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(6_000),
+                against_votes: Uint128::new(14_000),
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+
+        // under quorum: 10_000 more votes are needed for quorum but for votes have to go above
+        // 60% of 14_000 against votes, i.e. over 21_000
+        let res = query_power_needed_to_pass(deps.as_ref(), 1).unwrap();
+        assert_eq!(
+            res,
+            PowerNeededToPassResponse {
+                proposal_id: 1,
+                total_voting_power: Uint128::new(100_000),
+                power_needed: Uint128::new(15_001),
+            }
+        );
+
+        // one vote short of the needed power
+        proposal.for_votes = Uint128::new(21_000);
+        PROPOSALS
+            .save(&mut deps.storage, U64Key::new(1), &proposal)
+            .unwrap();
+        let res = query_power_needed_to_pass(deps.as_ref(), 1).unwrap();
+        assert_eq!(res.power_needed, Uint128::new(1));
+
+        // already passing
+        proposal.for_votes = Uint128::new(21_001);
+        PROPOSALS
+            .save(&mut deps.storage, U64Key::new(1), &proposal)
+            .unwrap();
+        let res = query_power_needed_to_pass(deps.as_ref(), 1).unwrap();
+        assert_eq!(res.power_needed, Uint128::zero());
+
+        // proposal that is not active
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 2,
+                status: ProposalStatus::Passed,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+        let error_res = query_power_needed_to_pass(deps.as_ref(), 2).unwrap_err();
+        assert_eq!(error_res, StdError::generic_err("Proposal is not active"));
+    }
+
+    #[test]
+    fn test_invalid_end_proposals() {
+        let mut deps = th_setup(&[]);
+
+        let active_proposal_id = 1_u64;
+        let executed_proposal_id = 2_u64;
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(99_999, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_vesting_total_voting_power_at(99_999, Uint128::zero());
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: active_proposal_id,
+                status: ProposalStatus::Active,
+                end_height: 100_000,
+                ..Default::default()
+            },
+        );
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: executed_proposal_id,
+                status: ProposalStatus::Executed,
+                ..Default::default()
+            },
+        );
+
+        // cannot end a proposal that has not ended its voting period
+        let msg = ExecuteMsg::EndProposal {
+            proposal_id: active_proposal_id,
+        };
+        let env = mock_env(MockEnvParams {
+            block_height: 100_000,
+            ..Default::default()
+        });
+        let info = mock_info("sender");
+        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(response, ContractError::EndProposalVotingPeriodNotEnded {});
+
+        // cannot end a non active proposal
+        let msg = ExecuteMsg::EndProposal {
+            proposal_id: executed_proposal_id,
+        };
+        let env = mock_env(MockEnvParams {
+            block_height: 100_001,
+            ..Default::default()
+        });
+        let info = mock_info("sender");
+        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(response, ContractError::ProposalNotActive {});
+    }
+
+    #[test]
+    fn test_end_proposal() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_vesting_total_voting_power_at(99_999, Uint128::zero());
+
+        let proposal_threshold = Decimal::from_ratio(51_u128, 100_u128);
+        let proposal_quorum = Decimal::from_ratio(2_u128, 100_u128);
+        let proposal_end_height = 100_000u64;
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_threshold = proposal_threshold;
+                config.proposal_required_quorum = proposal_quorum;
+                Ok(config)
+            })
+            .unwrap();
+
+        // end passed proposal
+        let initial_passed_proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(11_000),
+                against_votes: Uint128::new(10_000),
+                start_height: 90_000,
+                end_height: proposal_end_height + 1,
+                ..Default::default()
+            },
+        );
+
+        let msg = ExecuteMsg::EndProposal { proposal_id: 1 };
+
+        let env = mock_env(MockEnvParams {
+            block_height: initial_passed_proposal.end_height + 1,
+            ..Default::default()
+        });
+        let info = mock_info("sender");
+
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "end_proposal"),
+                attr("proposal_id", 1.to_string()),
+                attr("proposal_result", "passed"),
+            ]
+        );
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mars_token"),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: String::from("submitter"),
+                        amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                    })
+                    .unwrap(),
+                }),
+                1,
+            ),]
+        );
+
+        let final_passed_proposal = PROPOSALS.load(&deps.storage, U64Key::new(1u64)).unwrap();
+        assert_eq!(final_passed_proposal.status, ProposalStatus::Passed);
+
+        // end rejected proposal (no quorum)
+        let initial_passed_proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 2,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(11),
+                against_votes: Uint128::new(10),
+                end_height: proposal_end_height + 1,
+                start_height: 90_000,
+                ..Default::default()
+            },
+        );
+
+        let msg = ExecuteMsg::EndProposal { proposal_id: 2 };
+
+        let env = mock_env(MockEnvParams {
+            block_height: initial_passed_proposal.end_height + 1,
+            ..Default::default()
+        });
+        let info = mock_info("sender");
+
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "end_proposal"),
+                attr("proposal_id", 2.to_string()),
+                attr("proposal_result", "rejected"),
+            ]
+        );
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mars_token"),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: String::from("staking"),
+                        amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                2,
+            )]
+        );
+
+        let final_passed_proposal = PROPOSALS.load(&deps.storage, U64Key::new(2_u64)).unwrap();
+        assert_eq!(final_passed_proposal.status, ProposalStatus::Rejected);
+
+        // end rejected proposal (no threshold)
+        let initial_passed_proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 3,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(10_000),
+                against_votes: Uint128::new(11_000),
+                start_height: 90_000,
+                end_height: proposal_end_height + 1,
+                ..Default::default()
+            },
+        );
+
+        let msg = ExecuteMsg::EndProposal { proposal_id: 3 };
+
+        let env = mock_env(MockEnvParams {
+            block_height: initial_passed_proposal.end_height + 1,
+            ..Default::default()
+        });
+        let info = mock_info("sender");
+
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "end_proposal"),
+                attr("proposal_id", 3.to_string()),
+                attr("proposal_result", "rejected"),
+            ]
+        );
+
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mars_token"),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: String::from("staking"),
+                        amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                3,
+            )]
+        );
+
+        let final_passed_proposal = PROPOSALS.load(&deps.storage, U64Key::new(3_u64)).unwrap();
+        assert_eq!(final_passed_proposal.status, ProposalStatus::Rejected);
+    }
+
+    #[test]
+    fn test_end_proposal_caches_result() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(80_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_vesting_total_voting_power_at(89_999, Uint128::new(20_000));
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(15_000),
+                against_votes: Uint128::new(5_000),
+                start_height: 90_000,
+                end_height: 100_000,
+                ..Default::default()
+            },
+        );
+
+        // no result while the proposal is active
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.final_quorum, None);
+        assert_eq!(res.final_threshold, None);
+        assert_eq!(res.final_total_voting_power, None);
+
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("sender");
+        let msg = ExecuteMsg::EndProposal { proposal_id: 1 };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let expected_quorum = Decimal::from_ratio(20_000_u128, 100_000_u128);
+        let expected_threshold = Decimal::from_ratio(15_000_u128, 20_000_u128);
+        let expected_total_voting_power = Uint128::new(100_000);
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.final_quorum, Some(expected_quorum));
+        assert_eq!(proposal.final_threshold, Some(expected_threshold));
+        assert_eq!(
+            proposal.final_total_voting_power,
+            Some(expected_total_voting_power)
+        );
+
+        // cached result is returned without querying the voting power again
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(1));
+        let res = query_proposal(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            1,
+            None,
+            false,
+        )
+        .unwrap();
+        assert_eq!(res.final_quorum, Some(expected_quorum));
+        assert_eq!(res.final_threshold, Some(expected_threshold));
+        assert_eq!(
+            res.final_total_voting_power,
+            Some(expected_total_voting_power)
+        );
+    }
+
+    #[test]
+    fn test_end_proposal_failed_deposit_transfer() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_threshold = Decimal::from_ratio(51_u128, 100_u128);
+                config.proposal_required_quorum = Decimal::from_ratio(2_u128, 100_u128);
+                Ok(config)
+            })
+            .unwrap();
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(11_000),
+                against_votes: Uint128::new(10_000),
+                start_height: 90_000,
+                end_height: 100_000,
+                ..Default::default()
+            },
+        );
+
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("sender");
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+
+        let refund_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("mars_token"),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            })
+            .unwrap(),
+            funds: vec![],
+        });
+
+        // failed refund is recorded and the proposal keeps its final status
+        let res = reply(
+            deps.as_mut(),
+            env.clone(),
+            Reply {
+                id: 1,
+                result: ContractResult::Err("transfer failed".to_string()),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "record_pending_deposit"),
+                attr("proposal_id", "1"),
+                attr("error", "transfer failed"),
+            ]
+        );
+        assert_eq!(
+            PENDING_DEPOSITS
+                .load(&deps.storage, U64Key::new(1))
+                .unwrap(),
+            refund_msg
+        );
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Passed);
+        assert_eq!(proposal.deposit_status, DepositStatus::Locked);
+        let res = query_total_locked_deposits(deps.as_ref()).unwrap();
+        assert_eq!(res.amount, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+
+        // anyone can retry the transfer
+        let msg = ExecuteMsg::ClaimPendingDeposit { proposal_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone"), msg.clone()).unwrap();
+        assert_eq!(res.messages, vec![SubMsg::new(refund_msg)]);
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "claim_pending_deposit"),
+                attr("proposal_id", "1"),
+            ]
+        );
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.deposit_status, DepositStatus::Refunded);
+        let res = query_total_locked_deposits(deps.as_ref()).unwrap();
+        assert_eq!(res.amount, Uint128::zero());
+        assert!(PENDING_DEPOSITS
+            .may_load(&deps.storage, U64Key::new(1))
+            .unwrap()
+            .is_none());
+
+        // pending deposit can only be claimed once
+        execute(deps.as_mut(), env, mock_info("anyone"), msg).unwrap_err();
+    }
+
+    #[test]
+    fn test_deposit_status() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_threshold = Decimal::from_ratio(51_u128, 100_u128);
+                config.proposal_required_quorum = Decimal::from_ratio(2_u128, 100_u128);
+                config.emergency_owner = Some(Addr::unchecked("guardian"));
+                Ok(config)
+            })
+            .unwrap();
+
+        // proposal 1 passes, 2 is rejected and 3 is cancelled
+        for (id, for_votes) in [(1, 11_000), (2, 0), (3, 11_000)] {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    for_votes: Uint128::new(for_votes),
+                    against_votes: Uint128::new(10_000),
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let deposit_status = |deps: &OwnedDeps<_, _, _>, id: u64| -> DepositStatus {
+            let proposal_info: ProposalInfo = from_binary(
+                &query(
+                    deps.as_ref(),
+                    mock_env(MockEnvParams::default()),
+                    QueryMsg::Proposal {
+                        proposal_id: id,
+                        voter: None,
+                        include_archived: None,
+                    },
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            proposal_info.deposit_status
+        };
+
+        // deposits are locked while proposals are active
+        for id in 1..4 {
+            assert_eq!(deposit_status(&deps, id), DepositStatus::Locked);
+        }
+
+        let env = mock_env_at_block_height(100_001);
+        for id in 1..3 {
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("sender"),
+                ExecuteMsg::EndProposal { proposal_id: id },
+            )
+            .unwrap();
+        }
+        execute(
+            deps.as_mut(),
+            env,
+            mock_info("guardian"),
+            ExecuteMsg::ForceCancelProposal { proposal_id: 3 },
+        )
+        .unwrap();
+
+        assert_eq!(deposit_status(&deps, 1), DepositStatus::Refunded);
+        assert_eq!(deposit_status(&deps, 2), DepositStatus::Slashed);
+        assert_eq!(deposit_status(&deps, 3), DepositStatus::Slashed);
+    }
+
+    #[test]
+    fn test_sweep_pending_slashes() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        // nothing to sweep
+        let env = mock_env_at_block_height(100_001);
+        let msg = ExecuteMsg::SweepPendingSlashes {};
+        let error_res =
+            execute(deps.as_mut(), env.clone(), mock_info("anyone"), msg.clone()).unwrap_err();
+        assert_eq!(error_res, ContractError::NoPendingSlashes {});
+
+        // two rejected proposals whose slashed deposit transfer fails
+        for id in 1..3 {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
+            );
+            execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info("sender"),
+                ExecuteMsg::EndProposal { proposal_id: id },
+            )
+            .unwrap();
+
+            let res = reply(
+                deps.as_mut(),
+                env.clone(),
+                Reply {
+                    id,
+                    result: ContractResult::Err("staking paused".to_string()),
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                res.attributes,
+                vec![
+                    attr("action", "record_pending_slash"),
+                    attr("proposal_id", id.to_string()),
+                    attr("error", "staking paused"),
+                ]
+            );
+            let proposal = PROPOSALS.load(&deps.storage, U64Key::new(id)).unwrap();
+            assert_eq!(proposal.status, ProposalStatus::Rejected);
+        }
+
+        assert_eq!(
+            PENDING_SLASHES.load(&deps.storage).unwrap(),
+            TEST_PROPOSAL_REQUIRED_DEPOSIT * Uint128::new(2)
+        );
+        assert!(PENDING_DEPOSITS
+            .may_load(&deps.storage, U64Key::new(1))
+            .unwrap()
+            .is_none());
+
+        // anyone can sweep the accumulated slashed deposits to staking
+        let res = execute(deps.as_mut(), env.clone(), mock_info("anyone"), msg.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("mars_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("staking"),
+                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT * Uint128::new(2),
+                })
+                .unwrap(),
+                funds: vec![],
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "sweep_pending_slashes"),
+                attr(
+                    "amount",
+                    (TEST_PROPOSAL_REQUIRED_DEPOSIT * Uint128::new(2)).to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            PENDING_SLASHES.load(&deps.storage).unwrap(),
+            Uint128::zero()
+        );
+
+        // pending slashes can only be swept once
+        let error_res = execute(deps.as_mut(), env, mock_info("anyone"), msg).unwrap_err();
+        assert_eq!(error_res, ContractError::NoPendingSlashes {});
+    }
+
+    #[test]
+    fn test_end_proposals_batch() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_threshold = Decimal::from_ratio(51_u128, 100_u128);
+                config.proposal_required_quorum = Decimal::from_ratio(2_u128, 100_u128);
+                Ok(config)
+            })
+            .unwrap();
+
+        // batch length is capped
+        {
+            let msg = ExecuteMsg::EndProposalsBatch {
+                proposal_ids: (1..=(MAX_END_PROPOSALS_PER_BATCH as u64 + 1)).collect(),
+            };
+            let error_res = execute(
+                deps.as_mut(),
+                mock_env_at_block_height(100_001),
+                mock_info("sender"),
+                msg,
+            )
+            .unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::BatchTooLarge {
+                    max: MAX_END_PROPOSALS_PER_BATCH
+                }
+            );
+        }
+
+        // (id, status, for votes, end height)
+        let proposals = vec![
+            // ended and passing
+            (1, ProposalStatus::Active, 11_000, 100_000),
+            // ended and rejected
+            (2, ProposalStatus::Active, 0, 100_000),
+            // voting period not ended
+            (3, ProposalStatus::Active, 11_000, 100_001),
+            // already ended
+            (4, ProposalStatus::Passed, 11_000, 100_000),
+        ];
+        for (id, status, for_votes, end_height) in proposals {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status,
+                    for_votes: Uint128::new(for_votes),
+                    start_height: 90_000,
+                    end_height,
+                    ..Default::default()
+                },
+            );
+        }
+
+        // ended but without a voting power snapshot
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 5,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(11_000),
+                start_height: 95_000,
+                end_height: 100_000,
+                ..Default::default()
+            },
+        );
+        deps.querier.set_xmars_total_supply_missing_at(94_999);
+
+        let msg = ExecuteMsg::EndProposalsBatch {
+            proposal_ids: vec![1, 5, 2, 3, 4, 99],
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env_at_block_height(100_001),
+            mock_info("sender"),
+            msg,
+        )
+        .unwrap();
+
+        let deposit_transfer = |recipient: &str| {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("mars_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from(recipient),
+                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        };
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::reply_on_error(deposit_transfer("submitter"), 1),
+                SubMsg::reply_on_error(deposit_transfer("staking"), 2),
+            ]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "end_proposal"),
+                attr("proposal_id", "1"),
+                attr("proposal_result", "passed"),
+                attr("skipped_proposal_id", "5"),
+                attr(
+                    "error",
+                    ContractError::SnapshotUnavailable { block: 94_999 }.to_string()
+                ),
+                attr("action", "end_proposal"),
+                attr("proposal_id", "2"),
+                attr("proposal_result", "rejected"),
+            ]
+        );
+
+        let expected_statuses = vec![
+            (1, ProposalStatus::Passed),
+            (2, ProposalStatus::Rejected),
+            (3, ProposalStatus::Active),
+            (4, ProposalStatus::Passed),
+            (5, ProposalStatus::Active),
+        ];
+        for (id, expected_status) in expected_statuses {
+            let proposal = PROPOSALS.load(&deps.storage, U64Key::new(id)).unwrap();
+            assert_eq!(proposal.status, expected_status);
+        }
+    }
+
+    #[test]
+    fn test_query_resolved_addresses() {
+        let deps = th_setup(&[]);
+
+        let res: ResolvedAddressesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(MockEnvParams::default()),
+                QueryMsg::ResolvedAddresses {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            ResolvedAddressesResponse {
+                mars_token_address: Addr::unchecked("mars_token"),
+                xmars_token_address: Addr::unchecked("xmars_token"),
+                staking_address: Addr::unchecked("staking"),
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_stats() {
+        let mut deps = th_setup(&[]);
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(99_999, Uint128::new(100));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("voter"), 99_999, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(50);
+                config.proposal_required_threshold = Decimal::percent(50);
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        for _ in 0..4 {
+            let env = mock_env_at_block_height(100_000);
+            let info = mock_info("mars_token");
+            execute(deps.as_mut(), env, info, submit_msg.clone()).unwrap();
+        }
+
+        // proposals 2 and 3 pass, proposal 1 is rejected and proposal 4 stays active
+        for proposal_id in 2..=3 {
+            let env = mock_env_at_block_height(100_001);
+            let info = mock_info("voter");
+            let msg = ExecuteMsg::CastVote {
+                proposal_id,
+                vote: ProposalVoteOption::For,
+            };
+            execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+        for proposal_id in 1..=3 {
+            let env = mock_env_at_block_height(100_000 + TEST_PROPOSAL_VOTING_PERIOD + 1);
+            let info = mock_info("sender");
+            let msg = ExecuteMsg::EndProposal { proposal_id };
+            execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+
+        // proposal 3 is executed
+        let env = mock_env_at_block_height(
+            100_000 + TEST_PROPOSAL_VOTING_PERIOD + TEST_PROPOSAL_EFFECTIVE_DELAY + 1,
+        );
+        let info = mock_info("executer");
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 3 };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let res: StatsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(MockEnvParams::default()),
+                QueryMsg::Stats {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            StatsResponse {
+                proposal_count: 4,
+                active_proposal_count: 1,
+                passed_proposal_count: 1,
+                rejected_proposal_count: 1,
+                executed_proposal_count: 1,
+                cancelled_proposal_count: 0,
+                implemented_proposal_count: 0,
+                locked_deposits: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                proposal_required_quorum: Decimal::percent(50),
+                proposal_required_threshold: Decimal::percent(50),
+            }
+        );
+    }
+
+    #[test]
+    fn test_submit_proposal_deposit_surge() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.surge_window = 100;
+                config.surge_threshold = 2;
+                config.surge_multiplier = Decimal::percent(300);
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = |title: &str, amount: Uint128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: title.to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount,
+            })
+        };
+        let surge_deposit = TEST_PROPOSAL_REQUIRED_DEPOSIT * Uint128::new(3);
+
+        // submissions up to the threshold only need the regular deposit
+        let submissions = [
+            (100_000, "First title"),
+            (100_030, "Second title"),
+            (100_060, "Third title"),
+        ];
+        for (height, title) in submissions {
+            let env = mock_env_at_block_height(height);
+            let info = mock_info("mars_token");
+            let msg = submit_msg(title, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+            execute(deps.as_mut(), env, info, msg).unwrap();
+        }
+
+        // rapid submissions over the threshold raise the required deposit
+        let env = mock_env_at_block_height(100_100);
+        let res = query_current_required_deposit(deps.as_ref(), env.clone()).unwrap();
+        assert_eq!(res.amount, surge_deposit);
+
+        let info = mock_info("mars_token");
+        let msg = submit_msg("Fourth title", TEST_PROPOSAL_REQUIRED_DEPOSIT);
+        let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::InsufficientDeposit {
+                required: surge_deposit
+            }
+        );
+
+        let info = mock_info("mars_token");
+        let msg = submit_msg("Fourth title", surge_deposit);
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        // required deposit decays once the earlier submissions leave the window
+        let env = mock_env_at_block_height(100_131);
+        let res = query_current_required_deposit(deps.as_ref(), env).unwrap();
+        assert_eq!(res.amount, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+
+        // a reserved proposal counts from its finalization, even if a proposal with a greater id
+        // was submitted before it
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.reservation_period = 1_000;
+                Ok(config)
+            })
+            .unwrap();
+        let reserve_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::ReserveProposal {}).unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env_at_block_height(100_140);
+        execute(deps.as_mut(), env, mock_info("mars_token"), reserve_msg).unwrap();
+
+        let env = mock_env_at_block_height(100_150);
+        let msg = submit_msg("Sixth title", TEST_PROPOSAL_REQUIRED_DEPOSIT);
+        execute(deps.as_mut(), env, mock_info("mars_token"), msg).unwrap();
+
+        let finalize_msg = ExecuteMsg::FinalizeProposal {
+            proposal_id: 5,
+            title: "Fifth title".to_string(),
+            description: "A valid description".to_string(),
+            link: None,
+            messages: None,
+        };
+        let env = mock_env_at_block_height(100_260);
+        execute(deps.as_mut(), env, mock_info("submitter"), finalize_msg).unwrap();
+
+        let env = mock_env_at_block_height(100_300);
+        assert_eq!(count_recent_proposals(&deps.storage, &env, 100).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_query_current_required_deposit() {
+        let mut deps = th_setup(&[]);
+
+        // flat deposit
+        let res = query_current_required_deposit(deps.as_ref(), mock_env(MockEnvParams::default()))
+            .unwrap();
+        assert_eq!(
+            res,
+            CurrentRequiredDepositResponse {
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                token_address: Addr::unchecked("mars_token"),
+            }
+        );
+
+        // supply derived deposit, when greater than the flat deposit
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_deposit_ratio = Decimal::percent(1);
+                Ok(config)
+            })
+            .unwrap();
+        deps.querier
+            .set_cw20_total_supply(Addr::unchecked("mars_token"), Uint128::new(500_000));
+        let res = query_current_required_deposit(deps.as_ref(), mock_env(MockEnvParams::default()))
+            .unwrap();
+        assert_eq!(res.amount, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+
+        deps.querier
+            .set_cw20_total_supply(Addr::unchecked("mars_token"), Uint128::new(5_000_000));
+        let res = query_current_required_deposit(deps.as_ref(), mock_env(MockEnvParams::default()))
+            .unwrap();
+        assert_eq!(res.amount, Uint128::new(50_000));
+
+        // submissions are checked against the same deposit
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env(MockEnvParams::default());
+        let info = mock_info("mars_token");
+        let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::InsufficientDeposit {
+                required: Uint128::new(50_000)
+            }
+        );
+    }
+
+    #[test]
+    fn test_mint_treasury() {
+        let mut deps = th_setup(&[]);
+        let env = mock_env(MockEnvParams::default());
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.mint_per_proposal_cap = Uint128::new(1_000);
+                config.mint_total_cap = Uint128::new(1_500);
+                Ok(config)
+            })
+            .unwrap();
+
+        let mint_msg = |amount: u128| ExecuteMsg::MintTreasury {
+            recipient: String::from("recipient"),
+            amount: Uint128::new(amount),
+        };
+
+        // only the council itself can mint
+        let info = mock_info("anyone");
+        let error_res = execute(deps.as_mut(), env.clone(), info, mint_msg(1_000)).unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        // mint within caps
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let res = execute(deps.as_mut(), env.clone(), info, mint_msg(1_000)).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("mars_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: String::from("recipient"),
+                    amount: Uint128::new(1_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }))]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "mint_treasury"),
+                attr("recipient", "recipient"),
+                attr("amount", "1000"),
+                attr("total_minted", "1000"),
+            ]
+        );
+
+        // mint over the per proposal cap
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res = execute(deps.as_mut(), env.clone(), info, mint_msg(1_001)).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::MintTreasuryProposalCapExceeded {
+                cap: Uint128::new(1_000)
+            }
+        );
+
+        // mint over the remaining total cap
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res = execute(deps.as_mut(), env, info, mint_msg(501)).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::MintTreasuryTotalCapExceeded {
+                remaining: Uint128::new(500)
+            }
+        );
+
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.total_minted, Uint128::new(1_000));
+    }
+
+    #[test]
+    fn test_force_cancel_proposal() {
+        let mut deps = th_setup(&[]);
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("voter"), 99_999, Uint128::new(100));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("late_voter"), 99_999, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.emergency_owner = Some(Addr::unchecked("guardian"));
+                config.slashed_deposit_destination = SlashedDepositDestination::Burn;
+                config.refund_signaling_always = true;
+                Ok(config)
+            })
+            .unwrap();
+
+        let submit_msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: None,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg).unwrap();
+
+        let cast_vote_msg = ExecuteMsg::CastVote {
+            proposal_id: 1,
+            vote: ProposalVoteOption::For,
+        };
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("voter");
+        execute(deps.as_mut(), env, info, cast_vote_msg.clone()).unwrap();
+
+        let cancel_msg = ExecuteMsg::ForceCancelProposal { proposal_id: 1 };
+
+        // only the guardian or the council itself can cancel
+        let env = mock_env_at_block_height(100_002);
+        let info = mock_info("submitter");
+        let error_res = execute(deps.as_mut(), env, info, cancel_msg.clone()).unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        // guardian cancels a proposal that already has votes. The deposit is slashed to the
+        // configured destination, even for a signaling proposal
+        let env = mock_env_at_block_height(100_002);
+        let info = mock_info("guardian");
+        let res = execute(deps.as_mut(), env.clone(), info, cancel_msg.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mars_token"),
+                    msg: to_binary(&Cw20ExecuteMsg::Burn {
+                        amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                1,
+            )]
+        );
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "force_cancel_proposal"),
+                attr("proposal_id", "1"),
+            ]
+        );
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Cancelled);
+        assert_eq!(proposal.deposit_status, DepositStatus::Slashed);
+        assert_eq!(proposal.for_votes, Uint128::new(100));
+        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
+        assert_eq!(global_state.locked_deposits, Uint128::zero());
+        let cancelled_count = PROPOSAL_COUNT_BY_STATUS
+            .load(&deps.storage, "cancelled")
+            .unwrap();
+        assert_eq!(cancelled_count, 1);
+
+        // a failed slash is kept as pending
+        let res = reply(
+            deps.as_mut(),
+            env,
+            Reply {
+                id: 1,
+                result: ContractResult::Err(String::from("burn failed")),
+            },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "record_pending_slash"));
+        assert_eq!(
+            PENDING_SLASHES.load(&deps.storage).unwrap(),
+            TEST_PROPOSAL_REQUIRED_DEPOSIT
+        );
+
+        // cancelled proposal cannot be voted on, cancelled again or ended
+        let env = mock_env_at_block_height(100_003);
+        let info = mock_info("late_voter");
+        let error_res = execute(deps.as_mut(), env, info, cast_vote_msg).unwrap_err();
+        assert_eq!(error_res, ContractError::ProposalNotActive {});
+
+        let env = mock_env_at_block_height(100_003);
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res = execute(deps.as_mut(), env, info, cancel_msg).unwrap_err();
+        assert_eq!(error_res, ContractError::ProposalNotActive {});
+
+        let env = mock_env_at_block_height(100_000 + TEST_PROPOSAL_VOTING_PERIOD + 1);
+        let info = mock_info("sender");
+        let error_res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(error_res, ContractError::ProposalNotActive {});
+    }
+
+    #[test]
+    fn test_mark_implemented() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.emergency_owner = Some(Addr::unchecked("guardian"));
+                Ok(config)
+            })
+            .unwrap();
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Passed,
+                ..Default::default()
+            },
+        );
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 2,
+                status: ProposalStatus::Passed,
+                messages: Some(vec![ProposalMessage {
+                    execution_order: 0,
+                    msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: String::from("target_contract"),
+                        msg: Binary::from(br#"{"some":123}"#),
+                        funds: vec![],
+                    }),
+                    memo: None,
+                    route: ExecuteRoute::Local,
+                }]),
+                ..Default::default()
+            },
+        );
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 3,
+                status: ProposalStatus::Active,
+                ..Default::default()
+            },
+        );
+
+        let mark_msg = |proposal_id: u64, note: &str| ExecuteMsg::MarkImplemented {
+            proposal_id,
+            note: String::from(note),
+        };
+        let env = mock_env_at_block_height(1_000);
+
+        // only the emergency owner or the council itself can mark proposals as implemented
+        let info = mock_info("anyone");
+        let error_res = execute(deps.as_mut(), env.clone(), info, mark_msg(1, "Done")).unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        // proposal needs to have passed
+        let info = mock_info("guardian");
+        let error_res = execute(deps.as_mut(), env.clone(), info, mark_msg(3, "Done")).unwrap_err();
+        assert_eq!(error_res, ContractError::ExecuteProposalNotPassed {});
+
+        // proposals with messages are executed instead
+        let info = mock_info("guardian");
+        let error_res = execute(deps.as_mut(), env.clone(), info, mark_msg(2, "Done")).unwrap_err();
+        assert_eq!(error_res, ContractError::MarkImplementedNotSignaling {});
+
+        // note is bounded by the description max length
+        let long_note = "a".repeat(DEFAULT_DESC_MAX_LENGTH as usize + 1);
+        let info = mock_info("guardian");
+        let error_res =
+            execute(deps.as_mut(), env.clone(), info, mark_msg(1, &long_note)).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::ImplementationNoteTooLong {
+                max: DEFAULT_DESC_MAX_LENGTH
+            }
+        );
+
+        let info = mock_info("guardian");
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            mark_msg(1, "Multisig sent the grant"),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "mark_implemented"), attr("proposal_id", "1")]
+        );
+
+        let proposal_info = query_proposal(deps.as_ref(), env.clone(), 1, None, false).unwrap();
+        assert_eq!(proposal_info.status, ProposalStatus::Implemented);
+        assert_eq!(
+            proposal_info.implementation_note,
+            Some(String::from("Multisig sent the grant"))
+        );
+
+        // implemented is a terminal state
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        let error_res = execute(deps.as_mut(), env, info, mark_msg(1, "Again")).unwrap_err();
+        assert_eq!(error_res, ContractError::ExecuteProposalNotPassed {});
+    }
 
-            proposal_voting_period: Some(10),
-            proposal_effective_delay: Some(11),
-            proposal_expiration_period: Some(12),
-            proposal_required_deposit: Some(Uint128::new(111)),
-            proposal_required_threshold: Some(Decimal::percent(
-                MINIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE,
-            )),
-            proposal_required_quorum: Some(Decimal::one()),
+    #[test]
+    fn test_mark_implemented_through_proposal() {
+        let mut deps = th_setup(&[]);
+
+        let signaling_proposal_id = th_submit_and_pass_proposal(&mut deps, 1_000, None);
+
+        let mark_implemented_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from(MOCK_CONTRACT_ADDR),
+            msg: to_binary(&ExecuteMsg::MarkImplemented {
+                proposal_id: signaling_proposal_id,
+                note: String::from("Done"),
+            })
+            .unwrap(),
+            funds: vec![],
+        });
+        let proposal_id = th_submit_and_pass_proposal(
+            &mut deps,
+            5_000,
+            Some(vec![ProposalMessage {
+                execution_order: 0,
+                msg: mark_implemented_msg.clone(),
+                memo: None,
+                route: ExecuteRoute::Local,
+            }]),
+        );
+
+        let proposal = PROPOSALS
+            .load(&deps.storage, U64Key::new(proposal_id))
+            .unwrap();
+        let env = mock_env_at_block_height(proposal.end_height + TEST_PROPOSAL_EFFECTIVE_DELAY + 1);
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("executor"), msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, mark_implemented_msg);
+
+        // the council dispatching the proposal message marks the signaling proposal
+        let msg = ExecuteMsg::MarkImplemented {
+            proposal_id: signaling_proposal_id,
+            note: String::from("Done"),
         };
-        let msg = InstantiateMsg {
-            config: init_config.clone(),
+        execute(deps.as_mut(), env, mock_info(MOCK_CONTRACT_ADDR), msg).unwrap();
+
+        let signaling_proposal = PROPOSALS
+            .load(&deps.storage, U64Key::new(signaling_proposal_id))
+            .unwrap();
+        assert_eq!(signaling_proposal.status, ProposalStatus::Implemented);
+        assert_eq!(
+            signaling_proposal.implementation_note,
+            Some(String::from("Done"))
+        );
+    }
+
+    #[test]
+    fn test_total_locked_deposits() {
+        let mut deps = th_setup(&[]);
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(99_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_vesting_total_voting_power_at(99_999, Uint128::zero());
+
+        let submit_msg = |amount: Uint128| {
+            ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: None,
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount,
+            })
         };
-        let env = cosmwasm_std::testing::mock_env();
+        let query_locked_deposits = |deps: Deps| -> Uint128 {
+            let res: TotalLockedDepositsResponse = from_binary(
+                &query(
+                    deps,
+                    mock_env(MockEnvParams::default()),
+                    QueryMsg::TotalLockedDeposits {},
+                )
+                .unwrap(),
+            )
+            .unwrap();
+            res.amount
+        };
+
+        assert_eq!(query_locked_deposits(deps.as_ref()), Uint128::zero());
+
+        // submitting proposals locks their deposits
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg(Uint128::new(15_000))).unwrap();
+
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        execute(deps.as_mut(), env, info, submit_msg(Uint128::new(20_000))).unwrap();
+
+        assert_eq!(query_locked_deposits(deps.as_ref()), Uint128::new(35_000));
+
+        // ending a proposal releases its deposit
+        let env = mock_env_at_block_height(102_001);
+        let info = mock_info("sender");
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+
+        assert_eq!(query_locked_deposits(deps.as_ref()), Uint128::new(20_000));
+
+        let env = mock_env_at_block_height(102_001);
+        let info = mock_info("sender");
+        execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 2 },
+        )
+        .unwrap();
+
+        assert_eq!(query_locked_deposits(deps.as_ref()), Uint128::zero());
+    }
+
+    #[test]
+    fn test_query_escrow_balance() {
+        let mut deps = th_setup(&[]);
+
+        // the council holds less MARS than the deposits it has to give back
+        GLOBAL_STATE
+            .update(&mut deps.storage, |mut global_state| -> StdResult<_> {
+                global_state.locked_deposits = Uint128::new(30_000);
+                Ok(global_state)
+            })
+            .unwrap();
+        deps.querier.set_cw20_balances(
+            Addr::unchecked("mars_token"),
+            &[(Addr::unchecked(MOCK_CONTRACT_ADDR), Uint128::new(25_000))],
+        );
+
+        let res: EscrowBalanceResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(MockEnvParams::default()),
+                QueryMsg::EscrowBalance {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res,
+            EscrowBalanceResponse {
+                balance: Uint128::new(25_000),
+                locked_deposits: Uint128::new(30_000),
+            }
+        );
+    }
+
+    #[test]
+    fn test_query_config_diff() {
+        let mut deps = th_setup(&[]);
+
+        // the address provider is not reported even though it differs
+        let config = Config {
+            address_provider_address: Addr::unchecked("address_provider"),
+            proposal_voting_period: 1_000,
+            proposal_required_quorum: Decimal::percent(5),
+            ..Config::recommended_defaults()
+        };
+        CONFIG.save(&mut deps.storage, &config).unwrap();
+
+        let res: ConfigDiffResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(MockEnvParams::default()),
+                QueryMsg::ConfigDiff {},
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.fields,
+            vec![
+                ("proposal_voting_period".to_string(), "1000".to_string()),
+                (
+                    "proposal_required_quorum".to_string(),
+                    "\"0.05\"".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_query_config_history() {
+        let mut deps = th_setup(&[]);
+
+        let update_config_msg = |proposal_voting_period: u64| ExecuteMsg::UpdateConfig {
+            config: CreateOrUpdateConfig {
+                proposal_voting_period: Some(proposal_voting_period),
+                ..Default::default()
+            },
+        };
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Passed,
+                end_height: 100_000,
+                messages: Some(vec![ProposalMessage {
+                    execution_order: 0,
+                    msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                        msg: to_binary(&update_config_msg(3_000)).unwrap(),
+                        funds: vec![],
+                    }),
+                    memo: None,
+                    route: ExecuteRoute::Local,
+                }]),
+                ..Default::default()
+            },
+        );
+
+        let res = query_config_history(deps.as_ref(), None, None).unwrap();
+        assert_eq!(res.entries, vec![]);
+
+        // update made by the council while executing a proposal
+        let execute_height = 100_000 + TEST_PROPOSAL_EFFECTIVE_DELAY + 1;
+        let env = mock_env_at_block_height(execute_height);
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("executor"), msg).unwrap();
+
         let info = mock_info(MOCK_CONTRACT_ADDR);
-        let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        execute(deps.as_mut(), env.clone(), info, update_config_msg(3_000)).unwrap();
+        let first_config = CONFIG.load(&deps.storage).unwrap();
 
-        // *
-        // update config with invalid params
-        // *
-        {
-            let env = cosmwasm_std::testing::mock_env();
-            let info = mock_info(MOCK_CONTRACT_ADDR);
+        reply(
+            deps.as_mut(),
+            env,
+            Reply {
+                id: res.messages[0].id,
+                result: ContractResult::Ok(SubMsgExecutionResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            },
+        )
+        .unwrap();
 
-            // proposal_required_quorum greater than 1
-            let config = CreateOrUpdateConfig {
-                proposal_required_quorum: Some(Decimal::percent(101)),
-                ..init_config.clone()
-            };
-            let msg = UpdateConfig { config };
-            let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-            assert_eq!(
-                error_res,
-                MarsError::InvalidParam {
-                    param_name: "proposal_required_quorum".to_string(),
-                    invalid_value: "1.01".to_string(),
-                    predicate: "<= 1".to_string(),
-                }
-                .into()
-            );
+        // update made by the council once the proposal executed is not attributed to it
+        let direct_height = execute_height + 10;
+        let env = mock_env_at_block_height(direct_height);
+        let info = mock_info(MOCK_CONTRACT_ADDR);
+        execute(deps.as_mut(), env, info, update_config_msg(3_500)).unwrap();
+        let direct_config = CONFIG.load(&deps.storage).unwrap();
+
+        // update made through sudo
+        let sudo_height = execute_height + 50;
+        let msg = SudoMsg::UpdateConfig {
+            config: CreateOrUpdateConfig {
+                proposal_voting_period: Some(4_000),
+                ..Default::default()
+            },
+        };
+        sudo(deps.as_mut(), mock_env_at_block_height(sudo_height), msg).unwrap();
+        let second_config = CONFIG.load(&deps.storage).unwrap();
 
-            // proposal_required_threshold less than 50%
-            let config = CreateOrUpdateConfig {
-                proposal_required_threshold: Some(Decimal::percent(49)),
-                ..init_config.clone()
-            };
-            let msg = UpdateConfig { config };
-            let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-            assert_eq!(
-                error_res,
-                MarsError::InvalidParam {
-                    param_name: "proposal_required_threshold".to_string(),
-                    invalid_value: "0.49".to_string(),
-                    predicate: ">= 0.5 and <= 1".to_string(),
-                }
-                .into()
-            );
+        // another update in the same block is kept along with it
+        let msg = SudoMsg::UpdateConfig {
+            config: CreateOrUpdateConfig {
+                proposal_voting_period: Some(4_500),
+                ..Default::default()
+            },
+        };
+        sudo(deps.as_mut(), mock_env_at_block_height(sudo_height), msg).unwrap();
+        let third_config = CONFIG.load(&deps.storage).unwrap();
 
-            // proposal_required_threshold greater than 100%
-            let config = CreateOrUpdateConfig {
-                proposal_required_threshold: Some(Decimal::percent(101)),
-                ..init_config.clone()
-            };
-            let msg = UpdateConfig { config };
-            let error_res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
-            assert_eq!(
-                error_res,
-                MarsError::InvalidParam {
-                    param_name: "proposal_required_threshold".to_string(),
-                    invalid_value: "1.01".to_string(),
-                    predicate: ">= 0.5 and <= 1".to_string(),
-                }
-                .into()
-            );
-        }
+        let res = query_config_history(deps.as_ref(), None, None).unwrap();
+        assert_eq!(
+            res.entries,
+            vec![
+                ConfigHistoryEntry {
+                    height: execute_height,
+                    proposal_id: Some(1),
+                    config: first_config,
+                },
+                ConfigHistoryEntry {
+                    height: direct_height,
+                    proposal_id: None,
+                    config: direct_config,
+                },
+                ConfigHistoryEntry {
+                    height: sudo_height,
+                    proposal_id: None,
+                    config: second_config.clone(),
+                },
+                ConfigHistoryEntry {
+                    height: sudo_height,
+                    proposal_id: None,
+                    config: third_config.clone(),
+                },
+            ]
+        );
+        assert_eq!(res.entries[0].config.proposal_voting_period, 3_000);
+        assert_eq!(res.entries[3].config.proposal_voting_period, 4_500);
 
-        // *
-        // only council itself is authorized
-        // *
-        {
-            let msg = UpdateConfig {
-                config: init_config,
-            };
-            let info = mock_info("somebody");
-            let error_res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
-            assert_eq!(error_res, MarsError::Unauthorized {}.into());
-        }
+        // the updates of a block are returned in the same page
+        let res = query_config_history(deps.as_ref(), Some(direct_height), Some(1)).unwrap();
+        assert_eq!(
+            res.entries,
+            vec![
+                ConfigHistoryEntry {
+                    height: sudo_height,
+                    proposal_id: None,
+                    config: second_config,
+                },
+                ConfigHistoryEntry {
+                    height: sudo_height,
+                    proposal_id: None,
+                    config: third_config,
+                },
+            ]
+        );
+    }
 
-        // *
-        // update config with all new params
-        // *
-        {
-            let config = CreateOrUpdateConfig {
-                address_provider_address: Some(String::from("new_address_provider")),
+    #[test]
+    fn test_end_proposal_slashed_deposit_destination() {
+        let mut deps = th_setup(&[]);
 
-                proposal_voting_period: Some(101),
-                proposal_effective_delay: Some(111),
-                proposal_expiration_period: Some(121),
-                proposal_required_deposit: Some(Uint128::new(1111)),
-                proposal_required_threshold: Some(Decimal::from_ratio(4u128, 5u128)),
-                proposal_required_quorum: Some(Decimal::from_ratio(1u128, 5u128)),
-            };
-            let msg = UpdateConfig {
-                config: config.clone(),
-            };
-            let info = mock_info(MOCK_CONTRACT_ADDR);
-            let res = execute(deps.as_mut(), env, info, msg).unwrap();
-            assert_eq!(0, res.messages.len());
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        let destinations_and_expected_msgs = vec![
+            (
+                SlashedDepositDestination::Staking,
+                Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("staking"),
+                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                },
+            ),
+            (
+                SlashedDepositDestination::Burn,
+                Cw20ExecuteMsg::Burn {
+                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                },
+            ),
+            (
+                SlashedDepositDestination::Treasury(Addr::unchecked("treasury")),
+                Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("treasury"),
+                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                },
+            ),
+        ];
 
-            // Read config from state
-            let new_config = CONFIG.load(&deps.storage).unwrap();
+        for (i, (destination, expected_msg)) in
+            destinations_and_expected_msgs.into_iter().enumerate()
+        {
+            let proposal_id = i as u64 + 1;
 
-            assert_eq!(
-                new_config.address_provider_address,
-                Addr::unchecked("new_address_provider")
-            );
-            assert_eq!(
-                new_config.proposal_voting_period,
-                config.proposal_voting_period.unwrap()
-            );
-            assert_eq!(
-                new_config.proposal_effective_delay,
-                config.proposal_effective_delay.unwrap()
-            );
-            assert_eq!(
-                new_config.proposal_expiration_period,
-                config.proposal_expiration_period.unwrap()
-            );
-            assert_eq!(
-                new_config.proposal_required_deposit,
-                config.proposal_required_deposit.unwrap()
-            );
-            assert_eq!(
-                new_config.proposal_required_threshold,
-                config.proposal_required_threshold.unwrap()
+            CONFIG
+                .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                    config.slashed_deposit_destination = destination;
+                    Ok(config)
+                })
+                .unwrap();
+
+            // proposal without votes gets rejected
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id: proposal_id,
+                    status: ProposalStatus::Active,
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
             );
+
+            let env = mock_env_at_block_height(100_001);
+            let info = mock_info("sender");
+            let res = execute(
+                deps.as_mut(),
+                env,
+                info,
+                ExecuteMsg::EndProposal { proposal_id },
+            )
+            .unwrap();
+
             assert_eq!(
-                new_config.proposal_required_quorum,
-                config.proposal_required_quorum.unwrap()
+                res.messages,
+                vec![SubMsg::reply_on_error(
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: String::from("mars_token"),
+                        msg: to_binary(&expected_msg).unwrap(),
+                        funds: vec![],
+                    }),
+                    proposal_id,
+                )]
             );
         }
     }
 
     #[test]
-    fn test_submit_proposal_invalid_params() {
+    fn test_end_proposal_quorum_basis() {
         let mut deps = th_setup(&[]);
 
-        // *
-        // Invalid title
-        // *
-        {
-            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-                msg: to_binary(&ReceiveMsg::SubmitProposal {
-                    title: "a".to_string(),
-                    description: "A valid description".to_string(),
-                    link: None,
-                    messages: None,
-                })
-                .unwrap(),
-                sender: String::from("submitter"),
-                amount: Uint128::new(2_000_000),
-            });
-            let env = mock_env(MockEnvParams::default());
-            let info = mock_info("mars_token");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(response, ContractError::invalid_proposal("title too short"));
-        }
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_xmars_balance_at(
+            Addr::unchecked("treasury"),
+            89_999,
+            Uint128::new(40_000),
+        );
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
 
-        {
-            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-                msg: to_binary(&ReceiveMsg::SubmitProposal {
-                    title: (0..100).map(|_| "a").collect::<String>(),
-                    description: "A valid description".to_string(),
-                    link: None,
-                    messages: None,
-                })
-                .unwrap(),
-                sender: String::from("submitter"),
-                amount: Uint128::new(2_000_000),
-            });
-            let env = mock_env(MockEnvParams::default());
-            let info = mock_info("mars_token");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(response, ContractError::invalid_proposal("title too long"));
-        }
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(60);
+                config.proposal_required_threshold = Decimal::percent(50);
+                config.quorum_excluded_addresses = vec![Addr::unchecked("treasury")];
+                Ok(config)
+            })
+            .unwrap();
 
-        // *
-        // Invalid description
-        // *
-        {
-            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-                msg: to_binary(&ReceiveMsg::SubmitProposal {
-                    title: "A valid Title".to_string(),
-                    description: "a".to_string(),
-                    link: None,
-                    messages: None,
-                })
-                .unwrap(),
-                sender: String::from("submitter"),
-                amount: Uint128::new(2_000_000),
-            });
-            let env = mock_env(MockEnvParams::default());
-            let info = mock_info("mars_token");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(
-                response,
-                ContractError::invalid_proposal("description too short")
+        for id in 1..=2 {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    for_votes: Uint128::new(50_000),
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
             );
         }
 
-        {
-            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-                msg: to_binary(&ReceiveMsg::SubmitProposal {
-                    title: "A valid Title".to_string(),
-                    description: (0..1030).map(|_| "a").collect::<String>(),
-                    link: None,
-                    messages: None,
-                })
-                .unwrap(),
-                sender: String::from("submitter"),
-                amount: Uint128::new(2_000_000),
-            });
-            let env = mock_env(MockEnvParams::default());
-            let info = mock_info("mars_token");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(
-                response,
-                ContractError::invalid_proposal("description too long")
+        // total supply: 50_000 / 100_000 votes is below quorum
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("ender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "rejected"));
+
+        // circulating supply: 50_000 / (100_000 - 40_000) votes is above quorum
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.quorum_basis = QuorumBasis::CirculatingSupply;
+                Ok(config)
+            })
+            .unwrap();
+
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("ender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 2 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "passed"));
+    }
+
+    #[test]
+    fn test_end_proposal_min_for_votes_absolute() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(1_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(50);
+                config.proposal_required_threshold = Decimal::percent(50);
+                config.min_for_votes_absolute = Uint128::new(800);
+                Ok(config)
+            })
+            .unwrap();
+
+        for (id, for_votes) in [(1, 700), (2, 800)] {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    for_votes: Uint128::new(for_votes),
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
             );
         }
 
-        // *
-        // Invalid link
-        // *
-        {
-            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-                msg: to_binary(&ReceiveMsg::SubmitProposal {
-                    title: "A valid Title".to_string(),
-                    description: "A valid description".to_string(),
-                    link: Some("a".to_string()),
-                    messages: None,
-                })
-                .unwrap(),
-                sender: String::from("submitter"),
-                amount: Uint128::new(2_000_000),
-            });
-            let env = mock_env(MockEnvParams::default());
-            let info = mock_info("mars_token");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(response, ContractError::invalid_proposal("Link too short"));
-        }
+        // 700 / 1_000 votes meets quorum and threshold but not the absolute floor
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("ender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "rejected"));
 
-        {
-            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-                msg: to_binary(&ReceiveMsg::SubmitProposal {
-                    title: "A valid Title".to_string(),
-                    description: "A valid description".to_string(),
-                    link: Some((0..150).map(|_| "a").collect::<String>()),
-                    messages: None,
-                })
-                .unwrap(),
-                sender: String::from("submitter"),
-                amount: Uint128::new(2_000_000),
-            });
-            let env = mock_env(MockEnvParams::default());
-            let info = mock_info("mars_token");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(response, ContractError::invalid_proposal("Link too long"));
-        }
+        // 800 votes meets the absolute floor
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("ender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 2 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "passed"));
+    }
 
-        // *
-        // Invalid deposit amount
-        // *
-        {
-            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-                msg: to_binary(&ReceiveMsg::SubmitProposal {
-                    title: "A valid Title".to_string(),
-                    description: "A valid description".to_string(),
-                    link: None,
-                    messages: None,
-                })
-                .unwrap(),
-                sender: String::from("submitter"),
-                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT - Uint128::new(100),
-            });
-            let env = mock_env(MockEnvParams::default());
-            let info = mock_info("mars_token");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(
-                response,
-                ContractError::invalid_proposal("Must deposit at least 10000 Mars tokens")
+    #[test]
+    fn test_end_proposal_tie() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(1_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(50);
+                config.proposal_required_threshold = Decimal::percent(50);
+                Ok(config)
+            })
+            .unwrap();
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(400),
+                against_votes: Uint128::new(400),
+                start_height: 90_000,
+                end_height: 100_000,
+                ..Default::default()
+            },
+        );
+
+        // an exact 50/50 split meets quorum but ties always fail
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("ender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "rejected"));
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
+        assert_eq!(proposal.final_threshold, Some(Decimal::percent(50)));
+    }
+
+    #[test]
+    fn test_end_proposal_refund_signaling() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(1_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.refund_signaling_always = true;
+                Ok(config)
+            })
+            .unwrap();
+
+        let messages = Some(vec![ProposalMessage {
+            execution_order: 0,
+            msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("oracle"),
+                msg: Binary::from(br#"{"some":123}"#),
+                funds: vec![],
+            }),
+            memo: None,
+            route: ExecuteRoute::Local,
+        }]);
+        for (id, messages) in [(1, None), (2, messages)] {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    messages,
+                    ..Default::default()
+                },
             );
         }
 
-        // *
-        // Invalid deposit currency
-        // *
-        {
-            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-                msg: to_binary(&ReceiveMsg::SubmitProposal {
-                    title: "A valid Title".to_string(),
-                    description: "A valid description".to_string(),
-                    link: None,
-                    messages: None,
+        let deposit_msg = |recipient: &str| {
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("mars_token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from(recipient),
+                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
                 })
                 .unwrap(),
-                sender: String::from("submitter"),
-                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
-            });
-            let env = mock_env(MockEnvParams::default());
-            let info = mock_info("other_token");
-            let res_error = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(
-                res_error,
-                ContractError::invalid_proposal("Must deposit at least 10000 Mars tokens")
-            );
-        }
+                funds: vec![],
+            })
+        };
+
+        // rejected signaling proposal is refunded
+        let env = mock_env_at_block_height(100_001);
+        let msg = ExecuteMsg::EndProposal { proposal_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("submitter"), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "rejected"));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(deposit_msg("submitter"), 1)]
+        );
+
+        // rejected executable proposal is slashed
+        let msg = ExecuteMsg::EndProposal { proposal_id: 2 };
+        let res = execute(deps.as_mut(), env, mock_info("submitter"), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "rejected"));
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(deposit_msg("staking"), 2)]
+        );
     }
 
     #[test]
-    fn test_submit_proposal() {
+    fn test_end_proposal_snapshot_unavailable() {
         let mut deps = th_setup(&[]);
-        let submitter_address = Addr::unchecked("submitter");
 
-        // Submit Proposal without link or call data
-        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-            msg: to_binary(&ReceiveMsg::SubmitProposal {
-                title: "A valid title".to_string(),
-                description: "A valid description".to_string(),
-                link: None,
-                messages: None,
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier.set_xmars_total_supply_missing_at(89_999);
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(50);
+                config.proposal_required_threshold = Decimal::percent(50);
+                Ok(config)
             })
-            .unwrap(),
-            sender: submitter_address.to_string(),
-            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
-        });
-        let env = mock_env(MockEnvParams {
-            block_height: 100_000,
-            ..Default::default()
-        });
-        let info = mock_info("mars_token");
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        let expected_end_height = 100_000 + TEST_PROPOSAL_VOTING_PERIOD;
+            .unwrap();
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                for_votes: Uint128::new(800),
+                start_height: 90_000,
+                end_height: 100_000,
+                ..Default::default()
+            },
+        );
+
+        // proposal is not rejected while the snapshot is missing
+        let env = mock_env_at_block_height(100_001);
+        let msg = ExecuteMsg::EndProposal { proposal_id: 1 };
+        let error_res =
+            execute(deps.as_mut(), env.clone(), mock_info("ender"), msg.clone()).unwrap_err();
         assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "submit_proposal"),
-                attr("submitter", "submitter"),
-                attr("proposal_id", 1.to_string()),
-                attr("proposal_end_height", expected_end_height.to_string()),
-            ]
+            error_res,
+            ContractError::SnapshotUnavailable { block: 89_999 }
         );
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Active);
 
-        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
-        assert_eq!(global_state.proposal_count, 1);
+        let error_res = query_power_needed_to_pass(deps.as_ref(), 1).unwrap_err();
+        assert_eq!(
+            error_res,
+            StdError::generic_err("Voting power snapshot is not available at block: 89999")
+        );
 
-        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1_u64)).unwrap();
-        assert_eq!(proposal.proposal_id, 1);
-        assert_eq!(proposal.submitter_address, submitter_address);
-        assert_eq!(proposal.status, ProposalStatus::Active);
-        assert_eq!(proposal.for_votes, Uint128::new(0));
-        assert_eq!(proposal.against_votes, Uint128::new(0));
-        assert_eq!(proposal.start_height, 100_000);
-        assert_eq!(proposal.end_height, expected_end_height);
-        assert_eq!(proposal.title, "A valid title");
-        assert_eq!(proposal.description, "A valid description");
-        assert_eq!(proposal.link, None);
-        assert_eq!(proposal.messages, None);
-        assert_eq!(proposal.deposit_amount, TEST_PROPOSAL_REQUIRED_DEPOSIT);
+        // proposal can be ended once the snapshot exists
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(1_000));
+        let res = execute(deps.as_mut(), env, mock_info("ender"), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "passed"));
+    }
 
-        // Submit Proposal with link and call data
-        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
-            msg: to_binary(&ReceiveMsg::SubmitProposal {
-                title: "A valid title".to_string(),
-                description: "A valid description".to_string(),
-                link: Some("https://www.avalidlink.com".to_string()),
-                messages: Some(vec![ProposalMessage {
-                    execution_order: 0,
-                    msg: CosmosMsg::Wasm(WasmMsg::Execute {
-                        contract_addr: String::from(MOCK_CONTRACT_ADDR),
-                        msg: to_binary(&ExecuteMsg::UpdateConfig {
-                            config: CreateOrUpdateConfig::default(),
-                        })
-                        .unwrap(),
-                        funds: vec![],
-                    }),
-                }]),
+    #[test]
+    fn test_voting_token_override() {
+        let mut deps = th_setup(&[]);
+
+        // the address provider resolves xMARS to "xmars_token" while the mock fails voting
+        // power queries to any token other than "gov_token", so xMARS is not resolved through
+        // the address provider
+        deps.querier.set_xmars_address(Addr::unchecked("gov_token"));
+        deps.querier
+            .set_xmars_balance_at(Addr::unchecked("voter"), 99_999, Uint128::new(100));
+        deps.querier
+            .set_xmars_total_supply_at(99_999, Uint128::new(150));
+        // vesting is not set up at the address the provider resolves it to, so querying it
+        // fails. Its locked MARS would count if it was queried
+        deps.querier.set_vesting_voting_power_at(
+            Addr::unchecked("voter"),
+            99_999,
+            Uint128::new(1_000),
+        );
+        deps.querier
+            .set_vesting_total_voting_power_at(99_999, Uint128::new(1_000));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(50);
+                config.proposal_required_threshold = Decimal::percent(50);
+                config.voting_token_override = Some(Addr::unchecked("gov_token"));
+                Ok(config)
             })
-            .unwrap(),
-            sender: submitter_address.to_string(),
-            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
-        });
-        let env = mock_env(MockEnvParams {
-            block_height: 100_000,
-            ..Default::default()
-        });
-        let info = mock_info("mars_token");
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
-        let expected_end_height = 100_000 + TEST_PROPOSAL_VOTING_PERIOD;
-        assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "submit_proposal"),
-                attr("submitter", "submitter"),
-                attr("proposal_id", 2.to_string()),
-                attr("proposal_end_height", expected_end_height.to_string()),
-            ]
+            .unwrap();
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
         );
 
-        let global_state = GLOBAL_STATE.load(&deps.storage).unwrap();
-        assert_eq!(global_state.proposal_count, 2);
+        let res = query_voter_power(deps.as_ref(), 1, String::from("voter")).unwrap();
+        assert_eq!(res.voting_power, Uint128::new(100));
+
+        let msg = ExecuteMsg::CastVote {
+            proposal_id: 1,
+            vote: ProposalVoteOption::For,
+        };
+        let env = mock_env_at_block_height(100_001);
+        execute(deps.as_mut(), env, mock_info("voter"), msg).unwrap();
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.for_votes, Uint128::new(100));
+
+        // 100 / 150 votes is above quorum
+        let env = mock_env_at_block_height(100_101);
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info("ender"),
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "passed"));
+    }
+
+    #[test]
+    fn test_end_proposal_reward() {
+        let mut deps = th_setup(&[]);
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.end_proposal_reward = Uint128::new(50);
+                Ok(config)
+            })
+            .unwrap();
+
+        for id in 1..=2 {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
+            );
+        }
+
+        // caller receives the reward from the treasury
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("ender");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap();
 
-        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(2_u64)).unwrap();
         assert_eq!(
-            proposal.link,
-            Some("https://www.avalidlink.com".to_string())
+            res.attributes,
+            vec![
+                attr("action", "end_proposal"),
+                attr("proposal_id", "1"),
+                attr("proposal_result", "rejected"),
+                attr("end_proposal_reward", "50"),
+            ]
         );
         assert_eq!(
-            proposal.messages,
-            Some(vec![ProposalMessage {
-                execution_order: 0,
-                msg: CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
-                    msg: to_binary(&ExecuteMsg::UpdateConfig {
-                        config: CreateOrUpdateConfig::default(),
+            res.messages[1],
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("treasury"),
+                msg: to_binary(&treasury::msg::ExecuteMsg::ExecuteCosmosMsg(
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: String::from("mars_token"),
+                        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                            recipient: String::from("ender"),
+                            amount: Uint128::new(50),
+                        })
+                        .unwrap(),
+                        funds: vec![],
                     })
-                    .unwrap(),
-                    funds: vec![],
-                }),
-            }])
+                ))
+                .unwrap(),
+                funds: vec![],
+            }))
         );
+
+        // a proposal cannot be ended (and rewarded) twice
+        let env = mock_env_at_block_height(100_002);
+        let info = mock_info("ender");
+        let error_res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 1 },
+        )
+        .unwrap_err();
+        assert_eq!(error_res, ContractError::ProposalNotActive {});
+
+        // submitter does not get rewarded for ending its own proposal
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("submitter");
+        let res = execute(
+            deps.as_mut(),
+            env,
+            info,
+            ExecuteMsg::EndProposal { proposal_id: 2 },
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
     }
 
     #[test]
-    fn test_invalid_cast_votes() {
+    fn test_reclaim_stale_deposit() {
         let mut deps = th_setup(&[]);
-        let voter_address = Addr::unchecked("valid_voter");
-        let invalid_voter_address = Addr::unchecked("invalid_voter");
 
         deps.querier
             .set_xmars_address(Addr::unchecked("xmars_token"));
         deps.querier
-            .set_xmars_balance_at(voter_address, 99_999, Uint128::new(100));
-        deps.querier
-            .set_xmars_balance_at(invalid_voter_address, 99_999, Uint128::zero());
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
         deps.querier.set_vesting_address(Addr::unchecked("vesting"));
 
-        let active_proposal_id = 1_u64;
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.reclaim_grace_period = 1_000;
+                Ok(config)
+            })
+            .unwrap();
+
         th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: active_proposal_id,
+                id: 1,
                 status: ProposalStatus::Active,
-                start_height: 100_000,
-                end_height: 100_100,
+                start_height: 90_000,
+                end_height: 100_000,
                 ..Default::default()
             },
         );
 
-        let executed_proposal_id = 2_u64;
-        th_build_mock_proposal(
-            deps.as_mut(),
-            MockProposal {
-                id: executed_proposal_id,
-                status: ProposalStatus::Executed,
-                start_height: 100_000,
-                end_height: 100_100,
-                ..Default::default()
-            },
-        );
+        let msg = ExecuteMsg::ReclaimStaleDeposit { proposal_id: 1 };
 
-        // *
-        // voting on a non-existent proposal should fail
-        // *
+        // only the submitter can reclaim
         {
-            let msg = ExecuteMsg::CastVote {
-                proposal_id: 3,
-                vote: ProposalVoteOption::For,
-            };
-            let env = mock_env(MockEnvParams {
-                block_height: 100_001,
-                ..Default::default()
-            });
-            let info = mock_info("valid_voter");
-            let res_error = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            let env = mock_env_at_block_height(101_001);
+            let info = mock_info("someone");
+            let error_res = execute(deps.as_mut(), env, info, msg.clone()).unwrap_err();
+            assert_eq!(error_res, MarsError::Unauthorized {}.into());
+        }
+
+        // cannot reclaim during the grace period
+        {
+            let env = mock_env_at_block_height(101_000);
+            let info = mock_info("submitter");
+            let error_res = execute(deps.as_mut(), env, info, msg.clone()).unwrap_err();
             assert_eq!(
-                res_error,
-                StdError::NotFound {
-                    kind: "mars_core::council::Proposal".to_string(),
+                error_res,
+                ContractError::ReclaimStaleDepositGracePeriodNotEnded {
+                    reclaim_height: 101_000
                 }
-                .into()
             );
         }
 
-        // *
-        // voting on an inactive proposal should fail
-        // *
-        {
-            let msg = ExecuteMsg::CastVote {
-                proposal_id: executed_proposal_id,
-                vote: ProposalVoteOption::For,
-            };
-            let env = mock_env(MockEnvParams {
-                block_height: 100_001,
-                ..Default::default()
-            });
-            let info = mock_info("valid_voter");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(response, ContractError::ProposalNotActive {});
-        }
+        // reclaiming after the grace period tallies votes as a regular end
+        let env = mock_env_at_block_height(101_001);
+        let info = mock_info("submitter");
+        let res = execute(deps.as_mut(), env, info, msg.clone()).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "end_proposal"),
+                attr("proposal_id", "1"),
+                attr("proposal_result", "rejected"),
+            ]
+        );
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("mars_token"),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: String::from("staking"),
+                        amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                1,
+            )]
+        );
 
-        // *
-        // voting after proposal end should fail
-        // *
-        {
-            let msg = ExecuteMsg::CastVote {
-                proposal_id: active_proposal_id,
-                vote: ProposalVoteOption::For,
-            };
-            let env = mock_env(MockEnvParams {
-                block_height: 100_200,
-                ..Default::default()
-            });
-            let info = mock_info("valid_voter");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(response, ContractError::VoteVotingPeriodEnded {});
-        }
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Rejected);
 
-        // *
-        // voting without any voting power should fail
-        // *
-        {
-            let msg = ExecuteMsg::CastVote {
-                proposal_id: active_proposal_id,
-                vote: ProposalVoteOption::For,
-            };
-            let env = mock_env(MockEnvParams {
-                block_height: 100_001,
-                ..Default::default()
-            });
-            let info = mock_info("invalid_voter");
-            let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-            assert_eq!(response, ContractError::VoteNoVotingPower { block: 99_999 });
-        }
+        // proposal cannot be reclaimed twice
+        let env = mock_env_at_block_height(101_002);
+        let info = mock_info("submitter");
+        let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(error_res, ContractError::ProposalNotActive {});
     }
 
     #[test]
-    fn test_cast_vote() {
-        // setup
+    fn test_invalid_execute_proposals() {
         let mut deps = th_setup(&[]);
-        let voter_address = Addr::unchecked("voter");
-
-        let active_proposal_id = 1_u64;
-
-        deps.querier
-            .set_xmars_address(Addr::unchecked("xmars_token"));
-        deps.querier
-            .set_xmars_balance_at(voter_address.clone(), 99_999, Uint128::new(100));
 
-        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
-        deps.querier
-            .set_vesting_voting_power_at(voter_address.clone(), 99_999, Uint128::new(23));
+        let passed_proposal_id = 1_u64;
+        let executed_proposal_id = 2_u64;
 
-        let active_proposal = th_build_mock_proposal(
+        let passed_proposal = th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: active_proposal_id,
-                status: ProposalStatus::Active,
-                start_height: 100_000,
-                end_height: 100_100,
+                id: passed_proposal_id,
+                status: ProposalStatus::Passed,
+                end_height: 100_000,
+                ..Default::default()
+            },
+        );
+        let executed_proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: executed_proposal_id,
+                status: ProposalStatus::Executed,
                 ..Default::default()
             },
         );
 
-        // Add another vote on an extra proposal to voter to validate voting on multiple proposals
-        // is valid
-        PROPOSAL_VOTES
-            .save(
-                &mut deps.storage,
-                (U64Key::new(4_u64), &voter_address),
-                &ProposalVote {
-                    option: ProposalVoteOption::Against,
-                    power: Uint128::new(100),
-                },
-            )
-            .unwrap();
+        // cannot execute a non Passed proposal
+        let msg = ExecuteMsg::ExecuteProposal {
+            proposal_id: executed_proposal_id,
+        };
+        let env = mock_env(MockEnvParams {
+            block_height: executed_proposal.end_height + TEST_PROPOSAL_EFFECTIVE_DELAY + 1,
+            ..Default::default()
+        });
+        let info = mock_info("executer");
+        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(response, ContractError::ExecuteProposalNotPassed {},);
 
-        // Valid vote for
-        let msg = ExecuteMsg::CastVote {
-            proposal_id: active_proposal_id,
-            vote: ProposalVoteOption::For,
+        // cannot execute a proposal before the effective delay has passed
+        let msg = ExecuteMsg::ExecuteProposal {
+            proposal_id: passed_proposal_id,
         };
+        let env = mock_env(MockEnvParams {
+            block_height: passed_proposal.end_height + 1,
+            ..Default::default()
+        });
+        let info = mock_info("executer");
+        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(response, ContractError::ExecuteProposalDelayNotEnded {});
 
+        // cannot execute an expired proposal
+        let msg = ExecuteMsg::ExecuteProposal {
+            proposal_id: passed_proposal_id,
+        };
         let env = mock_env(MockEnvParams {
-            block_height: active_proposal.start_height + 1,
+            block_height: passed_proposal.end_height
+                + TEST_PROPOSAL_EFFECTIVE_DELAY
+                + TEST_PROPOSAL_EXPIRATION_PERIOD
+                + 1,
             ..Default::default()
         });
-        let info = mock_info("voter");
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let info = mock_info("executer");
+        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(response, ContractError::ExecuteProposalExpired {});
+    }
+
+    #[test]
+    fn test_query_expiring_executable_proposals() {
+        let mut deps = th_setup(&[]);
+
+        // executable proposals expire TEST_PROPOSAL_EFFECTIVE_DELAY +
+        // TEST_PROPOSAL_EXPIRATION_PERIOD = 500 blocks after ending
+        let mock_proposals = vec![
+            (1, ProposalStatus::Passed, 100_050),
+            (2, ProposalStatus::Passed, 100_000),
+            (3, ProposalStatus::Passed, 100_200),
+            // expired
+            (4, ProposalStatus::Passed, 99_000),
+            // delay not ended
+            (5, ProposalStatus::Passed, 100_250),
+            (6, ProposalStatus::Executed, 100_000),
+            (7, ProposalStatus::Rejected, 100_000),
+        ];
+        for (id, status, end_height) in mock_proposals {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status,
+                    end_height,
+                    ..Default::default()
+                },
+            );
+        }
+
+        let env = mock_env_at_block_height(100_400);
+        let expiring = |proposal_id: u64, expiration_height: u64| ExpiringExecutableProposal {
+            proposal_id,
+            expiration_height,
+        };
+
+        // soonest expiration first
+        let res =
+            query_expiring_executable_proposals(deps.as_ref(), env.clone(), 150, None).unwrap();
+        assert_eq!(
+            res.proposals,
+            vec![expiring(2, 100_500), expiring(1, 100_550)]
+        );
+
+        let res =
+            query_expiring_executable_proposals(deps.as_ref(), env.clone(), 99, None).unwrap();
+        assert_eq!(res.proposals, vec![]);
 
+        let res =
+            query_expiring_executable_proposals(deps.as_ref(), env.clone(), 1_000, None).unwrap();
         assert_eq!(
+            res.proposals,
             vec![
-                attr("action", "cast_vote"),
-                attr("proposal_id", active_proposal_id.to_string()),
-                attr("voter", "voter"),
-                attr("vote", "for"),
-                attr("voting_power", 123.to_string()), // 100 (free) + 23 (locked)
-            ],
-            res.attributes
+                expiring(2, 100_500),
+                expiring(1, 100_550),
+                expiring(3, 100_700)
+            ]
         );
 
-        let proposal = PROPOSALS
-            .load(&deps.storage, U64Key::new(active_proposal_id))
-            .unwrap();
-        assert_eq!(proposal.for_votes, Uint128::new(123));
-        assert_eq!(proposal.against_votes, Uint128::new(0));
+        let res = query_expiring_executable_proposals(deps.as_ref(), env, 1_000, Some(1)).unwrap();
+        assert_eq!(res.proposals, vec![expiring(2, 100_500)]);
+    }
 
-        let proposal_vote = PROPOSAL_VOTES
-            .load(
-                &deps.storage,
-                (U64Key::new(active_proposal_id), &voter_address),
-            )
-            .unwrap();
+    #[test]
+    fn test_query_votable_proposals_for() {
+        let mut deps = th_setup(&[]);
 
-        assert_eq!(proposal_vote.option, ProposalVoteOption::For);
-        assert_eq!(proposal_vote.power, Uint128::new(123));
+        let mock_proposals = vec![
+            (1, ProposalStatus::Active, 99_000, 101_000),
+            (2, ProposalStatus::Active, 99_000, 101_000),
+            (3, ProposalStatus::Active, 99_000, 100_000),
+            // voting ended
+            (4, ProposalStatus::Active, 98_000, 99_999),
+            // voting not started
+            (5, ProposalStatus::Active, 100_001, 102_000),
+            (6, ProposalStatus::Rejected, 99_000, 101_000),
+        ];
+        for (id, status, start_height, end_height) in mock_proposals {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status,
+                    start_height,
+                    end_height,
+                    ..Default::default()
+                },
+            );
+        }
 
-        // Voting again with same address should fail
-        let msg = ExecuteMsg::CastVote {
-            proposal_id: active_proposal_id,
-            vote: ProposalVoteOption::For,
+        // voter already voted on proposal 2
+        let voter_address = Addr::unchecked("voter");
+        PROPOSAL_VOTES
+            .save(
+                &mut deps.storage,
+                (U64Key::new(2), &voter_address),
+                &ProposalVote {
+                    option: ProposalVoteOption::For,
+                    power: Uint128::new(100),
+                    weighted_power: Uint128::new(100),
+                },
+            )
+            .unwrap();
+
+        let env = mock_env_at_block_height(100_000);
+        let votable_ids = |res: ProposalsListResponse| -> Vec<u64> {
+            res.proposal_list
+                .iter()
+                .map(|proposal| proposal.proposal_id)
+                .collect()
         };
 
-        let env = mock_env(MockEnvParams {
-            block_height: active_proposal.start_height + 1,
-            ..Default::default()
-        });
-        let info = mock_info("voter");
-        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(response, ContractError::VoteUserAlreadyVoted {});
+        let res =
+            query_votable_proposals_for(deps.as_ref(), env.clone(), "voter".to_string(), None)
+                .unwrap();
+        assert_eq!(votable_ids(res), vec![1, 3]);
 
-        // Valid against vote
-        {
-            let msg = ExecuteMsg::CastVote {
-                proposal_id: active_proposal_id,
-                vote: ProposalVoteOption::Against,
-            };
+        let res = query_votable_proposals_for(
+            deps.as_ref(),
+            env.clone(),
+            "other_voter".to_string(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(votable_ids(res), vec![1, 2, 3]);
 
-            deps.querier.set_xmars_balance_at(
-                Addr::unchecked("voter2"),
-                active_proposal.start_height - 1,
-                Uint128::new(200),
-            );
+        let res =
+            query_votable_proposals_for(deps.as_ref(), env, "voter".to_string(), Some(1)).unwrap();
+        assert_eq!(votable_ids(res), vec![1]);
+    }
 
-            let env = mock_env(MockEnvParams {
-                block_height: active_proposal.start_height + 1,
-                ..Default::default()
-            });
-            let info = mock_info("voter2");
-            let res = execute(deps.as_mut(), env, info, msg).unwrap();
-            assert_eq!(
-                vec![
-                    attr("action", "cast_vote"),
-                    attr("proposal_id", active_proposal_id.to_string()),
-                    attr("voter", "voter2"),
-                    attr("vote", "against"),
-                    attr("voting_power", 200.to_string()),
-                ],
-                res.attributes
+    #[test]
+    fn test_query_proposal_tallies() {
+        let mut deps = th_setup(&[]);
+
+        let mock_proposals = vec![
+            (1, ProposalStatus::Passed, 300, 100),
+            (2, ProposalStatus::Active, 50, 80),
+            (3, ProposalStatus::Rejected, 10, 200),
+        ];
+        for (id, status, for_votes, against_votes) in mock_proposals {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status,
+                    for_votes: Uint128::new(for_votes),
+                    against_votes: Uint128::new(against_votes),
+                    ..Default::default()
+                },
             );
         }
 
-        // Extra for and against votes to check aggregates are computed correctly
-        deps.querier.set_xmars_balance_at(
-            Addr::unchecked("voter3"),
-            active_proposal.start_height - 1,
-            Uint128::new(300),
+        // nonexistent proposals are skipped and the requested order is kept
+        let res: ProposalTalliesResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(MockEnvParams::default()),
+                QueryMsg::ProposalTallies {
+                    ids: vec![3, 7, 1, 2],
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.tallies,
+            vec![
+                ProposalTally {
+                    proposal_id: 3,
+                    for_votes: Uint128::new(10),
+                    against_votes: Uint128::new(200),
+                    status: ProposalStatus::Rejected,
+                },
+                ProposalTally {
+                    proposal_id: 1,
+                    for_votes: Uint128::new(300),
+                    against_votes: Uint128::new(100),
+                    status: ProposalStatus::Passed,
+                },
+                ProposalTally {
+                    proposal_id: 2,
+                    for_votes: Uint128::new(50),
+                    against_votes: Uint128::new(80),
+                    status: ProposalStatus::Active,
+                },
+            ]
         );
 
-        deps.querier.set_xmars_balance_at(
-            Addr::unchecked("voter4"),
-            active_proposal.start_height - 1,
-            Uint128::new(400),
-        );
+        // number of ids is capped
+        query(
+            deps.as_ref(),
+            mock_env(MockEnvParams::default()),
+            QueryMsg::ProposalTallies {
+                ids: (1..=PAGINATION_MAX_LIMIT as u64 + 1).collect(),
+            },
+        )
+        .unwrap_err();
+    }
 
-        {
-            let msg = ExecuteMsg::CastVote {
-                proposal_id: active_proposal_id,
-                vote: ProposalVoteOption::For,
-            };
-            let env = mock_env(MockEnvParams {
-                block_height: active_proposal.start_height + 1,
-                ..Default::default()
-            });
-            let info = mock_info("voter3");
-            execute(deps.as_mut(), env, info, msg).unwrap();
-        }
+    #[test]
+    fn test_query_is_executable() {
+        let mut deps = th_setup(&[]);
 
-        {
-            let msg = ExecuteMsg::CastVote {
-                proposal_id: active_proposal_id,
-                vote: ProposalVoteOption::Against,
-            };
-            let env = mock_env(MockEnvParams {
-                block_height: active_proposal.start_height + 1,
+        let passed_proposal = th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Passed,
+                end_height: 100_000,
                 ..Default::default()
-            });
-            let info = mock_info("voter4");
-            execute(deps.as_mut(), env, info, msg).unwrap();
+            },
+        );
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 2,
+                status: ProposalStatus::Executed,
+                end_height: 100_000,
+                ..Default::default()
+            },
+        );
+
+        let delay_end_height = passed_proposal.end_height + TEST_PROPOSAL_EFFECTIVE_DELAY;
+        let expiration_height = delay_end_height + TEST_PROPOSAL_EXPIRATION_PERIOD;
+        let test_cases = vec![
+            (2, delay_end_height, ProposalExecutableReason::NotPassed),
+            (
+                1,
+                delay_end_height - 1,
+                ProposalExecutableReason::DelayNotEnded,
+            ),
+            (1, delay_end_height, ProposalExecutableReason::Ready),
+            (1, expiration_height, ProposalExecutableReason::Ready),
+            (1, expiration_height + 1, ProposalExecutableReason::Expired),
+        ];
+        for (proposal_id, block_height, expected_reason) in test_cases {
+            let res = query_is_executable(
+                deps.as_ref(),
+                mock_env_at_block_height(block_height),
+                proposal_id,
+            )
+            .unwrap();
+            assert_eq!(
+                res,
+                IsExecutableResponse {
+                    proposal_id,
+                    is_executable: expected_reason == ProposalExecutableReason::Ready,
+                    reason: expected_reason,
+                }
+            );
         }
 
-        let proposal = PROPOSALS
-            .load(&deps.storage, U64Key::new(active_proposal_id))
+        // query agrees with execution
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let env = mock_env_at_block_height(delay_end_height - 1);
+        let error_res =
+            execute(deps.as_mut(), env, mock_info("executer"), msg.clone()).unwrap_err();
+        assert_eq!(error_res, ContractError::ExecuteProposalDelayNotEnded {});
+
+        let env = mock_env_at_block_height(delay_end_height);
+        execute(deps.as_mut(), env, mock_info("executer"), msg).unwrap();
+        let res = query_is_executable(deps.as_ref(), mock_env_at_block_height(delay_end_height), 1)
             .unwrap();
-        assert_eq!(proposal.for_votes, Uint128::new(123 + 300));
-        assert_eq!(proposal.against_votes, Uint128::new(200 + 400));
+        assert_eq!(res.reason, ProposalExecutableReason::NotPassed);
     }
 
     #[test]
-    fn test_query_proposals() {
-        // Arrange
+    fn test_execute_proposals() {
         let mut deps = th_setup(&[]);
+        let contract_address = Addr::unchecked(MOCK_CONTRACT_ADDR);
+        let other_address = Addr::unchecked("other");
+        let new_code_id = 123;
 
-        let active_proposal_1_id = 1_u64;
-        th_build_mock_proposal(
+        let binary_msg = Binary::from(br#"{"key": 123}"#);
+        let initial_proposal = th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: active_proposal_1_id,
-                status: ProposalStatus::Active,
-                start_height: 100_000,
-                end_height: 100_100,
+                id: 1,
+                status: ProposalStatus::Passed,
+                end_height: 100_000,
+                messages: Some(vec![
+                    ProposalMessage {
+                        execution_order: 2,
+                        msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                            contract_addr: other_address.to_string(),
+                            msg: binary_msg.clone(),
+                            funds: vec![],
+                        }),
+                        memo: Some(String::from("ibc memo")),
+                        route: ExecuteRoute::Local,
+                    },
+                    ProposalMessage {
+                        execution_order: 3,
+                        msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                            contract_addr: contract_address.to_string(),
+                            msg: to_binary(&ExecuteMsg::UpdateConfig {
+                                config: CreateOrUpdateConfig::default(),
+                            })
+                            .unwrap(),
+                            funds: vec![],
+                        }),
+                        memo: None,
+                        route: ExecuteRoute::Local,
+                    },
+                    ProposalMessage {
+                        execution_order: 1,
+                        msg: CosmosMsg::Wasm(WasmMsg::Migrate {
+                            contract_addr: contract_address.to_string(),
+                            new_code_id,
+                            msg: binary_msg.clone(),
+                        }),
+                        memo: None,
+                        route: ExecuteRoute::Local,
+                    },
+                ]),
                 ..Default::default()
             },
         );
 
-        let active_proposal_2_id = 2_u64;
-        let msg = CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: String::from("test_address"),
-            msg: Binary::from(br#"{"some":123}"#),
-            funds: vec![],
+        let env = mock_env(MockEnvParams {
+            block_height: initial_proposal.end_height + TEST_PROPOSAL_EFFECTIVE_DELAY + 1,
+            ..Default::default()
         });
-        let messages = Option::from(vec![ProposalMessage {
-            execution_order: 0,
-            msg: msg.clone(),
-        }]);
-        th_build_mock_proposal(
-            deps.as_mut(),
-            MockProposal {
-                id: active_proposal_2_id,
-                status: ProposalStatus::Active,
-                start_height: 100_000,
-                end_height: 100_100,
-                messages,
-                ..Default::default()
-            },
+        let info = mock_info("executer");
+
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "execute_proposal"),
+                attr("proposal_id", 1.to_string()),
+                // memo of the second message in execution order
+                attr("memo_1", "ibc memo"),
+            ]
         );
 
-        let global_state = GlobalState {
-            proposal_count: 2_u64,
-        };
-        GLOBAL_STATE.save(&mut deps.storage, &global_state).unwrap();
-        // Assert corectly sorts asc
-        let res = query_proposals(deps.as_ref(), None, None).unwrap();
-        assert_eq!(res.proposal_count, 2);
-        assert_eq!(res.proposal_list.len(), 2);
-        assert_eq!(res.proposal_list[0].proposal_id, active_proposal_1_id);
-        assert_eq!(res.proposal_list[1].proposal_id, active_proposal_2_id);
-        assert_eq!(res.proposal_list[1].messages.clone().unwrap()[0].msg, msg);
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::reply_on_success(
+                    CosmosMsg::Wasm(WasmMsg::Migrate {
+                        contract_addr: contract_address.to_string(),
+                        new_code_id,
+                        msg: binary_msg.clone(),
+                    }),
+                    execution_reply_id(1, 0).unwrap()
+                ),
+                SubMsg::reply_on_success(
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: other_address.to_string(),
+                        funds: vec![],
+                        msg: binary_msg,
+                    }),
+                    execution_reply_id(1, 1).unwrap()
+                ),
+                SubMsg::reply_on_success(
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: contract_address.to_string(),
+                        funds: vec![],
+                        msg: to_binary(&ExecuteMsg::UpdateConfig {
+                            config: CreateOrUpdateConfig::default()
+                        })
+                        .unwrap(),
+                    }),
+                    execution_reply_id(1, 2).unwrap()
+                ),
+            ]
+        );
 
-        // Assert start != 0
-        let res = query_proposals(deps.as_ref(), Some(2), None).unwrap();
-        assert_eq!(res.proposal_count, 2);
-        assert_eq!(res.proposal_list.len(), 1);
-        assert_eq!(res.proposal_list[0].proposal_id, active_proposal_2_id);
+        let final_passed_proposal = PROPOSALS
+            .load(&mut deps.storage, U64Key::new(1_u64))
+            .unwrap();
 
-        // Assert start > length of collection
-        let res = query_proposals(deps.as_ref(), Some(99), None).unwrap();
-        assert_eq!(res.proposal_count, 2);
-        assert_eq!(res.proposal_list.len(), 0);
+        assert_eq!(ProposalStatus::Executed, final_passed_proposal.status);
+    }
 
-        // Assert limit
-        let res = query_proposals(deps.as_ref(), None, Some(1)).unwrap();
-        assert_eq!(res.proposal_count, 2);
-        assert_eq!(res.proposal_list.len(), 1);
-        assert_eq!(res.proposal_list[0].proposal_id, active_proposal_1_id);
+    #[test]
+    fn test_execute_proposal_executor_allowlist() {
+        let mut deps = th_setup(&[]);
 
-        // Assert limit greater than length of collection
-        let res = query_proposals(deps.as_ref(), None, Some(99)).unwrap();
-        assert_eq!(res.proposal_count, 2);
-        assert_eq!(res.proposal_list.len(), 2);
+        for id in 1..=2 {
+            th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Passed,
+                    end_height: 100_000,
+                    ..Default::default()
+                },
+            );
+        }
+        let block_height = 100_000 + TEST_PROPOSAL_EFFECTIVE_DELAY + 1;
+
+        // empty allowlist lets anyone execute
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let env = mock_env_at_block_height(block_height);
+        execute(deps.as_mut(), env, mock_info("anyone"), msg).unwrap();
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.executor_allowlist = vec![Addr::unchecked("executor")];
+                Ok(config)
+            })
+            .unwrap();
+
+        // caller not on the allowlist is rejected
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 2 };
+        let env = mock_env_at_block_height(block_height);
+        let error_res = execute(deps.as_mut(), env, mock_info("anyone"), msg.clone()).unwrap_err();
+        assert_eq!(error_res, MarsError::Unauthorized {}.into());
+
+        // allowed executor can execute
+        let env = mock_env_at_block_height(block_height);
+        execute(deps.as_mut(), env, mock_info("executor"), msg).unwrap();
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(2)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Executed);
     }
 
     #[test]
-    fn test_invalid_end_proposals() {
+    fn test_execute_proposal_routes() {
         let mut deps = th_setup(&[]);
+        let local_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("local_contract"),
+            msg: Binary::from(br#"{"key": 123}"#),
+            funds: vec![],
+        });
+        let remote_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("remote_contract"),
+            msg: Binary::from(br#"{"key": 456}"#),
+            funds: vec![],
+        });
+        let messages = vec![
+            ProposalMessage {
+                execution_order: 1,
+                msg: local_msg.clone(),
+                memo: None,
+                route: ExecuteRoute::Local,
+            },
+            ProposalMessage {
+                execution_order: 2,
+                msg: remote_msg.clone(),
+                memo: None,
+                route: ExecuteRoute::Ica {
+                    channel: String::from("channel-7"),
+                },
+            },
+        ];
 
-        let active_proposal_id = 1_u64;
-        let executed_proposal_id = 2_u64;
+        // Ica routes are rejected at submission without an ICA controller
+        {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                msg: to_binary(&ReceiveMsg::SubmitProposal {
+                    title: "A valid title".to_string(),
+                    description: "A valid description".to_string(),
+                    link: None,
+                    messages: Some(messages.clone()),
+                    auto_execute: None,
+                    category: None,
+                    min_quorum_power: None,
+                    execution_deadline_blocks: None,
+                })
+                .unwrap(),
+                sender: String::from("submitter"),
+                amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            });
+            let env = mock_env_at_block_height(100_000);
+            let info = mock_info("mars_token");
+            let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+            assert_eq!(error_res, ContractError::IcaRouteNotSupported {});
+        }
 
-        deps.querier
-            .set_xmars_address(Addr::unchecked("xmars_token"));
-        deps.querier
-            .set_xmars_total_supply_at(99_999, Uint128::new(100));
-        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
-        deps.querier
-            .set_vesting_total_voting_power_at(99_999, Uint128::zero());
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.ica_controller_address = Some(Addr::unchecked("ica_controller"));
+                Ok(config)
+            })
+            .unwrap();
 
         th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: active_proposal_id,
-                status: ProposalStatus::Active,
+                id: 1,
+                status: ProposalStatus::Passed,
                 end_height: 100_000,
-                ..Default::default()
-            },
-        );
-        th_build_mock_proposal(
-            deps.as_mut(),
-            MockProposal {
-                id: executed_proposal_id,
-                status: ProposalStatus::Executed,
+                messages: Some(messages),
                 ..Default::default()
             },
         );
 
-        // cannot end a proposal that has not ended its voting period
-        let msg = ExecuteMsg::EndProposal {
-            proposal_id: active_proposal_id,
-        };
-        let env = mock_env(MockEnvParams {
-            block_height: 100_000,
-            ..Default::default()
-        });
-        let info = mock_info("sender");
-        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(response, ContractError::EndProposalVotingPeriodNotEnded {});
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let env = mock_env_at_block_height(100_000 + TEST_PROPOSAL_EFFECTIVE_DELAY + 1);
+        let res = execute(deps.as_mut(), env, mock_info("executer"), msg).unwrap();
 
-        // cannot end a non active proposal
-        let msg = ExecuteMsg::EndProposal {
-            proposal_id: executed_proposal_id,
-        };
-        let env = mock_env(MockEnvParams {
-            block_height: 100_001,
-            ..Default::default()
-        });
-        let info = mock_info("sender");
-        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(response, ContractError::ProposalNotActive {});
+        // local messages are executed as is, Ica ones through the controller
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::reply_on_success(local_msg, execution_reply_id(1, 0).unwrap()),
+                SubMsg::reply_on_success(
+                    CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: String::from("ica_controller"),
+                        msg: to_binary(&IcaControllerExecuteMsg::SendTx {
+                            channel: String::from("channel-7"),
+                            msgs: vec![remote_msg],
+                        })
+                        .unwrap(),
+                        funds: vec![],
+                    }),
+                    execution_reply_id(1, 1).unwrap()
+                ),
+            ]
+        );
     }
 
     #[test]
-    fn test_end_proposal() {
+    fn test_execute_proposal_height_overflow() {
         let mut deps = th_setup(&[]);
 
-        deps.querier
-            .set_xmars_address(Addr::unchecked("xmars_token"));
-        deps.querier
-            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
-        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
-        deps.querier
-            .set_vesting_total_voting_power_at(99_999, Uint128::zero());
-
-        let proposal_threshold = Decimal::from_ratio(51_u128, 100_u128);
-        let proposal_quorum = Decimal::from_ratio(2_u128, 100_u128);
-        let proposal_end_height = 100_000u64;
-
         CONFIG
             .update(&mut deps.storage, |mut config| -> StdResult<Config> {
-                config.proposal_required_threshold = proposal_threshold;
-                config.proposal_required_quorum = proposal_quorum;
+                config.proposal_effective_delay = u64::MAX - 1_000;
+                config.proposal_expiration_period = u64::MAX - 1_000;
                 Ok(config)
             })
             .unwrap();
 
-        // end passed proposal
-        let initial_passed_proposal = th_build_mock_proposal(
+        th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
                 id: 1,
-                status: ProposalStatus::Active,
-                for_votes: Uint128::new(11_000),
-                against_votes: Uint128::new(10_000),
-                start_height: 90_000,
-                end_height: proposal_end_height + 1,
+                status: ProposalStatus::Passed,
+                end_height: 100_000,
                 ..Default::default()
             },
         );
 
-        let msg = ExecuteMsg::EndProposal { proposal_id: 1 };
-
-        let env = mock_env(MockEnvParams {
-            block_height: initial_passed_proposal.end_height + 1,
-            ..Default::default()
-        });
-        let info = mock_info("sender");
-
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
-
-        assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "end_proposal"),
-                attr("proposal_id", 1.to_string()),
-                attr("proposal_result", "passed"),
-            ]
-        );
-
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("executer");
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
         assert_eq!(
-            res.messages,
-            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: String::from("mars_token"),
-                funds: vec![],
-                msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: String::from("submitter"),
-                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
-                })
-                .unwrap(),
-            })),]
+            error_res,
+            ContractError::Overflow(OverflowError::new(
+                OverflowOperation::Add,
+                100_000,
+                u64::MAX - 1_000
+            ))
         );
+    }
 
-        let final_passed_proposal = PROPOSALS.load(&deps.storage, U64Key::new(1u64)).unwrap();
-        assert_eq!(final_passed_proposal.status, ProposalStatus::Passed);
+    #[test]
+    fn test_execution_results() {
+        let mut deps = th_setup(&[]);
 
-        // end rejected proposal (no quorum)
-        let initial_passed_proposal = th_build_mock_proposal(
+        let proposal_message = |execution_order: u64| ProposalMessage {
+            execution_order,
+            msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: format!("contract_{}", execution_order),
+                msg: Binary::from(br#"{"some":123}"#),
+                funds: vec![],
+            }),
+            memo: None,
+            route: ExecuteRoute::Local,
+        };
+        th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: 2,
-                status: ProposalStatus::Active,
-                for_votes: Uint128::new(11),
-                against_votes: Uint128::new(10),
-                end_height: proposal_end_height + 1,
-                start_height: 90_000,
+                id: 1,
+                status: ProposalStatus::Passed,
+                end_height: 100_000,
+                messages: Some(vec![
+                    proposal_message(5),
+                    proposal_message(1),
+                    proposal_message(3),
+                ]),
                 ..Default::default()
             },
         );
 
-        let msg = ExecuteMsg::EndProposal { proposal_id: 2 };
-
-        let env = mock_env(MockEnvParams {
-            block_height: initial_passed_proposal.end_height + 1,
-            ..Default::default()
-        });
-        let info = mock_info("sender");
+        let execution_height = 100_000 + TEST_PROPOSAL_EFFECTIVE_DELAY + 1;
+        let env = mock_env_at_block_height(execution_height);
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("executer"), msg).unwrap();
 
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        // every message replies on success with an id of its own
+        assert_eq!(res.messages.len(), 3);
+        for (position, sub_msg) in res.messages.iter().enumerate() {
+            assert_eq!(sub_msg.reply_on, ReplyOn::Success);
+            assert_eq!(sub_msg.id, execution_reply_id(1, position).unwrap());
+        }
+        let reply_ids: Vec<u64> = res.messages.iter().map(|sub_msg| sub_msg.id).collect();
+
+        // nothing is recorded until the messages reply
+        let res = query_execution_results(deps.as_ref(), 1).unwrap();
+        assert!(res.results.is_empty());
+
+        for id in reply_ids {
+            let res = reply(
+                deps.as_mut(),
+                env.clone(),
+                Reply {
+                    id,
+                    result: ContractResult::Ok(SubMsgExecutionResponse {
+                        events: vec![],
+                        data: None,
+                    }),
+                },
+            )
+            .unwrap();
+            assert_eq!(res.attributes[0], attr("action", "record_execution_result"));
+        }
 
+        let res = query_execution_results(deps.as_ref(), 1).unwrap();
         assert_eq!(
-            res.attributes,
+            res.results,
             vec![
-                attr("action", "end_proposal"),
-                attr("proposal_id", 2.to_string()),
-                attr("proposal_result", "rejected"),
+                ExecutionResult {
+                    position: 0,
+                    execution_order: 1,
+                    height: execution_height,
+                },
+                ExecutionResult {
+                    position: 1,
+                    execution_order: 3,
+                    height: execution_height,
+                },
+                ExecutionResult {
+                    position: 2,
+                    execution_order: 5,
+                    height: execution_height,
+                },
             ]
         );
 
+        // proposal ids that do not fit in the reply id cannot be tracked
         assert_eq!(
-            res.messages,
-            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: String::from("mars_token"),
-                msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: String::from("staking"),
-                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
-                })
-                .unwrap(),
-                funds: vec![],
-            }))]
+            execution_reply_id(1 << 31, 0).unwrap_err(),
+            StdError::generic_err("proposal message execution cannot be tracked")
         );
+    }
 
-        let final_passed_proposal = PROPOSALS.load(&deps.storage, U64Key::new(2_u64)).unwrap();
-        assert_eq!(final_passed_proposal.status, ProposalStatus::Rejected);
+    #[test]
+    fn test_execute_proposal_reentrancy() {
+        let mut deps = th_setup(&[]);
+        let env = mock_env_at_block_height(100_000 + TEST_PROPOSAL_EFFECTIVE_DELAY + 1);
+        let contract_address = env.contract.address.clone();
 
-        // end rejected proposal (no threshold)
-        let initial_passed_proposal = th_build_mock_proposal(
+        let nested_msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: 3,
-                status: ProposalStatus::Active,
-                for_votes: Uint128::new(10_000),
-                against_votes: Uint128::new(11_000),
-                start_height: 90_000,
-                end_height: proposal_end_height + 1,
+                id: 1,
+                status: ProposalStatus::Passed,
+                end_height: 100_000,
+                messages: Some(vec![ProposalMessage {
+                    execution_order: 0,
+                    msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: contract_address.to_string(),
+                        msg: to_binary(&nested_msg).unwrap(),
+                        funds: vec![],
+                    }),
+                    memo: None,
+                    route: ExecuteRoute::Local,
+                }]),
                 ..Default::default()
             },
         );
 
-        let msg = ExecuteMsg::EndProposal { proposal_id: 3 };
-
-        let env = mock_env(MockEnvParams {
-            block_height: initial_passed_proposal.end_height + 1,
-            ..Default::default()
-        });
-        let info = mock_info("sender");
-
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
-
-        assert_eq!(
-            res.attributes,
-            vec![
-                attr("action", "end_proposal"),
-                attr("proposal_id", 3.to_string()),
-                attr("proposal_result", "rejected"),
-            ]
-        );
-
+        let info = mock_info("executer");
+        let res = execute(deps.as_mut(), env.clone(), info, nested_msg.clone()).unwrap();
         assert_eq!(
             res.messages,
-            vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: String::from("mars_token"),
-                msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                    recipient: String::from("staking"),
-                    amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
-                })
-                .unwrap(),
-                funds: vec![],
-            }))]
+            vec![SubMsg::reply_on_success(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract_address.to_string(),
+                    msg: to_binary(&nested_msg).unwrap(),
+                    funds: vec![],
+                }),
+                execution_reply_id(1, 0).unwrap()
+            )]
         );
 
-        let final_passed_proposal = PROPOSALS.load(&deps.storage, U64Key::new(3_u64)).unwrap();
-        assert_eq!(final_passed_proposal.status, ProposalStatus::Rejected);
+        // nested call dispatched by the proposal itself hits the status guard
+        let info = mock_info(contract_address.as_str());
+        let error_res = execute(deps.as_mut(), env, info, nested_msg).unwrap_err();
+        assert_eq!(error_res, ContractError::ExecuteProposalNotPassed {});
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Executed);
     }
 
     #[test]
-    fn test_invalid_execute_proposals() {
+    fn test_execute_fast_track_proposals() {
         let mut deps = th_setup(&[]);
 
-        let passed_proposal_id = 1_u64;
-        let executed_proposal_id = 2_u64;
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.fast_track_targets = vec![Addr::unchecked("oracle")];
+                Ok(config)
+            })
+            .unwrap();
+
+        let execute_msg = |contract_addr: &str| ProposalMessage {
+            execution_order: 0,
+            msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from(contract_addr),
+                msg: Binary::from(br#"{"key": 123}"#),
+                funds: vec![],
+            }),
+            memo: None,
+            route: ExecuteRoute::Local,
+        };
 
-        let passed_proposal = th_build_mock_proposal(
+        // proposal with all messages to fast track targets can be executed right after passing
+        th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: passed_proposal_id,
+                id: 1,
                 status: ProposalStatus::Passed,
                 end_height: 100_000,
+                messages: Some(vec![execute_msg("oracle"), execute_msg("oracle")]),
                 ..Default::default()
             },
         );
-        let executed_proposal = th_build_mock_proposal(
+
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("executer");
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        // proposal with a message to another contract has to wait for the delay
+        th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: executed_proposal_id,
-                status: ProposalStatus::Executed,
+                id: 2,
+                status: ProposalStatus::Passed,
+                end_height: 100_000,
+                messages: Some(vec![execute_msg("oracle"), execute_msg("other")]),
                 ..Default::default()
             },
         );
 
-        // cannot execute a non Passed proposal
-        let msg = ExecuteMsg::ExecuteProposal {
-            proposal_id: executed_proposal_id,
-        };
-        let env = mock_env(MockEnvParams {
-            block_height: executed_proposal.end_height + TEST_PROPOSAL_EFFECTIVE_DELAY + 1,
-            ..Default::default()
-        });
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 2 };
+        let env = mock_env_at_block_height(100_001);
         let info = mock_info("executer");
-        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(response, ContractError::ExecuteProposalNotPassed {},);
+        let error_res = execute(deps.as_mut(), env, info, msg.clone()).unwrap_err();
+        assert_eq!(error_res, ContractError::ExecuteProposalDelayNotEnded {});
 
-        // cannot execute a proposal before the effective delay has passed
-        let msg = ExecuteMsg::ExecuteProposal {
-            proposal_id: passed_proposal_id,
-        };
-        let env = mock_env(MockEnvParams {
-            block_height: passed_proposal.end_height + 1,
-            ..Default::default()
-        });
+        let env = mock_env_at_block_height(100_000 + TEST_PROPOSAL_EFFECTIVE_DELAY);
         let info = mock_info("executer");
-        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(response, ContractError::ExecuteProposalDelayNotEnded {});
+        execute(deps.as_mut(), env, info, msg).unwrap();
 
-        // cannot execute an expired proposal
-        let msg = ExecuteMsg::ExecuteProposal {
-            proposal_id: passed_proposal_id,
+        // so does a proposal executing a fast track target through an interchain account
+        let ica_msg = ProposalMessage {
+            route: ExecuteRoute::Ica {
+                channel: String::from("channel-0"),
+            },
+            ..execute_msg("oracle")
         };
-        let env = mock_env(MockEnvParams {
-            block_height: passed_proposal.end_height
-                + TEST_PROPOSAL_EFFECTIVE_DELAY
-                + TEST_PROPOSAL_EXPIRATION_PERIOD
-                + 1,
-            ..Default::default()
-        });
-        let info = mock_info("executer");
-        let response = execute(deps.as_mut(), env, info, msg).unwrap_err();
-        assert_eq!(response, ContractError::ExecuteProposalExpired {});
-    }
-
-    #[test]
-    fn test_execute_proposals() {
-        let mut deps = th_setup(&[]);
-        let contract_address = Addr::unchecked(MOCK_CONTRACT_ADDR);
-        let other_address = Addr::unchecked("other");
-        let new_code_id = 123;
-
-        let binary_msg = Binary::from(br#"{"key": 123}"#);
-        let initial_proposal = th_build_mock_proposal(
+        th_build_mock_proposal(
             deps.as_mut(),
             MockProposal {
-                id: 1,
+                id: 3,
                 status: ProposalStatus::Passed,
                 end_height: 100_000,
-                messages: Some(vec![
-                    ProposalMessage {
-                        execution_order: 2,
-                        msg: CosmosMsg::Wasm(WasmMsg::Execute {
-                            contract_addr: other_address.to_string(),
-                            msg: binary_msg.clone(),
-                            funds: vec![],
-                        }),
-                    },
-                    ProposalMessage {
-                        execution_order: 3,
-                        msg: CosmosMsg::Wasm(WasmMsg::Execute {
-                            contract_addr: contract_address.to_string(),
-                            msg: to_binary(&ExecuteMsg::UpdateConfig {
-                                config: CreateOrUpdateConfig::default(),
-                            })
-                            .unwrap(),
-                            funds: vec![],
-                        }),
-                    },
-                    ProposalMessage {
-                        execution_order: 1,
-                        msg: CosmosMsg::Wasm(WasmMsg::Migrate {
-                            contract_addr: contract_address.to_string(),
-                            new_code_id,
-                            msg: binary_msg.clone(),
-                        }),
-                    },
-                ]),
+                messages: Some(vec![ica_msg]),
                 ..Default::default()
             },
         );
 
-        let env = mock_env(MockEnvParams {
-            block_height: initial_proposal.end_height + TEST_PROPOSAL_EFFECTIVE_DELAY + 1,
-            ..Default::default()
-        });
+        let msg = ExecuteMsg::ExecuteProposal { proposal_id: 3 };
+        let env = mock_env_at_block_height(100_001);
         let info = mock_info("executer");
+        let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(error_res, ContractError::ExecuteProposalDelayNotEnded {});
+    }
+
+    #[test]
+    fn test_end_proposal_auto_execute() {
+        let mut deps = th_setup(&[]);
+
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.fast_track_targets = vec![Addr::unchecked("oracle")];
+                config.proposal_required_threshold = Decimal::percent(50);
+                config.proposal_required_quorum = Decimal::percent(50);
+                Ok(config)
+            })
+            .unwrap();
+
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier
+            .set_xmars_total_supply_at(89_999, Uint128::new(100_000));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+        deps.querier
+            .set_vesting_total_voting_power_at(89_999, Uint128::zero());
+
+        let execute_msg = |contract_addr: &str| ProposalMessage {
+            execution_order: 0,
+            msg: CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from(contract_addr),
+                msg: Binary::from(br#"{"key": 123}"#),
+                funds: vec![],
+            }),
+            memo: None,
+            route: ExecuteRoute::Local,
+        };
+
+        // auto execute is rejected on submission when a message targets another contract
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages: Some(vec![execute_msg("oracle"), execute_msg("other")]),
+                auto_execute: Some(true),
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env_at_block_height(100_000);
+        let info = mock_info("mars_token");
+        let error_res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(
+            error_res,
+            ContractError::invalid_proposal(
+                "auto execute is only allowed when all messages target fast track contracts"
+            )
+        );
+
+        for (id, auto_execute) in [(1, true), (2, false)] {
+            let mut proposal = th_build_mock_proposal(
+                deps.as_mut(),
+                MockProposal {
+                    id,
+                    status: ProposalStatus::Active,
+                    for_votes: Uint128::new(60_000),
+                    start_height: 90_000,
+                    end_height: 100_000,
+                    messages: Some(vec![execute_msg("oracle")]),
+                    ..Default::default()
+                },
+            );
+            proposal.auto_execute = auto_execute;
+            PROPOSALS
+                .save(&mut deps.storage, U64Key::new(id), &proposal)
+                .unwrap();
+        }
+
+        // flagged proposal is executed through ExecuteProposal as soon as it passes
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("submitter");
+        let msg = ExecuteMsg::EndProposal { proposal_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1],
+            SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                    msg: to_binary(&ExecuteMsg::ExecuteProposal { proposal_id: 1 }).unwrap(),
+                    funds: vec![],
+                }),
+                AUTO_EXECUTE_REPLY_ID_FLAG | 1,
+            )
+        );
+        assert!(res.attributes.contains(&attr("auto_executed", "true")));
 
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Passed);
+
+        // the council can execute it right away, even with an executor allowlist
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.executor_allowlist = vec![Addr::unchecked("executor")];
+                Ok(config)
+            })
+            .unwrap();
+        let info = mock_info(MOCK_CONTRACT_ADDR);
         let msg = ExecuteMsg::ExecuteProposal { proposal_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(res.messages[0].msg, execute_msg("oracle").msg);
 
-        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(1)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Executed);
 
+        // a failed execution is recorded. The chain reverts it, leaving the proposal Passed
+        let res = reply(
+            deps.as_mut(),
+            env,
+            Reply {
+                id: AUTO_EXECUTE_REPLY_ID_FLAG | 1,
+                result: ContractResult::Err(String::from("oracle error")),
+            },
+        )
+        .unwrap();
         assert_eq!(
             res.attributes,
             vec![
-                attr("action", "execute_proposal"),
-                attr("proposal_id", 1.to_string()),
+                attr("action", "record_auto_execute_failure"),
+                attr("proposal_id", "1"),
+                attr("error", "oracle error"),
             ]
         );
 
-        assert_eq!(
-            res.messages,
-            vec![
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Migrate {
-                    contract_addr: contract_address.to_string(),
-                    new_code_id,
-                    msg: binary_msg.clone(),
-                })),
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: other_address.to_string(),
-                    funds: vec![],
-                    msg: binary_msg,
-                })),
-                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: contract_address.to_string(),
-                    funds: vec![],
-                    msg: to_binary(&ExecuteMsg::UpdateConfig {
-                        config: CreateOrUpdateConfig::default()
-                    })
-                    .unwrap(),
-                })),
-            ]
+        // proposal without the flag only passes
+        let env = mock_env_at_block_height(100_001);
+        let info = mock_info("submitter");
+        let msg = ExecuteMsg::EndProposal { proposal_id: 2 };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let proposal = PROPOSALS.load(&deps.storage, U64Key::new(2)).unwrap();
+        assert_eq!(proposal.status, ProposalStatus::Passed);
+    }
+
+    #[test]
+    fn test_query_proposal_time_remaining() {
+        let mut deps = th_setup(&[]);
+
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 1,
+                status: ProposalStatus::Active,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
+        );
+        th_build_mock_proposal(
+            deps.as_mut(),
+            MockProposal {
+                id: 2,
+                status: ProposalStatus::Passed,
+                start_height: 100_000,
+                end_height: 100_100,
+                ..Default::default()
+            },
         );
 
-        let final_passed_proposal = PROPOSALS
-            .load(&mut deps.storage, U64Key::new(1_u64))
-            .unwrap();
+        // active proposal mid voting
+        let res =
+            query_proposal_time_remaining(deps.as_ref(), mock_env_at_block_height(100_040), 1)
+                .unwrap();
+        assert_eq!(
+            res,
+            ProposalTimeRemainingResponse {
+                proposal_id: 1,
+                blocks_remaining: 60,
+                estimated_seconds_remaining: 60 * DEFAULT_AVG_BLOCK_TIME_SECONDS,
+            }
+        );
 
-        assert_eq!(ProposalStatus::Executed, final_passed_proposal.status);
+        // active proposal after the voting period end
+        let res =
+            query_proposal_time_remaining(deps.as_ref(), mock_env_at_block_height(100_101), 1)
+                .unwrap();
+        assert_eq!(res.blocks_remaining, 0);
+        assert_eq!(res.estimated_seconds_remaining, 0);
+
+        // ended proposal
+        let res =
+            query_proposal_time_remaining(deps.as_ref(), mock_env_at_block_height(100_040), 2)
+                .unwrap();
+        assert_eq!(res.blocks_remaining, 0);
+        assert_eq!(res.estimated_seconds_remaining, 0);
     }
 
     #[test]
@@ -2103,6 +12995,7 @@ mod tests {
             active_proposal_id,
             Option::None,
             Option::None,
+            Option::None,
         )
         .unwrap();
         assert_eq!(res.votes.len(), 5);
@@ -2122,6 +13015,7 @@ mod tests {
             active_proposal_id,
             Option::from(String::from("voter4")),
             Option::None,
+            Option::None,
         )
         .unwrap();
         assert_eq!(res.votes.len(), 1);
@@ -2133,10 +13027,39 @@ mod tests {
             active_proposal_id,
             Option::None,
             Option::from(1),
+            Option::None,
         )
         .unwrap();
         assert_eq!(res.votes.len(), 1);
         assert_eq!(res.votes[0].voter_address, Addr::unchecked("voter1"));
+
+        // Assert filter by option
+        let res = query_proposal_votes(
+            deps.as_ref(),
+            active_proposal_id,
+            Option::None,
+            Option::None,
+            Option::from(ProposalVoteOption::For),
+        )
+        .unwrap();
+        let voter_addresses: Vec<String> = res
+            .votes
+            .into_iter()
+            .map(|vote| vote.voter_address)
+            .collect();
+        assert_eq!(voter_addresses, vec!["voter1", "voter2", "voter3"]);
+
+        // Assert limit counts matching votes only
+        let res = query_proposal_votes(
+            deps.as_ref(),
+            active_proposal_id,
+            Option::None,
+            Option::from(1),
+            Option::from(ProposalVoteOption::Against),
+        )
+        .unwrap();
+        assert_eq!(res.votes.len(), 1);
+        assert_eq!(res.votes[0].voter_address, Addr::unchecked("voter4"));
     }
 
     // TEST HELPERS
@@ -2152,6 +13075,40 @@ mod tests {
             proposal_required_deposit: Some(TEST_PROPOSAL_REQUIRED_DEPOSIT),
             proposal_required_quorum: Some(Decimal::one()),
             proposal_required_threshold: Some(Decimal::one()),
+            submitter_cooldown: Some(0),
+            emergency_owner: None,
+            title_max: None,
+            description_max: None,
+            link_max: None,
+            slashed_deposit_destination: None,
+            end_proposal_reward: None,
+            snapshot_offset: None,
+            reclaim_grace_period: None,
+            fast_track_targets: None,
+            quorum_basis: None,
+            quorum_excluded_addresses: None,
+            discussion_period: None,
+            mint_per_proposal_cap: None,
+            mint_total_cap: None,
+            reservation_period: None,
+            allow_privileged_token_calls: None,
+            early_vote_bonus: None,
+            voting_token_override: None,
+            avg_block_time_seconds: None,
+            min_for_votes_absolute: None,
+            proposal_required_deposit_ratio: None,
+            min_vote_power: None,
+            dedupe_window: None,
+            executor_allowlist: None,
+            ica_controller_address: None,
+            surge_window: None,
+            surge_threshold: None,
+            surge_multiplier: None,
+            allowed_categories: None,
+            accepted_deposit_tokens: None,
+            refund_signaling_always: None,
+            auto_expire_grace: None,
+            require_call_simulation: None,
         };
 
         let msg = InstantiateMsg { config };
@@ -2194,19 +13151,110 @@ mod tests {
             status: mock_proposal.status,
             for_votes: mock_proposal.for_votes,
             against_votes: mock_proposal.against_votes,
+            for_voter_count: 0,
+            against_voter_count: 0,
+            early_vote_bonus_votes: Uint128::zero(),
             start_height: mock_proposal.start_height,
+            vote_start_height: mock_proposal.start_height,
             end_height: mock_proposal.end_height,
+            voting_power_snapshot_block: mock_proposal.start_height.saturating_sub(1),
             title: "A valid title".to_string(),
             description: "A description".to_string(),
             link: None,
             messages: mock_proposal.messages,
             deposit_amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            deposit_token: None,
+            required_deposit_at_submission: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+            deposit_status: DepositStatus::Locked,
+            co_sponsors: vec![],
+            final_quorum: None,
+            final_threshold: None,
+            final_total_voting_power: None,
+            amended_at: None,
+            auto_execute: false,
+            category: None,
+            min_quorum_power: None,
+            execution_deadline_blocks: None,
+            implementation_note: None,
         };
 
         PROPOSALS
             .save(deps.storage, U64Key::new(mock_proposal.id), &proposal)
             .unwrap();
 
+        if proposal.status == ProposalStatus::Active {
+            GLOBAL_STATE
+                .update(deps.storage, |mut global_state| -> StdResult<_> {
+                    global_state.locked_deposits += proposal.deposit_amount;
+                    Ok(global_state)
+                })
+                .unwrap();
+        }
+
         proposal
     }
+
+    /// Submit a proposal at the given height and have it pass with the votes of a single voter
+    /// holding the whole xMARS supply. Lowers the quorum and threshold from the th_setup ones
+    /// so a unanimous vote passes. Returns the id of the proposal
+    fn th_submit_and_pass_proposal(
+        deps: &mut OwnedDeps<MockStorage, MockApi, MarsMockQuerier>,
+        submit_height: u64,
+        messages: Option<Vec<ProposalMessage>>,
+    ) -> u64 {
+        CONFIG
+            .update(&mut deps.storage, |mut config| -> StdResult<Config> {
+                config.proposal_required_quorum = Decimal::percent(2);
+                config.proposal_required_threshold = Decimal::percent(51);
+                Ok(config)
+            })
+            .unwrap();
+        deps.querier
+            .set_xmars_address(Addr::unchecked("xmars_token"));
+        deps.querier.set_xmars_balance_at(
+            Addr::unchecked("voter"),
+            submit_height - 1,
+            Uint128::new(100),
+        );
+        deps.querier
+            .set_xmars_total_supply_at(submit_height - 1, Uint128::new(100));
+        deps.querier.set_vesting_address(Addr::unchecked("vesting"));
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            msg: to_binary(&ReceiveMsg::SubmitProposal {
+                title: "A valid title".to_string(),
+                description: "A valid description".to_string(),
+                link: None,
+                messages,
+                auto_execute: None,
+                category: None,
+                min_quorum_power: None,
+                execution_deadline_blocks: None,
+            })
+            .unwrap(),
+            sender: String::from("submitter"),
+            amount: TEST_PROPOSAL_REQUIRED_DEPOSIT,
+        });
+        let env = mock_env_at_block_height(submit_height);
+        execute(deps.as_mut(), env, mock_info("mars_token"), msg).unwrap();
+        let proposal_id = GLOBAL_STATE.load(&deps.storage).unwrap().proposal_count;
+
+        let msg = ExecuteMsg::CastVote {
+            proposal_id,
+            vote: ProposalVoteOption::For,
+        };
+        let env = mock_env_at_block_height(submit_height + 1);
+        execute(deps.as_mut(), env, mock_info("voter"), msg).unwrap();
+
+        let end_height = PROPOSALS
+            .load(&deps.storage, U64Key::new(proposal_id))
+            .unwrap()
+            .end_height;
+        let msg = ExecuteMsg::EndProposal { proposal_id };
+        let env = mock_env_at_block_height(end_height + 1);
+        let res = execute(deps.as_mut(), env, mock_info("anyone"), msg).unwrap();
+        assert_eq!(res.attributes[2], attr("proposal_result", "passed"));
+
+        proposal_id
+    }
 }
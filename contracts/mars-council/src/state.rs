@@ -1,8 +1,45 @@
-use crate::{Config, GlobalState, Proposal, ProposalVote};
-use cosmwasm_std::Addr;
+use crate::{
+    Config, ConfigHistoryEntry, ExecutionResult, GlobalState, Proposal, ProposalReservation,
+    ProposalStatusChange, ProposalVote,
+};
+use cosmwasm_std::{Addr, CosmosMsg, Empty, Uint128};
 use cw_storage_plus::{Item, Map, U64Key};
 
 pub const CONFIG: Item<Config> = Item::new("config");
+/// Config set by each update, keyed by update height. Updates in the same block are kept in order
+pub const CONFIG_HISTORY: Map<U64Key, Vec<ConfigHistoryEntry>> = Map::new("config_history");
+/// Proposal whose messages are being executed, which config updates made by the council itself
+/// come from. Removed once its last message executes
+pub const EXECUTING_PROPOSAL: Item<u64> = Item::new("executing_proposal");
 pub const GLOBAL_STATE: Item<GlobalState> = Item::new("global_state");
+/// Emergency owner proposed through TransferGuardianship, until it accepts
+pub const PENDING_GUARDIAN: Item<Addr> = Item::new("pending_guardian");
 pub const PROPOSALS: Map<U64Key, Proposal> = Map::new("proposals");
+/// Finalized proposals moved out of PROPOSALS through ArchiveProposals
+pub const ARCHIVED_PROPOSALS: Map<U64Key, Proposal> = Map::new("archived_proposals");
 pub const PROPOSAL_VOTES: Map<(U64Key, &Addr), ProposalVote> = Map::new("proposal_votes");
+pub const LAST_SUBMISSION: Map<&Addr, u64> = Map::new("last_submission");
+pub const PROPOSALS_BY_SUBMITTER: Map<(&Addr, U64Key), Empty> = Map::new("proposals_by_submitter");
+pub const PROPOSAL_COUNT_BY_STATUS: Map<&str, u64> = Map::new("proposal_count_by_status");
+/// Number of proposals that started at each block height
+pub const PROPOSAL_COUNT_BY_START_HEIGHT: Map<U64Key, u64> =
+    Map::new("proposal_count_by_start_height");
+/// Proposal ids by the hash of their title, submitter and start height (see `proposal_title_hash`)
+pub const TITLE_HASH_INDEX: Map<&[u8], u64> = Map::new("title_hash_index");
+/// Status changes of each proposal, keyed by proposal id and sequence number
+pub const STATUS_HISTORY: Map<(U64Key, U64Key), ProposalStatusChange> = Map::new("status_history");
+/// Proposal messages that executed successfully, keyed by proposal id and position in execution
+/// order
+pub const EXECUTION_RESULTS: Map<(U64Key, U64Key), ExecutionResult> = Map::new("execution_results");
+pub const RESERVATIONS: Map<U64Key, ProposalReservation> = Map::new("reservations");
+/// Deposit refunds of ended proposals that failed, to be retried through ClaimPendingDeposit
+pub const PENDING_DEPOSITS: Map<U64Key, CosmosMsg> = Map::new("pending_deposits");
+/// Co-sponsor deposits of ended proposals that were refunded, to be claimed through
+/// ClaimSponsorRefund
+pub const SPONSOR_REFUNDS: Map<(U64Key, &Addr), Uint128> = Map::new("sponsor_refunds");
+/// Slashed deposits of rejected proposals whose transfer failed, to be sent through
+/// SweepPendingSlashes
+pub const PENDING_SLASHES: Item<Uint128> = Item::new("pending_slashes");
+/// Last voter whose vote was backfilled, for legacy proposals whose votes span several migrate
+/// pages
+pub const MIGRATION_VOTE_CURSORS: Map<U64Key, Addr> = Map::new("migration_vote_cursors");
@@ -7,7 +7,10 @@ use cw20::{
     AllAccountsResponse, AllAllowancesResponse, AllowanceResponse, BalanceResponse,
     TokenInfoResponse,
 };
-use mars_ma_token::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use mars_core::asset::Asset;
+use mars_core::xmars_token::TotalSupplyResponse;
+use mars_ma_token::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use mars_ma_token::AllowanceSpendLimit;
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -18,9 +21,13 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
     export_schema(&schema_for!(AllowanceResponse), &out_dir);
+    export_schema(&schema_for!(AllowanceSpendLimit), &out_dir);
     export_schema(&schema_for!(BalanceResponse), &out_dir);
     export_schema(&schema_for!(TokenInfoResponse), &out_dir);
     export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
     export_schema(&schema_for!(AllAccountsResponse), &out_dir);
+    export_schema(&schema_for!(Asset), &out_dir);
+    export_schema(&schema_for!(TotalSupplyResponse), &out_dir);
 }
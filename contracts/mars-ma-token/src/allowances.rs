@@ -1,10 +1,286 @@
-use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Response, Uint128};
-use cw20::Cw20ReceiveMsg;
+use cosmwasm_std::{
+    Addr, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage, Timestamp, Uint128,
+};
+use cw20::{AllAllowancesResponse, AllowanceInfo, Cw20ReceiveMsg, Expiration};
 use cw20_base::allowances::deduct_allowance;
+use cw20_base::state::ALLOWANCES;
 use cw20_base::ContractError;
 
 use crate::core;
-use crate::state::CONFIG;
+use crate::state::{ALLOWANCE_SPEND_LIMITS, CONFIG, PENDING_ALLOWANCE_TRANSFERS};
+use crate::AllowanceSpendLimit;
+
+/// Same as cw20-base's increase allowance, but returns an overflow error instead of panicking
+/// when the new allowance does not fit in an Uint128. New allowances increased without an
+/// expiration get the configured default allowance expiry
+pub fn execute_increase_allowance(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+    amount: Uint128,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    let key = (&info.sender, &spender_addr);
+    let expires = match expires {
+        None if !ALLOWANCES.has(deps.storage, key) => {
+            CONFIG.load(deps.storage)?.default_allowance_expiry
+        }
+        expires => expires,
+    };
+
+    ALLOWANCES.update(deps.storage, key, |allow| -> StdResult<_> {
+        let mut val = allow.unwrap_or_default();
+        if let Some(exp) = expires {
+            val.expires = exp;
+        }
+        val.allowance = val.allowance.checked_add(amount)?;
+        Ok(val)
+    })?;
+
+    let res = Response::new()
+        .add_attribute("action", "increase_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn execute_set_allowance_spend_limit(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+    per_block_limit: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    if spender_addr == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    let key = (&info.sender, &spender_addr);
+    let limit_attribute = match per_block_limit {
+        Some(per_block_limit) => {
+            let spend_limit = match ALLOWANCE_SPEND_LIMITS.may_load(deps.storage, key)? {
+                Some(spend_limit) => AllowanceSpendLimit {
+                    per_block_limit,
+                    ..spend_limit
+                },
+                None => AllowanceSpendLimit {
+                    per_block_limit,
+                    last_spend_block: 0,
+                    spent_this_block: Uint128::zero(),
+                },
+            };
+            ALLOWANCE_SPEND_LIMITS.save(deps.storage, key, &spend_limit)?;
+            per_block_limit.to_string()
+        }
+        None => {
+            ALLOWANCE_SPEND_LIMITS.remove(deps.storage, key);
+            String::from("none")
+        }
+    };
+
+    let res = Response::new()
+        .add_attribute("action", "set_allowance_spend_limit")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("per_block_limit", limit_attribute);
+    Ok(res)
+}
+
+/// Propose moving the caller's allowance for spender to new_owner. Nothing moves until
+/// new_owner accepts it, as an allowance spends the owner's balance
+pub fn execute_transfer_allowance(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    let new_owner_addr = deps.api.addr_validate(&new_owner)?;
+    if new_owner_addr == spender_addr {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+
+    let key = (&info.sender, &spender_addr);
+    if !ALLOWANCES.has(deps.storage, key) {
+        return Err(ContractError::NoAllowance {});
+    }
+    PENDING_ALLOWANCE_TRANSFERS.save(deps.storage, key, &new_owner_addr)?;
+
+    let res = Response::new()
+        .add_attribute("action", "transfer_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("new_owner", new_owner)
+        .add_attribute("spender", spender);
+    Ok(res)
+}
+
+/// Move the owner's allowance (and spend limit, if any) for spender to the caller, keeping
+/// amount and expiration, if the owner transferred it to the caller
+pub fn execute_accept_allowance(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+    spender: String,
+) -> Result<Response, ContractError> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+
+    let old_key = (&owner_addr, &spender_addr);
+    let new_key = (&info.sender, &spender_addr);
+
+    match PENDING_ALLOWANCE_TRANSFERS.may_load(deps.storage, old_key)? {
+        Some(new_owner) if new_owner == info.sender => {}
+        _ => {
+            return Err(StdError::generic_err("No pending allowance transfer to the sender").into())
+        }
+    }
+    PENDING_ALLOWANCE_TRANSFERS.remove(deps.storage, old_key);
+
+    let allowance = ALLOWANCES
+        .may_load(deps.storage, old_key)?
+        .ok_or(ContractError::NoAllowance {})?;
+    if ALLOWANCES.has(deps.storage, new_key) {
+        return Err(StdError::generic_err("New owner already has an allowance for spender").into());
+    }
+    ALLOWANCES.remove(deps.storage, old_key);
+    ALLOWANCES.save(deps.storage, new_key, &allowance)?;
+
+    if let Some(spend_limit) = ALLOWANCE_SPEND_LIMITS.may_load(deps.storage, old_key)? {
+        ALLOWANCE_SPEND_LIMITS.remove(deps.storage, old_key);
+        ALLOWANCE_SPEND_LIMITS.save(deps.storage, new_key, &spend_limit)?;
+    }
+
+    let res = Response::new()
+        .add_attribute("action", "accept_allowance")
+        .add_attribute("owner", owner)
+        .add_attribute("new_owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("amount", allowance.allowance);
+    Ok(res)
+}
+
+/// Remove the caller's allowance (and spend limit, if any) for spender. Unlike decrease
+/// allowance, the amount left does not need to be known so the spender cannot race it
+pub fn execute_revoke_allowance(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    spender: String,
+) -> Result<Response, ContractError> {
+    let spender_addr = deps.api.addr_validate(&spender)?;
+
+    let key = (&info.sender, &spender_addr);
+    ALLOWANCES.remove(deps.storage, key);
+    ALLOWANCE_SPEND_LIMITS.remove(deps.storage, key);
+    PENDING_ALLOWANCE_TRANSFERS.remove(deps.storage, key);
+
+    let res = Response::new()
+        .add_attribute("action", "revoke_allowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender);
+    Ok(res)
+}
+
+/// Deduct amount from the allowance, making sure the spender does not go over its per block
+/// spend limit (if any)
+fn deduct_allowance_with_spend_limit(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    spender: &Addr,
+    block: &BlockInfo,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let key = (owner, spender);
+    if let Some(mut spend_limit) = ALLOWANCE_SPEND_LIMITS.may_load(storage, key)? {
+        if spend_limit.last_spend_block != block.height {
+            spend_limit.last_spend_block = block.height;
+            spend_limit.spent_this_block = Uint128::zero();
+        }
+
+        spend_limit.spent_this_block = spend_limit
+            .spent_this_block
+            .checked_add(amount)
+            .map_err(StdError::from)?;
+        if spend_limit.spent_this_block > spend_limit.per_block_limit {
+            return Err(StdError::generic_err(format!(
+                "Cannot spend more than {} per block",
+                spend_limit.per_block_limit
+            ))
+            .into());
+        }
+
+        ALLOWANCE_SPEND_LIMITS.save(storage, key, &spend_limit)?;
+    }
+
+    deduct_allowance(storage, owner, spender, block, amount)?;
+
+    Ok(())
+}
+
+pub fn query_allowance_spend_limit(
+    deps: Deps,
+    owner: String,
+    spender: String,
+) -> StdResult<Option<AllowanceSpendLimit>> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    ALLOWANCE_SPEND_LIMITS.may_load(deps.storage, (&owner_addr, &spender_addr))
+}
+
+// Query limits, same as cw20-base's enumerable queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn query_expiring_allowances(
+    deps: Deps,
+    owner: String,
+    before_block: u64,
+    before_time: Option<Timestamp>,
+    limit: Option<u32>,
+) -> StdResult<AllAllowancesResponse> {
+    let owner_addr = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    let allowances: StdResult<Vec<AllowanceInfo>> = ALLOWANCES
+        .prefix(&owner_addr)
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, allowance)) => match allowance.expires {
+                Expiration::AtHeight(height) => height <= before_block,
+                Expiration::AtTime(time) => match before_time {
+                    Some(before_time) => time <= before_time,
+                    None => false,
+                },
+                Expiration::Never {} => false,
+            },
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| {
+            let (k, v) = item?;
+            Ok(AllowanceInfo {
+                spender: String::from_utf8(k)?,
+                allowance: v.allowance,
+                expires: v.expires,
+            })
+        })
+        .collect();
+
+    Ok(AllAllowancesResponse {
+        allowances: allowances?,
+    })
+}
 
 pub fn execute_transfer_from(
     deps: DepsMut,
@@ -18,7 +294,7 @@ pub fn execute_transfer_from(
     let owner_addr = deps.api.addr_validate(&owner)?;
 
     // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    deduct_allowance_with_spend_limit(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
     let config = CONFIG.load(deps.storage)?;
     let messages = core::transfer(deps.storage, &config, owner_addr, rcpt_addr, amount, true)?;
@@ -46,7 +322,7 @@ pub fn execute_send_from(
     let owner_addr = deps.api.addr_validate(&owner)?;
 
     // deduct allowance before doing anything else have enough allowance
-    deduct_allowance(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
+    deduct_allowance_with_spend_limit(deps.storage, &owner_addr, &info.sender, &env.block, amount)?;
 
     let config = CONFIG.load(deps.storage)?;
     let transfer_messages =
@@ -75,7 +351,10 @@ mod tests {
     use super::*;
 
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{attr, to_binary, Addr, Binary, CosmosMsg, StdError, SubMsg, WasmMsg};
+    use cosmwasm_std::{
+        attr, to_binary, Addr, Binary, CosmosMsg, OverflowError, OverflowOperation, StdError,
+        SubMsg, WasmMsg,
+    };
 
     use cw20::{AllowanceResponse, Cw20ReceiveMsg, Expiration};
     use cw20_base::allowances::query_allowance;
@@ -352,4 +631,506 @@ mod tests {
         let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
         assert_eq!(err, ContractError::Expired {});
     }
+
+    #[test]
+    fn increase_allowance_overflow_returns_error() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::MAX - Uint128::new(10),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(11),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::overflow(OverflowError::new(
+                OverflowOperation::Add,
+                Uint128::MAX - Uint128::new(10),
+                Uint128::new(11)
+            )))
+        );
+
+        // allowance is unchanged
+        let allowance = query_allowance(deps.as_ref(), owner, spender).unwrap();
+        assert_eq!(allowance.allowance, Uint128::MAX - Uint128::new(10));
+    }
+
+    #[test]
+    fn increase_allowance_applies_default_expiry() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let spender2 = String::from("addr0003");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+        let default_expiry = Expiration::AtHeight(500_000);
+        CONFIG
+            .update(deps.as_mut().storage, |mut config| -> StdResult<_> {
+                config.default_allowance_expiry = Some(default_expiry);
+                Ok(config)
+            })
+            .unwrap();
+
+        // new allowance without expiration gets the default
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(100),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender.clone()).unwrap();
+        assert_eq!(allowance.expires, default_expiry);
+
+        // an explicit expiration overrides the default
+        let explicit_expiry = Expiration::AtHeight(123_456);
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender2.clone(),
+            amount: Uint128::new(100),
+            expires: Some(explicit_expiry),
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender2.clone()).unwrap();
+        assert_eq!(allowance.expires, explicit_expiry);
+
+        // increasing an existing allowance without expiration keeps its expiration
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender2.clone(),
+            amount: Uint128::new(50),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let allowance = query_allowance(deps.as_ref(), owner, spender2).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(150));
+        assert_eq!(allowance.expires, explicit_expiry);
+    }
+
+    #[test]
+    fn transfer_allowance_moves_allowance_to_new_owner() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let new_owner = String::from("addr0003");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+
+        let transfer_allowance_msg = ExecuteMsg::TransferAllowance {
+            spender: spender.clone(),
+            new_owner: new_owner.clone(),
+        };
+
+        // cannot transfer a missing allowance
+        let info = mock_info(owner.as_ref(), &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            transfer_allowance_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NoAllowance {});
+
+        // provide an allowance with expiration and spend limit
+        let expires = Expiration::AtHeight(123_456);
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(7777),
+            expires: Some(expires),
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SetAllowanceSpendLimit {
+            spender: spender.clone(),
+            per_block_limit: Some(Uint128::new(1000)),
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // transfer it
+        let info = mock_info(owner.as_ref(), &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            transfer_allowance_msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "transfer_allowance"),
+                attr("owner", owner.clone()),
+                attr("new_owner", new_owner.clone()),
+                attr("spender", spender.clone()),
+            ]
+        );
+
+        // only the new owner can accept it
+        let accept_allowance_msg = ExecuteMsg::AcceptAllowance {
+            owner: owner.clone(),
+            spender: spender.clone(),
+        };
+        let info = mock_info(spender.as_ref(), &[]);
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            accept_allowance_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::generic_err(
+                "No pending allowance transfer to the sender"
+            ))
+        );
+
+        let info = mock_info(new_owner.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, accept_allowance_msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "accept_allowance"),
+                attr("owner", owner.clone()),
+                attr("new_owner", new_owner.clone()),
+                attr("spender", spender.clone()),
+                attr("amount", "7777"),
+            ]
+        );
+
+        // allowance and spend limit moved to the new owner's bucket
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender.clone()).unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
+        let allowance = query_allowance(deps.as_ref(), new_owner.clone(), spender.clone()).unwrap();
+        assert_eq!(
+            allowance,
+            AllowanceResponse {
+                allowance: Uint128::new(7777),
+                expires,
+            }
+        );
+
+        let spend_limit =
+            query_allowance_spend_limit(deps.as_ref(), owner.clone(), spender.clone()).unwrap();
+        assert_eq!(spend_limit, None);
+        let spend_limit =
+            query_allowance_spend_limit(deps.as_ref(), new_owner.clone(), spender.clone())
+                .unwrap()
+                .unwrap();
+        assert_eq!(spend_limit.per_block_limit, Uint128::new(1000));
+
+        // cannot overwrite an existing allowance of the new owner
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(10),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, transfer_allowance_msg).unwrap();
+        let msg = ExecuteMsg::AcceptAllowance { owner, spender };
+        let info = mock_info(new_owner.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::generic_err(
+                "New owner already has an allowance for spender"
+            ))
+        );
+    }
+
+    #[test]
+    fn unaccepted_allowance_transfer_cannot_be_spent() {
+        let mut deps = mock_dependencies(&[]);
+        let attacker = String::from("addr0001");
+        let accomplice = String::from("addr0002");
+        let victim = String::from("addr0003");
+
+        do_instantiate(deps.as_mut(), &victim, Uint128::new(999999));
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: accomplice.clone(),
+            amount: Uint128::MAX,
+            expires: None,
+        };
+        let info = mock_info(attacker.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::TransferAllowance {
+            spender: accomplice.clone(),
+            new_owner: victim.clone(),
+        };
+        let info = mock_info(attacker.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the victim never accepted, so the accomplice has no allowance over its balance
+        let allowance = query_allowance(deps.as_ref(), victim.clone(), accomplice.clone()).unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: victim.clone(),
+            recipient: attacker,
+            amount: Uint128::new(999999),
+        };
+        let info = mock_info(accomplice.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::NoAllowance {});
+        assert_eq!(get_balance(deps.as_ref(), victim), Uint128::new(999999));
+    }
+
+    #[test]
+    fn revoke_allowance_removes_allowance() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+
+        let revoke_msg = ExecuteMsg::RevokeAllowance {
+            spender: spender.clone(),
+        };
+        let expected_attributes = vec![
+            attr("action", "revoke_allowance"),
+            attr("owner", owner.clone()),
+            attr("spender", spender.clone()),
+        ];
+
+        // revoking a missing allowance does nothing
+        let info = mock_info(owner.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, revoke_msg.clone()).unwrap();
+        assert_eq!(res.attributes, expected_attributes);
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender.clone()).unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
+
+        // provide an allowance with spend limit
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(7777),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SetAllowanceSpendLimit {
+            spender: spender.clone(),
+            per_block_limit: Some(Uint128::new(1000)),
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // revoke removes both, whatever the amount left
+        let info = mock_info(owner.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, revoke_msg.clone()).unwrap();
+        assert_eq!(res.attributes, expected_attributes);
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender.clone()).unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
+        let spend_limit =
+            query_allowance_spend_limit(deps.as_ref(), owner.clone(), spender.clone()).unwrap();
+        assert_eq!(spend_limit, None);
+
+        // and revoking again is a no-op
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, revoke_msg).unwrap();
+        let allowance = query_allowance(deps.as_ref(), owner, spender).unwrap();
+        assert_eq!(allowance, AllowanceResponse::default());
+    }
+
+    #[test]
+    fn query_expiring_allowances_filters_by_expiration() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+
+        let now = mock_env().block.time;
+        let allowances = vec![
+            ("spender1", Expiration::Never {}),
+            ("spender2", Expiration::AtHeight(20_000)),
+            ("spender3", Expiration::AtHeight(30_000)),
+            ("spender4", Expiration::AtTime(now.plus_seconds(100))),
+            ("spender5", Expiration::AtTime(now.plus_seconds(1_000))),
+        ];
+        for (spender, expires) in allowances {
+            let msg = ExecuteMsg::IncreaseAllowance {
+                spender: String::from(spender),
+                amount: Uint128::new(100),
+                expires: Some(expires),
+            };
+            let info = mock_info(owner.as_ref(), &[]);
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let allowance_info = |spender: &str, expires: Expiration| AllowanceInfo {
+            spender: String::from(spender),
+            allowance: Uint128::new(100),
+            expires,
+        };
+
+        // height cutoff only selects height expirations up to it
+        let res =
+            query_expiring_allowances(deps.as_ref(), owner.clone(), 20_000, None, None).unwrap();
+        assert_eq!(
+            res.allowances,
+            vec![allowance_info("spender2", Expiration::AtHeight(20_000))]
+        );
+
+        // time cutoff also selects time expirations up to it
+        let res = query_expiring_allowances(
+            deps.as_ref(),
+            owner.clone(),
+            20_000,
+            Some(now.plus_seconds(100)),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            res.allowances,
+            vec![
+                allowance_info("spender2", Expiration::AtHeight(20_000)),
+                allowance_info("spender4", Expiration::AtTime(now.plus_seconds(100))),
+            ]
+        );
+
+        // limit
+        let res = query_expiring_allowances(
+            deps.as_ref(),
+            owner,
+            30_000,
+            Some(now.plus_seconds(1_000)),
+            Some(3),
+        )
+        .unwrap();
+        assert_eq!(
+            res.allowances,
+            vec![
+                allowance_info("spender2", Expiration::AtHeight(20_000)),
+                allowance_info("spender3", Expiration::AtHeight(30_000)),
+                allowance_info("spender4", Expiration::AtTime(now.plus_seconds(100))),
+            ]
+        );
+    }
+
+    #[test]
+    fn transfer_from_respects_per_block_spend_limit() {
+        let mut deps = mock_dependencies(&[]);
+        let owner = String::from("addr0001");
+        let spender = String::from("addr0002");
+        let rcpt = String::from("addr0003");
+
+        do_instantiate(deps.as_mut(), &owner, Uint128::new(999999));
+
+        // provide an allowance
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: spender.clone(),
+            amount: Uint128::new(10000),
+            expires: None,
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // owner cannot set a spend limit on itself
+        let msg = ExecuteMsg::SetAllowanceSpendLimit {
+            spender: owner.clone(),
+            per_block_limit: Some(Uint128::new(1000)),
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotSetOwnAccount {});
+
+        // limit spender to 1000 per block
+        let msg = ExecuteMsg::SetAllowanceSpendLimit {
+            spender: spender.clone(),
+            per_block_limit: Some(Uint128::new(1000)),
+        };
+        let info = mock_info(owner.as_ref(), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "set_allowance_spend_limit"),
+                attr("owner", owner.clone()),
+                attr("spender", spender.clone()),
+                attr("per_block_limit", "1000"),
+            ]
+        );
+
+        let transfer_msg = |amount: u128| ExecuteMsg::TransferFrom {
+            owner: owner.clone(),
+            recipient: rcpt.clone(),
+            amount: Uint128::new(amount),
+        };
+
+        // spend up to the limit in one block
+        let env = mock_env();
+        let info = mock_info(spender.as_ref(), &[]);
+        execute(deps.as_mut(), env.clone(), info.clone(), transfer_msg(600)).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), transfer_msg(400)).unwrap();
+
+        // going over the limit in the same block fails even if allowance remains
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), transfer_msg(1)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::generic_err(
+                "Cannot spend more than 1000 per block"
+            ))
+        );
+
+        let spend_limit =
+            query_allowance_spend_limit(deps.as_ref(), owner.clone(), spender.clone())
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            spend_limit,
+            AllowanceSpendLimit {
+                per_block_limit: Uint128::new(1000),
+                last_spend_block: env.block.height,
+                spent_this_block: Uint128::new(1000),
+            }
+        );
+
+        // spending succeeds again in the next block
+        let mut env = mock_env();
+        env.block.height += 1;
+        execute(deps.as_mut(), env, info.clone(), transfer_msg(1000)).unwrap();
+
+        assert_eq!(get_balance(deps.as_ref(), rcpt.clone()), Uint128::new(2000));
+        let allowance = query_allowance(deps.as_ref(), owner.clone(), spender.clone()).unwrap();
+        assert_eq!(allowance.allowance, Uint128::new(8000));
+
+        // removing the limit allows spending more in a single block
+        let msg = ExecuteMsg::SetAllowanceSpendLimit {
+            spender: spender.clone(),
+            per_block_limit: None,
+        };
+        let owner_info = mock_info(owner.as_ref(), &[]);
+        execute(deps.as_mut(), mock_env(), owner_info, msg).unwrap();
+        assert_eq!(
+            query_allowance_spend_limit(deps.as_ref(), owner.clone(), spender).unwrap(),
+            None
+        );
+
+        let mut env = mock_env();
+        env.block.height += 1;
+        execute(deps.as_mut(), env, info, transfer_msg(5000)).unwrap();
+        assert_eq!(get_balance(deps.as_ref(), rcpt), Uint128::new(7000));
+    }
 }
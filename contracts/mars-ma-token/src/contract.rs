@@ -1,14 +1,12 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, QueryRequest, Response,
-    StdResult, Uint128, WasmMsg, WasmQuery,
+    to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, QueryRequest, Response,
+    StdError, StdResult, Uint128, WasmMsg, WasmQuery,
 };
 use cw2::set_contract_version;
-use cw20::{BalanceResponse, Cw20ReceiveMsg};
-use cw20_base::allowances::{
-    execute_decrease_allowance, execute_increase_allowance, query_allowance,
-};
+use cw20::{BalanceResponse, Cw20ReceiveMsg, Expiration};
+use cw20_base::allowances::{execute_decrease_allowance, query_allowance};
 use cw20_base::contract::{
     create_accounts, execute_update_marketing, execute_upload_logo, query_balance,
     query_download_logo, query_marketing_info, query_minter, query_token_info,
@@ -16,14 +14,22 @@ use cw20_base::contract::{
 use cw20_base::enumerable::{query_all_accounts, query_all_allowances};
 use cw20_base::state::{BALANCES, TOKEN_INFO};
 use cw20_base::ContractError;
+use cw_storage_plus::Item;
+use serde::{Deserialize, Serialize};
 
+use mars_core::asset::{Asset, AssetType};
 use mars_core::cw20_core::instantiate_token_info_and_marketing;
 use mars_core::red_bank;
+use mars_core::xmars_token::TotalSupplyResponse;
 
-use crate::allowances::{execute_send_from, execute_transfer_from};
+use crate::allowances::{
+    execute_accept_allowance, execute_increase_allowance, execute_revoke_allowance,
+    execute_send_from, execute_set_allowance_spend_limit, execute_transfer_allowance,
+    execute_transfer_from, query_allowance_spend_limit, query_expiring_allowances,
+};
 use crate::core;
-use crate::msg::{BalanceAndTotalSupplyResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::CONFIG;
+use crate::msg::{BalanceAndTotalSupplyResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{capture_total_supply_snapshot, find_total_supply_snapshot_value_at, CONFIG};
 use crate::Config;
 
 // version info for migration info
@@ -33,7 +39,7 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -51,6 +57,7 @@ pub fn instantiate(
 
     let total_supply = create_accounts(&mut deps, &base_msg.initial_balances)?;
     instantiate_token_info_and_marketing(&mut deps, base_msg, total_supply)?;
+    capture_total_supply_snapshot(deps.storage, &env, total_supply)?;
 
     // store token config
     CONFIG.save(
@@ -58,6 +65,8 @@ pub fn instantiate(
         &Config {
             red_bank_address: deps.api.addr_validate(&msg.red_bank_address)?,
             incentives_address: deps.api.addr_validate(&msg.incentives_address)?,
+            underlying_asset: msg.underlying_asset,
+            default_allowance_expiry: msg.default_allowance_expiry,
         },
     )?;
 
@@ -84,6 +93,9 @@ pub fn execute(
         ExecuteMsg::Transfer { recipient, amount } => {
             execute_transfer(deps, env, info, recipient, amount)
         }
+        ExecuteMsg::TransferBatch { transfers } => {
+            execute_transfer_batch(deps, env, info, transfers)
+        }
         ExecuteMsg::TransferOnLiquidation {
             sender,
             recipient,
@@ -96,6 +108,7 @@ pub fn execute(
             msg,
         } => execute_send(deps, env, info, contract, amount, msg),
         ExecuteMsg::Mint { recipient, amount } => execute_mint(deps, env, info, recipient, amount),
+        ExecuteMsg::MintBatch { mints } => execute_mint_batch(deps, env, info, mints),
         ExecuteMsg::IncreaseAllowance {
             spender,
             amount,
@@ -110,6 +123,19 @@ pub fn execute(
         } => Ok(execute_decrease_allowance(
             deps, env, info, spender, amount, expires,
         )?),
+        ExecuteMsg::SetAllowanceSpendLimit {
+            spender,
+            per_block_limit,
+        } => execute_set_allowance_spend_limit(deps, env, info, spender, per_block_limit),
+        ExecuteMsg::TransferAllowance { spender, new_owner } => {
+            execute_transfer_allowance(deps, env, info, spender, new_owner)
+        }
+        ExecuteMsg::AcceptAllowance { owner, spender } => {
+            execute_accept_allowance(deps, env, info, owner, spender)
+        }
+        ExecuteMsg::RevokeAllowance { spender } => {
+            execute_revoke_allowance(deps, env, info, spender)
+        }
         ExecuteMsg::TransferFrom {
             owner,
             recipient,
@@ -126,6 +152,9 @@ pub fn execute(
             description,
             marketing,
         } => execute_update_marketing(deps, env, info, project, description, marketing),
+        ExecuteMsg::UpdateTokenInfo { name, symbol } => {
+            execute_update_token_info(deps, env, info, name, symbol)
+        }
         ExecuteMsg::UploadLogo(logo) => execute_upload_logo(deps, env, info, logo),
     }
 }
@@ -162,6 +191,31 @@ pub fn execute_transfer(
     Ok(res)
 }
 
+pub fn execute_transfer_batch(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    transfers_unchecked: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "transfer_batch")
+        .add_attribute("from", info.sender.clone());
+
+    let mut transfers = Vec::with_capacity(transfers_unchecked.len());
+    for (recipient_unchecked, amount) in transfers_unchecked {
+        transfers.push((deps.api.addr_validate(&recipient_unchecked)?, amount));
+        res = res
+            .add_attribute("to", recipient_unchecked)
+            .add_attribute("amount", amount);
+    }
+
+    let messages = core::transfer_batch(deps.storage, &config, info.sender, transfers)?;
+
+    Ok(res.add_messages(messages))
+}
+
 pub fn execute_transfer_on_liquidation(
     deps: DepsMut,
     _env: Env,
@@ -192,7 +246,7 @@ pub fn execute_transfer_on_liquidation(
 
 pub fn execute_burn(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     user_unchecked: String,
     amount: Uint128,
@@ -213,11 +267,12 @@ pub fn execute_burn(
 
     // reduce total_supply
     let mut total_supply_before = Uint128::zero();
-    TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
+    let token_info = TOKEN_INFO.update(deps.storage, |mut info| -> StdResult<_> {
         total_supply_before = info.total_supply;
         info.total_supply = info.total_supply.checked_sub(amount)?;
         Ok(info)
     })?;
+    capture_total_supply_snapshot(deps.storage, &env, token_info.total_supply)?;
 
     let res = Response::new()
         .add_message(core::balance_change_msg(
@@ -234,7 +289,7 @@ pub fn execute_burn(
 
 pub fn execute_mint(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient_unchecked: String,
     amount: Uint128,
@@ -258,6 +313,7 @@ pub fn execute_mint(
         }
     }
     TOKEN_INFO.save(deps.storage, &token_info)?;
+    capture_total_supply_snapshot(deps.storage, &env, token_info.total_supply)?;
 
     // add amount to recipient balance
     let rcpt_address = deps.api.addr_validate(&recipient_unchecked)?;
@@ -278,6 +334,98 @@ pub fn execute_mint(
     Ok(res)
 }
 
+pub fn execute_mint_batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mints: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    let mut token_info = TOKEN_INFO.load(deps.storage)?;
+    if token_info.mint.is_none() || token_info.mint.as_ref().unwrap().minter != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let total_supply_before = token_info.total_supply;
+
+    // update supply once for the whole batch and enforce cap
+    let mut batch_amount = Uint128::zero();
+    for (_, amount) in mints.iter() {
+        if amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        batch_amount += *amount;
+    }
+    token_info.total_supply += batch_amount;
+    if let Some(limit) = token_info.get_cap() {
+        if token_info.total_supply > limit {
+            return Err(ContractError::CannotExceedCap {});
+        }
+    }
+    TOKEN_INFO.save(deps.storage, &token_info)?;
+    capture_total_supply_snapshot(deps.storage, &env, token_info.total_supply)?;
+
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "mint_batch")
+        .add_attribute("total_amount", batch_amount);
+
+    // add amounts to recipient balances
+    for (recipient_unchecked, amount) in mints {
+        let rcpt_address = deps.api.addr_validate(&recipient_unchecked)?;
+        let rcpt_balance_before = core::increase_balance(deps.storage, &rcpt_address, amount)?;
+
+        res = res
+            .add_message(core::balance_change_msg(
+                config.incentives_address.clone(),
+                rcpt_address,
+                rcpt_balance_before,
+                total_supply_before,
+            )?)
+            .add_attribute("to", recipient_unchecked)
+            .add_attribute("amount", amount);
+    }
+
+    Ok(res)
+}
+
+pub fn execute_update_token_info(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    name: String,
+    symbol: String,
+) -> Result<Response, ContractError> {
+    // only money market can rename the token
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.red_bank_address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut token_info = TOKEN_INFO.load(deps.storage)?;
+
+    // validate name and symbol with the same rules as on instantiation
+    cw20_base::msg::InstantiateMsg {
+        name: name.clone(),
+        symbol: symbol.clone(),
+        decimals: token_info.decimals,
+        initial_balances: vec![],
+        mint: None,
+        marketing: None,
+    }
+    .validate()?;
+
+    token_info.name = name.clone();
+    token_info.symbol = symbol.clone();
+    TOKEN_INFO.save(deps.storage, &token_info)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_token_info")
+        .add_attribute("name", name)
+        .add_attribute("symbol", symbol);
+    Ok(res)
+}
+
 pub fn execute_send(
     deps: DepsMut,
     _env: Env,
@@ -321,12 +469,71 @@ pub fn execute_send(
     Ok(res)
 }
 
+// MIGRATION
+
+/// Config stored before the underlying asset was added
+#[derive(Serialize, Deserialize)]
+struct LegacyConfig {
+    red_bank_address: Addr,
+    incentives_address: Addr,
+    #[serde(default)]
+    default_allowance_expiry: Option<Expiration>,
+}
+
+const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // Configs already storing the underlying asset are left as they are
+    if CONFIG.load(deps.storage).is_ok() {
+        return Ok(Response::new().add_attribute("action", "migrate"));
+    }
+
+    let legacy_config = LEGACY_CONFIG.load(deps.storage)?;
+    let markets: red_bank::MarketsListResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: legacy_config.red_bank_address.to_string(),
+            msg: to_binary(&red_bank::msg::QueryMsg::MarketsList {})?,
+        }))?;
+    let market = markets
+        .markets_list
+        .into_iter()
+        .find(|market| market.ma_token_address == env.contract.address)
+        .ok_or_else(|| StdError::not_found("red bank market of the token"))?;
+    let underlying_asset = match market.asset_type {
+        AssetType::Native => Asset::Native {
+            denom: market.denom,
+        },
+        AssetType::Cw20 => Asset::Cw20 {
+            contract_addr: market.asset_label,
+        },
+    };
+
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            red_bank_address: legacy_config.red_bank_address,
+            incentives_address: legacy_config.incentives_address,
+            underlying_asset: underlying_asset.clone(),
+            default_allowance_expiry: legacy_config.default_allowance_expiry,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        ("action", "migrate"),
+        ("underlying_asset", &underlying_asset.get_attributes().0),
+    ]))
+}
+
 // QUERY
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Balance { address } => to_binary(&query_balance(deps, address)?),
+        QueryMsg::BalanceBatch { addresses } => to_binary(&query_balance_batch(deps, addresses)?),
         QueryMsg::BalanceAndTotalSupply { address } => {
             to_binary(&query_balance_and_total_supply(deps, address)?)
         }
@@ -335,11 +542,26 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Allowance { owner, spender } => {
             to_binary(&query_allowance(deps, owner, spender)?)
         }
+        QueryMsg::AllowanceSpendLimit { owner, spender } => {
+            to_binary(&query_allowance_spend_limit(deps, owner, spender)?)
+        }
         QueryMsg::AllAllowances {
             owner,
             start_after,
             limit,
         } => to_binary(&query_all_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::ExpiringAllowances {
+            owner,
+            before_block,
+            before_time,
+            limit,
+        } => to_binary(&query_expiring_allowances(
+            deps,
+            owner,
+            before_block,
+            before_time,
+            limit,
+        )?),
         QueryMsg::AllAccounts { start_after, limit } => {
             to_binary(&query_all_accounts(deps, start_after, limit)?)
         }
@@ -348,9 +570,33 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::UnderlyingAssetBalance { address } => {
             to_binary(&query_underlying_asset_balance(deps, env, address)?)
         }
+        QueryMsg::UnderlyingAsset {} => to_binary(&query_underlying_asset(deps)?),
+        QueryMsg::TotalSupplyAt { block } => to_binary(&query_total_supply_at(deps, block)?),
     }
 }
 
+const MAX_BALANCE_BATCH_ADDRESSES: usize = 30;
+
+fn query_balance_batch(deps: Deps, addresses: Vec<String>) -> StdResult<Vec<(String, Uint128)>> {
+    if addresses.len() > MAX_BALANCE_BATCH_ADDRESSES {
+        return Err(StdError::generic_err(format!(
+            "Cannot query more than {} balances at once",
+            MAX_BALANCE_BATCH_ADDRESSES
+        )));
+    }
+
+    addresses
+        .into_iter()
+        .map(|address_unchecked| {
+            let address = deps.api.addr_validate(&address_unchecked)?;
+            let balance = BALANCES
+                .may_load(deps.storage, &address)?
+                .unwrap_or_default();
+            Ok((address_unchecked, balance))
+        })
+        .collect()
+}
+
 fn query_balance_and_total_supply(
     deps: Deps,
     address_unchecked: String,
@@ -389,10 +635,26 @@ pub fn query_underlying_asset_balance(
     Ok(BalanceResponse { balance: query })
 }
 
+pub fn query_underlying_asset(deps: Deps) -> StdResult<Asset> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(config.underlying_asset)
+}
+
+pub fn query_total_supply_at(deps: Deps, block: u64) -> StdResult<TotalSupplyResponse> {
+    let snapshot = find_total_supply_snapshot_value_at(deps.storage, block)?;
+    Ok(TotalSupplyResponse {
+        total_supply: snapshot.unwrap_or_else(Uint128::zero),
+        snapshot_found: snapshot.is_some(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, Addr, CosmosMsg, StdError, SubMsg, WasmMsg};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{
+        attr, coins, from_binary, Addr, CosmosMsg, StdError, Storage, SubMsg, WasmMsg,
+    };
+    use mars_core::red_bank::MarketInfo;
 
     use cw20::{
         Cw20Coin, Logo, LogoInfo, MarketingInfoResponse, MinterResponse, TokenInfoResponse,
@@ -427,6 +689,10 @@ mod tests {
                 }),
                 red_bank_address: String::from("red_bank"),
                 incentives_address: String::from("incentives"),
+                underlying_asset: Asset::Native {
+                    denom: String::from("uusd"),
+                },
+                default_allowance_expiry: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -477,6 +743,10 @@ mod tests {
                 init_hook: None,
                 red_bank_address: String::from("red_bank"),
                 incentives_address: String::from("incentives"),
+                underlying_asset: Asset::Native {
+                    denom: String::from("uusd"),
+                },
+                default_allowance_expiry: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -527,6 +797,10 @@ mod tests {
                 init_hook: None,
                 red_bank_address: String::from("red_bank"),
                 incentives_address: String::from("incentives"),
+                underlying_asset: Asset::Native {
+                    denom: String::from("uusd"),
+                },
+                default_allowance_expiry: None,
             };
             let info = mock_info("creator", &[]);
             let env = mock_env();
@@ -537,6 +811,37 @@ mod tests {
             );
         }
 
+        #[test]
+        fn underlying_asset() {
+            let mut deps = mock_dependencies(&[]);
+            let underlying_asset = Asset::Cw20 {
+                contract_addr: String::from("asset_token"),
+            };
+            let instantiate_msg = InstantiateMsg {
+                name: "Cash Token".to_string(),
+                symbol: "CASH".to_string(),
+                decimals: 9,
+                initial_balances: vec![],
+                mint: None,
+                marketing: None,
+                init_hook: None,
+                red_bank_address: String::from("red_bank"),
+                incentives_address: String::from("incentives"),
+                underlying_asset: underlying_asset.clone(),
+                default_allowance_expiry: None,
+            };
+            let info = mock_info("creator", &[]);
+            let env = mock_env();
+            instantiate(deps.as_mut(), env.clone(), info, instantiate_msg).unwrap();
+
+            let config = CONFIG.load(&deps.storage).unwrap();
+            assert_eq!(config.underlying_asset, underlying_asset);
+
+            let res = query(deps.as_ref(), env, QueryMsg::UnderlyingAsset {}).unwrap();
+            let queried_asset: Asset = from_binary(&res).unwrap();
+            assert_eq!(queried_asset, underlying_asset);
+        }
+
         mod marketing {
             use super::*;
 
@@ -558,6 +863,10 @@ mod tests {
                     init_hook: None,
                     red_bank_address: String::from("red_bank"),
                     incentives_address: String::from("incentives"),
+                    underlying_asset: Asset::Native {
+                        denom: String::from("uusd"),
+                    },
+                    default_allowance_expiry: None,
                 };
 
                 let info = mock_info("creator", &[]);
@@ -601,6 +910,10 @@ mod tests {
                     init_hook: None,
                     red_bank_address: String::from("red_bank"),
                     incentives_address: String::from("incentives"),
+                    underlying_asset: Asset::Native {
+                        denom: String::from("uusd"),
+                    },
+                    default_allowance_expiry: None,
                 };
 
                 let info = mock_info("creator", &[]);
@@ -702,6 +1015,85 @@ mod tests {
         assert_eq!(err, ContractError::Unauthorized {});
     }
 
+    #[test]
+    fn can_mint_batch_by_minter() {
+        let mut deps = mock_dependencies(&[]);
+
+        let genesis = String::from("genesis");
+        let amount = Uint128::new(1_000);
+        let minter = String::from("minter");
+        let limit = Uint128::new(10_000);
+        do_instantiate_with_minter(deps.as_mut(), &genesis, amount, &minter, Some(limit));
+
+        let msg = ExecuteMsg::MintBatch {
+            mints: vec![
+                (String::from("winner1"), Uint128::new(2_000)),
+                (genesis.clone(), Uint128::new(3_000)),
+            ],
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let env = mock_env();
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("incentives"),
+                    msg: to_binary(&mars_core::incentives::msg::ExecuteMsg::BalanceChange {
+                        user_address: Addr::unchecked("winner1"),
+                        user_balance_before: Uint128::zero(),
+                        total_supply_before: amount,
+                    },)
+                    .unwrap(),
+                    funds: vec![],
+                })),
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("incentives"),
+                    msg: to_binary(&mars_core::incentives::msg::ExecuteMsg::BalanceChange {
+                        user_address: Addr::unchecked(&genesis),
+                        user_balance_before: amount,
+                        total_supply_before: amount,
+                    },)
+                    .unwrap(),
+                    funds: vec![],
+                })),
+            ]
+        );
+        assert_eq!(get_balance(deps.as_ref(), "winner1"), Uint128::new(2_000));
+        assert_eq!(get_balance(deps.as_ref(), genesis), Uint128::new(4_000));
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(6_000)
+        );
+
+        // others cannot mint
+        let msg = ExecuteMsg::MintBatch {
+            mints: vec![(String::from("winner1"), Uint128::new(1))],
+        };
+        let info = mock_info("anyone else", &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // cap is enforced over the whole batch even if each mint fits on its own
+        let msg = ExecuteMsg::MintBatch {
+            mints: vec![
+                (String::from("winner1"), Uint128::new(3_000)),
+                (String::from("winner2"), Uint128::new(3_000)),
+            ],
+        };
+        let info = mock_info(minter.as_ref(), &[]);
+        let env = mock_env();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert_eq!(err, ContractError::CannotExceedCap {});
+        assert_eq!(get_balance(deps.as_ref(), "winner2"), Uint128::zero());
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap().total_supply,
+            Uint128::new(6_000)
+        );
+    }
+
     #[test]
     fn no_one_mints_if_minter_unset() {
         let mut deps = mock_dependencies(&[]);
@@ -743,6 +1135,10 @@ mod tests {
             init_hook: None,
             red_bank_address: String::from("red_bank"),
             incentives_address: String::from("incentives"),
+            underlying_asset: Asset::Native {
+                denom: String::from("uusd"),
+            },
+            default_allowance_expiry: None,
         };
         let info = mock_info("creator", &[]);
         let env = mock_env();
@@ -873,6 +1269,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn transfer_batch() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        let addr2 = String::from("addr0002");
+        let addr3 = String::from("addr0003");
+        let amount1 = Uint128::new(10_000);
+
+        do_instantiate(deps.as_mut(), &addr1, amount1);
+
+        // transfers are validated as single ones
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::TransferBatch {
+            transfers: vec![(addr2.clone(), Uint128::zero())],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::TransferBatch {
+            transfers: vec![(addr1.clone(), Uint128::new(1_000))],
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::generic_err(
+                "Sender and recipient cannot be the same"
+            ))
+        );
+
+        // valid batch is finalized on the red bank through a single message
+        let info = mock_info(addr1.as_ref(), &[]);
+        let msg = ExecuteMsg::TransferBatch {
+            transfers: vec![
+                (addr2.clone(), Uint128::new(1_000)),
+                (addr3.clone(), Uint128::new(2_000)),
+            ],
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let balance_change_msg = |user: &str, user_balance_before: u128| {
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("incentives"),
+                msg: to_binary(&mars_core::incentives::msg::ExecuteMsg::BalanceChange {
+                    user_address: Addr::unchecked(user),
+                    user_balance_before: Uint128::new(user_balance_before),
+                    total_supply_before: amount1,
+                })
+                .unwrap(),
+                funds: vec![],
+            }))
+        };
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: String::from("red_bank"),
+                    msg: to_binary(
+                        &red_bank::msg::ExecuteMsg::FinalizeLiquidityTokenTransferBatch {
+                            transfers: vec![
+                                red_bank::msg::LiquidityTokenTransfer {
+                                    sender_address: Addr::unchecked(&addr1),
+                                    recipient_address: Addr::unchecked(&addr2),
+                                    sender_previous_balance: Uint128::new(10_000),
+                                    recipient_previous_balance: Uint128::zero(),
+                                    amount: Uint128::new(1_000),
+                                },
+                                red_bank::msg::LiquidityTokenTransfer {
+                                    sender_address: Addr::unchecked(&addr1),
+                                    recipient_address: Addr::unchecked(&addr3),
+                                    sender_previous_balance: Uint128::new(9_000),
+                                    recipient_previous_balance: Uint128::zero(),
+                                    amount: Uint128::new(2_000),
+                                },
+                            ],
+                        }
+                    )
+                    .unwrap(),
+                    funds: vec![],
+                })),
+                balance_change_msg(&addr1, 10_000),
+                balance_change_msg(&addr2, 0),
+                balance_change_msg(&addr1, 9_000),
+                balance_change_msg(&addr3, 0),
+            ]
+        );
+
+        assert_eq!(get_balance(deps.as_ref(), addr1), Uint128::new(7_000));
+        assert_eq!(get_balance(deps.as_ref(), addr2), Uint128::new(1_000));
+        assert_eq!(get_balance(deps.as_ref(), addr3), Uint128::new(2_000));
+    }
+
     #[test]
     fn transfer_on_liquidation() {
         let mut deps = mock_dependencies(&coins(2, "token"));
@@ -1064,6 +1552,150 @@ mod tests {
         );
     }
 
+    #[test]
+    fn total_supply_at() {
+        let mut deps = mock_dependencies(&[]);
+        let user = String::from("user");
+        let initial_supply = Uint128::new(1_000);
+        let minter = String::from("red_bank");
+        let mint_height = mock_env().block.height + 10;
+        let mint_batch_height = mint_height + 10;
+        let burn_height = mint_batch_height + 10;
+
+        // instantiation records the initial supply
+        do_instantiate_with_minter(deps.as_mut(), &user, initial_supply, &minter, None);
+
+        let info = mock_info(&minter, &[]);
+        let mut env = mock_env();
+        env.block.height = mint_height;
+        let msg = ExecuteMsg::Mint {
+            recipient: user.clone(),
+            amount: Uint128::new(500),
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let info = mock_info(&minter, &[]);
+        let mut env = mock_env();
+        env.block.height = mint_batch_height;
+        let msg = ExecuteMsg::MintBatch {
+            mints: vec![
+                (user.clone(), Uint128::new(200)),
+                (String::from("other"), Uint128::new(300)),
+            ],
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        // transfers leave the total supply unchanged
+        let info = mock_info(&user, &[]);
+        let mut env = mock_env();
+        env.block.height = burn_height - 5;
+        let msg = ExecuteMsg::Transfer {
+            recipient: String::from("other"),
+            amount: Uint128::new(100),
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let info = mock_info(&minter, &[]);
+        let mut env = mock_env();
+        env.block.height = burn_height;
+        let msg = ExecuteMsg::Burn {
+            user: user.clone(),
+            amount: Uint128::new(700),
+        };
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let expected = vec![
+            (mock_env().block.height - 1, Uint128::zero(), false),
+            (mock_env().block.height, Uint128::new(1_000), true),
+            (mint_height - 1, Uint128::new(1_000), true),
+            (mint_height, Uint128::new(1_500), true),
+            (mint_batch_height, Uint128::new(2_000), true),
+            (burn_height - 1, Uint128::new(2_000), true),
+            (burn_height, Uint128::new(1_300), true),
+            (burn_height + 100, Uint128::new(1_300), true),
+        ];
+        for (block, total_supply, snapshot_found) in expected {
+            let res: TotalSupplyResponse = from_binary(
+                &query(deps.as_ref(), mock_env(), QueryMsg::TotalSupplyAt { block }).unwrap(),
+            )
+            .unwrap();
+            assert_eq!(
+                res,
+                TotalSupplyResponse {
+                    total_supply,
+                    snapshot_found,
+                },
+                "total supply at block {}",
+                block
+            );
+        }
+    }
+
+    #[test]
+    fn update_token_info() {
+        let mut deps = mock_dependencies(&[]);
+        let addr1 = String::from("addr0001");
+        do_instantiate(deps.as_mut(), &addr1, Uint128::new(1_000));
+
+        let msg = ExecuteMsg::UpdateTokenInfo {
+            name: String::from("Mars Renamed Token"),
+            symbol: String::from("maRENAMED"),
+        };
+
+        // only red bank can rename the token
+        let info = mock_info(addr1.as_ref(), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        // name and symbol are validated
+        let info = mock_info("red_bank", &[]);
+        let invalid_msg = ExecuteMsg::UpdateTokenInfo {
+            name: String::from("Mars Renamed Token"),
+            symbol: String::from("ma_RENAMED"),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, invalid_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::generic_err(
+                "Ticker symbol is not in expected format [a-zA-Z\\-]{3,12}"
+            ))
+        );
+
+        let info = mock_info("red_bank", &[]);
+        let invalid_msg = ExecuteMsg::UpdateTokenInfo {
+            name: String::from("ma"),
+            symbol: String::from("maRENAMED"),
+        };
+        let err = execute(deps.as_mut(), mock_env(), info, invalid_msg).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Std(StdError::generic_err(
+                "Name is not in the expected format (3-50 UTF-8 bytes)"
+            ))
+        );
+
+        // valid rename keeps decimals and supply
+        let token_info_before = query_token_info(deps.as_ref()).unwrap();
+        let info = mock_info("red_bank", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "update_token_info"),
+                attr("name", "Mars Renamed Token"),
+                attr("symbol", "maRENAMED"),
+            ]
+        );
+        assert_eq!(
+            query_token_info(deps.as_ref()).unwrap(),
+            TokenInfoResponse {
+                name: String::from("Mars Renamed Token"),
+                symbol: String::from("maRENAMED"),
+                ..token_info_before
+            }
+        );
+    }
+
     #[test]
     fn send() {
         let mut deps = mock_dependencies(&coins(2, "token"));
@@ -1173,4 +1805,178 @@ mod tests {
             amount1
         );
     }
+
+    #[test]
+    fn queries_match_cw20_standard() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let amount = Uint128::new(12_340_000);
+        do_instantiate_with_minter(
+            deps.as_mut(),
+            "addr0000",
+            amount,
+            "minter",
+            Some(Uint128::new(100_000_000)),
+        );
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: String::from("spender"),
+            amount: Uint128::new(777),
+            expires: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+        // queries built with the standard cw20 client messages are accepted and their responses
+        // deserialize to the standard cw20 response types
+        let query_cw20 = |query_msg: cw20::Cw20QueryMsg| -> Binary {
+            let msg: QueryMsg = from_binary(&to_binary(&query_msg).unwrap()).unwrap();
+            query(deps.as_ref(), env.clone(), msg).unwrap()
+        };
+
+        let res: cw20::BalanceResponse = from_binary(&query_cw20(cw20::Cw20QueryMsg::Balance {
+            address: String::from("addr0000"),
+        }))
+        .unwrap();
+        assert_eq!(res.balance, amount);
+
+        let res: TokenInfoResponse =
+            from_binary(&query_cw20(cw20::Cw20QueryMsg::TokenInfo {})).unwrap();
+        assert_eq!(
+            res,
+            TokenInfoResponse {
+                name: "Auto Gen".to_string(),
+                symbol: "AUTO".to_string(),
+                decimals: 3,
+                total_supply: amount,
+            }
+        );
+
+        let res: Option<MinterResponse> =
+            from_binary(&query_cw20(cw20::Cw20QueryMsg::Minter {})).unwrap();
+        assert_eq!(
+            res,
+            Some(MinterResponse {
+                minter: String::from("minter"),
+                cap: Some(Uint128::new(100_000_000)),
+            })
+        );
+
+        let res: cw20::AllowanceResponse =
+            from_binary(&query_cw20(cw20::Cw20QueryMsg::Allowance {
+                owner: String::from("addr0000"),
+                spender: String::from("spender"),
+            }))
+            .unwrap();
+        assert_eq!(res.allowance, Uint128::new(777));
+
+        let res: cw20::AllAllowancesResponse =
+            from_binary(&query_cw20(cw20::Cw20QueryMsg::AllAllowances {
+                owner: String::from("addr0000"),
+                start_after: None,
+                limit: None,
+            }))
+            .unwrap();
+        assert_eq!(res.allowances.len(), 1);
+        assert_eq!(res.allowances[0].spender, "spender");
+
+        let res: cw20::AllAccountsResponse =
+            from_binary(&query_cw20(cw20::Cw20QueryMsg::AllAccounts {
+                start_after: None,
+                limit: None,
+            }))
+            .unwrap();
+        assert_eq!(res.accounts, vec![String::from("addr0000")]);
+
+        let res: MarketingInfoResponse =
+            from_binary(&query_cw20(cw20::Cw20QueryMsg::MarketingInfo {})).unwrap();
+        assert_eq!(res, MarketingInfoResponse::default());
+    }
+
+    #[test]
+    fn balance_batch() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let amount = Uint128::new(12_340_000);
+        do_instantiate(deps.as_mut(), "addr0000", amount);
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: String::from("addr0001"),
+            amount: Uint128::new(1_000),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+        let msg = QueryMsg::BalanceBatch {
+            addresses: vec![
+                String::from("addr0001"),
+                String::from("nobody"),
+                String::from("addr0000"),
+            ],
+        };
+        let res: Vec<(String, Uint128)> =
+            from_binary(&query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(
+            res,
+            vec![
+                (String::from("addr0001"), Uint128::new(1_000)),
+                (String::from("nobody"), Uint128::zero()),
+                (String::from("addr0000"), amount - Uint128::new(1_000)),
+            ]
+        );
+
+        // number of addresses is capped
+        let msg = QueryMsg::BalanceBatch {
+            addresses: vec![String::from("addr0000"); MAX_BALANCE_BATCH_ADDRESSES + 1],
+        };
+        let error_res = query(deps.as_ref(), env, msg).unwrap_err();
+        assert_eq!(
+            error_res,
+            StdError::generic_err("Cannot query more than 30 balances at once")
+        );
+    }
+
+    #[test]
+    fn migrate_backfills_underlying_asset() {
+        let mut deps = mars_core::testing::mock_dependencies(&[]);
+        let market = |denom: &str, ma_token_address: &str| MarketInfo {
+            denom: String::from(denom),
+            asset_label: String::from(denom),
+            asset_reference: denom.as_bytes().to_vec(),
+            asset_type: AssetType::Native,
+            ma_token_address: Addr::unchecked(ma_token_address),
+        };
+        deps.querier.set_red_bank_markets_list(
+            Addr::unchecked("red_bank"),
+            &[
+                market("uluna", "other_ma_token"),
+                market("uusd", MOCK_CONTRACT_ADDR),
+            ],
+        );
+
+        // config as stored before the underlying asset was added
+        deps.storage.set(
+            b"config",
+            br#"{"red_bank_address":"red_bank","incentives_address":"incentives"}"#,
+        );
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![attr("action", "migrate"), attr("underlying_asset", "uusd")]
+        );
+
+        let expected_config = Config {
+            red_bank_address: Addr::unchecked("red_bank"),
+            incentives_address: Addr::unchecked("incentives"),
+            underlying_asset: Asset::Native {
+                denom: String::from("uusd"),
+            },
+            default_allowance_expiry: None,
+        };
+        assert_eq!(CONFIG.load(&deps.storage).unwrap(), expected_config);
+
+        // migrating again leaves the config as it is
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes, vec![attr("action", "migrate")]);
+        assert_eq!(CONFIG.load(&deps.storage).unwrap(), expected_config);
+    }
 }
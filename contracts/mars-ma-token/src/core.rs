@@ -3,6 +3,8 @@ use cosmwasm_std::{to_binary, Addr, CosmosMsg, StdError, StdResult, Storage, Uin
 use cw20_base::state::{BALANCES, TOKEN_INFO};
 use cw20_base::ContractError;
 
+use mars_core::red_bank::msg::LiquidityTokenTransfer;
+
 use crate::Config;
 
 /// Deduct amount from sender balance and add it to recipient balance
@@ -15,17 +17,8 @@ pub fn transfer(
     amount: Uint128,
     finalize_on_red_bank: bool,
 ) -> Result<Vec<CosmosMsg>, ContractError> {
-    if sender_address == recipient_address {
-        return Err(StdError::generic_err("Sender and recipient cannot be the same").into());
-    }
-
-    if amount.is_zero() {
-        return Err(ContractError::InvalidZeroAmount {});
-    }
-
-    let sender_previous_balance = decrease_balance(storage, &sender_address, amount)?;
-
-    let recipient_previous_balance = increase_balance(storage, &recipient_address, amount)?;
+    let (sender_previous_balance, recipient_previous_balance) =
+        move_balance(storage, &sender_address, &recipient_address, amount)?;
 
     let total_supply = TOKEN_INFO.load(storage)?.total_supply;
 
@@ -62,6 +55,74 @@ pub fn transfer(
     Ok(messages)
 }
 
+/// Transfer amount from sender to each recipient, in order. The transfers are finalized on the
+/// red bank through a single message, which comes before the incentives messages
+pub fn transfer_batch(
+    storage: &mut dyn Storage,
+    config: &Config,
+    sender_address: Addr,
+    transfers: Vec<(Addr, Uint128)>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let total_supply = TOKEN_INFO.load(storage)?.total_supply;
+
+    let mut finalize_transfers = vec![];
+    let mut incentives_messages = vec![];
+    for (recipient_address, amount) in transfers {
+        let (sender_previous_balance, recipient_previous_balance) =
+            move_balance(storage, &sender_address, &recipient_address, amount)?;
+
+        incentives_messages.push(balance_change_msg(
+            config.incentives_address.clone(),
+            sender_address.clone(),
+            sender_previous_balance,
+            total_supply,
+        )?);
+        incentives_messages.push(balance_change_msg(
+            config.incentives_address.clone(),
+            recipient_address.clone(),
+            recipient_previous_balance,
+            total_supply,
+        )?);
+        finalize_transfers.push(LiquidityTokenTransfer {
+            sender_address: sender_address.clone(),
+            recipient_address,
+            sender_previous_balance,
+            recipient_previous_balance,
+            amount,
+        });
+    }
+
+    let mut messages = vec![finalize_transfer_batch_msg(
+        config.red_bank_address.clone(),
+        finalize_transfers,
+    )?];
+    messages.extend(incentives_messages);
+
+    Ok(messages)
+}
+
+/// Move amount from sender to recipient balance, returns both previous balances
+fn move_balance(
+    storage: &mut dyn Storage,
+    sender_address: &Addr,
+    recipient_address: &Addr,
+    amount: Uint128,
+) -> Result<(Uint128, Uint128), ContractError> {
+    if sender_address == recipient_address {
+        return Err(StdError::generic_err("Sender and recipient cannot be the same").into());
+    }
+
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let sender_previous_balance = decrease_balance(storage, sender_address, amount)?;
+
+    let recipient_previous_balance = increase_balance(storage, recipient_address, amount)?;
+
+    Ok((sender_previous_balance, recipient_previous_balance))
+}
+
 /// Lower user balance and commit to store, returns previous balance
 pub fn decrease_balance(
     storage: &mut dyn Storage,
@@ -82,12 +143,15 @@ pub fn increase_balance(
     amount: Uint128,
 ) -> Result<Uint128, StdError> {
     let previous_balance = BALANCES.load(storage, address).unwrap_or_default();
-    let new_balance = previous_balance + amount;
+    let new_balance = previous_balance.checked_add(amount)?;
     BALANCES.save(storage, address, &new_balance)?;
 
     Ok(previous_balance)
 }
 
+/// Message to finalize the transfer on the red bank. It must not be sent as a submessage that
+/// swallows errors: the red bank rejects transfers that leave the sender's position unhealthy,
+/// and that check is what makes the transfer safe, so its failure has to revert the transfer.
 pub fn finalize_transfer_msg(
     red_bank_address: Addr,
     sender_address: Addr,
@@ -111,6 +175,23 @@ pub fn finalize_transfer_msg(
     }))
 }
 
+/// Message to finalize several transfers on the red bank at once. Same as
+/// `finalize_transfer_msg`, it must not be sent as a submessage that swallows errors
+pub fn finalize_transfer_batch_msg(
+    red_bank_address: Addr,
+    transfers: Vec<LiquidityTokenTransfer>,
+) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: red_bank_address.into(),
+        msg: to_binary(
+            &mars_core::red_bank::msg::ExecuteMsg::FinalizeLiquidityTokenTransferBatch {
+                transfers,
+            },
+        )?,
+        funds: vec![],
+    }))
+}
+
 pub fn balance_change_msg(
     incentives_address: Addr,
     user_address: Addr,
@@ -1,6 +1,34 @@
 /// state: contains state specific to ma_token (not included in cw20_base)
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Env, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::{Bound, Item, Map, U64Key};
 
-use crate::Config;
+use crate::{AllowanceSpendLimit, Config};
 
 pub const CONFIG: Item<Config> = Item::new("config");
+pub const ALLOWANCE_SPEND_LIMITS: Map<(&Addr, &Addr), AllowanceSpendLimit> =
+    Map::new("allowance_spend_limits");
+/// New owner an (owner, spender) allowance is being transferred to, until it accepts it
+pub const PENDING_ALLOWANCE_TRANSFERS: Map<(&Addr, &Addr), Addr> =
+    Map::new("pending_allowance_transfers");
+pub const TOTAL_SUPPLY_SNAPSHOTS: Map<U64Key, Uint128> = Map::new("total_supply_snapshots");
+
+pub fn capture_total_supply_snapshot(
+    storage: &mut dyn Storage,
+    env: &Env,
+    total_supply: Uint128,
+) -> StdResult<()> {
+    TOTAL_SUPPLY_SNAPSHOTS.save(storage, U64Key::new(env.block.height), &total_supply)
+}
+
+/// Total supply at the given block, or None if no total supply was recorded at or before it
+pub fn find_total_supply_snapshot_value_at(
+    storage: &dyn Storage,
+    block: u64,
+) -> StdResult<Option<Uint128>> {
+    let end = Bound::inclusive(U64Key::new(block));
+    TOTAL_SUPPLY_SNAPSHOTS
+        .range(storage, None, Some(end), Order::Descending)
+        .next()
+        .map(|item| item.map(|(_, total_supply)| total_supply))
+        .transpose()
+}
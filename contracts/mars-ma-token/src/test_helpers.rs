@@ -4,6 +4,7 @@ use cosmwasm_std::testing::{mock_env, mock_info};
 
 use cw20::{Cw20Coin, MinterResponse, TokenInfoResponse};
 use cw20_base::contract::{query_balance, query_minter, query_token_info};
+use mars_core::asset::Asset;
 
 use crate::contract::instantiate;
 use crate::msg::InstantiateMsg;
@@ -56,6 +57,10 @@ fn _do_instantiate(
         init_hook: None,
         red_bank_address: String::from("red_bank"),
         incentives_address: String::from("incentives"),
+        underlying_asset: Asset::Native {
+            denom: String::from("uusd"),
+        },
+        default_allowance_expiry: None,
     };
     let info = mock_info("creator", &[]);
     let env = mock_env();
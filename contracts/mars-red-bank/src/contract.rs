@@ -29,7 +29,8 @@ use crate::interest_rates::{
     get_underlying_debt_amount, get_underlying_liquidity_amount, update_interest_rates,
 };
 use crate::msg::{
-    CreateOrUpdateConfig, ExecuteMsg, InitOrUpdateAssetParams, InstantiateMsg, QueryMsg, ReceiveMsg,
+    CreateOrUpdateConfig, ExecuteMsg, InitOrUpdateAssetParams, InstantiateMsg,
+    LiquidityTokenTransfer, QueryMsg, ReceiveMsg,
 };
 use crate::state::{
     CONFIG, DEBTS, GLOBAL_STATE, MARKETS, MARKET_REFERENCES_BY_INDEX,
@@ -221,6 +222,10 @@ pub fn execute(
             recipient_previous_balance,
             amount,
         ),
+
+        ExecuteMsg::FinalizeLiquidityTokenTransferBatch { transfers } => {
+            execute_finalize_liquidity_token_transfer_batch(deps, env, info, transfers)
+        }
     }
 }
 
@@ -374,10 +379,10 @@ pub fn execute_init_asset(
             let symbol = if let Some(asset_symbol) = asset_symbol_option {
                 asset_symbol
             } else {
-                match asset {
-                    Asset::Native { denom } => denom,
+                match &asset {
+                    Asset::Native { denom } => denom.clone(),
                     Asset::Cw20 { contract_addr } => {
-                        let contract_addr = deps.api.addr_validate(&contract_addr)?;
+                        let contract_addr = deps.api.addr_validate(contract_addr)?;
                         cw20_get_symbol(&deps.querier, contract_addr)?
                     }
                 }
@@ -429,6 +434,8 @@ pub fn execute_init_asset(
                         }),
                         red_bank_address: env.contract.address.to_string(),
                         incentives_address: incentives_address.into(),
+                        underlying_asset: asset,
+                        default_allowance_expiry: None,
                     })?,
                     funds: vec![],
                     label: token_symbol,
@@ -1865,41 +1872,100 @@ pub fn execute_finalize_liquidity_token_transfer(
     to_previous_balance: Uint128,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
+    let transfer = LiquidityTokenTransfer {
+        sender_address: from_address,
+        recipient_address: to_address,
+        sender_previous_balance: from_previous_balance,
+        recipient_previous_balance: to_previous_balance,
+        amount,
+    };
+    let events = finalize_liquidity_token_transfers(deps, env, &info.sender, vec![transfer])?;
+
+    let res = Response::new()
+        .add_attribute("action", "finalize_liquidity_token_transfer")
+        .add_events(events);
+    Ok(res)
+}
+
+pub fn execute_finalize_liquidity_token_transfer_batch(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    transfers: Vec<LiquidityTokenTransfer>,
+) -> Result<Response, ContractError> {
+    let transfer_count = transfers.len();
+    let events = finalize_liquidity_token_transfers(deps, env, &info.sender, transfers)?;
+
+    let res = Response::new()
+        .add_attribute("action", "finalize_liquidity_token_transfer_batch")
+        .add_attribute("transfer_count", transfer_count.to_string())
+        .add_events(events);
+    Ok(res)
+}
+
+/// Validate liquidity token transfers made on the given ma token and update the collateral
+/// status of the users involved. Returns the collateral position change events
+fn finalize_liquidity_token_transfers(
+    deps: DepsMut,
+    env: Env,
+    ma_token_address: &Addr,
+    transfers: Vec<LiquidityTokenTransfer>,
+) -> Result<Vec<Event>, ContractError> {
     // Get liquidity token market
-    let market_reference = MARKET_REFERENCES_BY_MA_TOKEN.load(deps.storage, &info.sender)?;
+    let market_reference = MARKET_REFERENCES_BY_MA_TOKEN.load(deps.storage, ma_token_address)?;
     let market = MARKETS.load(deps.storage, market_reference.as_slice())?;
 
-    // Check user health factor is above 1
+    // Check user health factor is above 1. Balances are already updated on the token when the
+    // transfers are finalized, so each sender is only checked once
     let global_state = GLOBAL_STATE.load(deps.storage)?;
-    let mut from_user = USERS.load(deps.storage, &from_address)?;
     let config = CONFIG.load(deps.storage)?;
     let oracle_address = address_provider::helpers::query_address(
         &deps.querier,
         config.address_provider_address,
         MarsContract::Oracle,
     )?;
-    let user_position = get_user_position(
-        deps.as_ref(),
-        env.block.time.seconds(),
-        &from_address,
-        oracle_address,
-        &from_user,
-        global_state.market_count,
-    )?;
-    if let UserHealthStatus::Borrowing(health_factor) = user_position.health_status {
-        if health_factor < Decimal::one() {
-            return Err(ContractError::CannotTransferTokenWhenInvalidHealthFactor {});
+    let mut checked_senders: Vec<&Addr> = vec![];
+    for transfer in &transfers {
+        if checked_senders.contains(&&transfer.sender_address) {
+            continue;
+        }
+        let from_user = USERS.load(deps.storage, &transfer.sender_address)?;
+        let user_position = get_user_position(
+            deps.as_ref(),
+            env.block.time.seconds(),
+            &transfer.sender_address,
+            oracle_address.clone(),
+            &from_user,
+            global_state.market_count,
+        )?;
+        if let UserHealthStatus::Borrowing(health_factor) = user_position.health_status {
+            if health_factor < Decimal::one() {
+                return Err(ContractError::CannotTransferTokenWhenInvalidHealthFactor {});
+            }
         }
+        checked_senders.push(&transfer.sender_address);
     }
 
     let asset_label = String::from_utf8(market_reference).expect("Found invalid UTF-8");
     let mut events = vec![];
 
-    // Update users's positions
-    if from_address != to_address {
-        if from_previous_balance.checked_sub(amount)?.is_zero() {
+    // Update users's positions, in the order the transfers were made
+    for transfer in &transfers {
+        let LiquidityTokenTransfer {
+            sender_address: from_address,
+            recipient_address: to_address,
+            sender_previous_balance: from_previous_balance,
+            recipient_previous_balance: to_previous_balance,
+            amount,
+        } = transfer;
+        if from_address == to_address {
+            continue;
+        }
+
+        if from_previous_balance.checked_sub(*amount)?.is_zero() {
+            let mut from_user = USERS.load(deps.storage, from_address)?;
             unset_bit(&mut from_user.collateral_assets, market.index)?;
-            USERS.save(deps.storage, &from_address, &from_user)?;
+            USERS.save(deps.storage, from_address, &from_user)?;
             events.push(build_collateral_position_changed_event(
                 asset_label.as_str(),
                 false,
@@ -1909,10 +1975,10 @@ pub fn execute_finalize_liquidity_token_transfer(
 
         if to_previous_balance.is_zero() && !amount.is_zero() {
             let mut to_user = USERS
-                .may_load(deps.storage, &to_address)?
+                .may_load(deps.storage, to_address)?
                 .unwrap_or_default();
             set_bit(&mut to_user.collateral_assets, market.index)?;
-            USERS.save(deps.storage, &to_address, &to_user)?;
+            USERS.save(deps.storage, to_address, &to_user)?;
             events.push(build_collateral_position_changed_event(
                 asset_label.as_str(),
                 true,
@@ -1921,10 +1987,7 @@ pub fn execute_finalize_liquidity_token_transfer(
         }
     }
 
-    let res = Response::new()
-        .add_attribute("action", "finalize_liquidity_token_transfer")
-        .add_events(events);
-    Ok(res)
+    Ok(events)
 }
 
 // QUERIES
@@ -2845,6 +2908,10 @@ mod tests {
                         }),
                         red_bank_address: MOCK_CONTRACT_ADDR.to_string(),
                         incentives_address: "incentives".to_string(),
+                        underlying_asset: Asset::Native {
+                            denom: "someasset".to_string(),
+                        },
+                        default_allowance_expiry: None,
                     })
                     .unwrap(),
                     funds: vec![],
@@ -3050,6 +3117,10 @@ mod tests {
                     }),
                     red_bank_address: MOCK_CONTRACT_ADDR.to_string(),
                     incentives_address: "incentives".to_string(),
+                    underlying_asset: Asset::Native {
+                        denom: "someasset".to_string(),
+                    },
+                    default_allowance_expiry: None,
                 })
                 .unwrap(),
                 funds: vec![],
@@ -8816,6 +8887,142 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_finalize_liquidity_token_transfer_batch() {
+        let mut deps = th_setup(&[]);
+        let env = mock_env(MockEnvParams::default());
+        let info_matoken = mock_info("masomecoin");
+
+        let mock_market = Market {
+            ma_token_address: Addr::unchecked("masomecoin"),
+            liquidity_index: Decimal::one(),
+            liquidation_threshold: Decimal::from_ratio(5u128, 10u128),
+            ..Default::default()
+        };
+        let market = th_init_market(deps.as_mut(), b"somecoin", &mock_market);
+        let debt_mock_market = Market {
+            borrow_index: Decimal::one(),
+            ..Default::default()
+        };
+        let debt_market = th_init_market(deps.as_mut(), b"debtcoin", &debt_mock_market);
+
+        deps.querier
+            .set_oracle_price(b"somecoin".to_vec(), Decimal::from_ratio(1u128, 2u128));
+        deps.querier
+            .set_oracle_price(b"debtcoin".to_vec(), Decimal::from_ratio(2u128, 1u128));
+
+        let sender_address = Addr::unchecked("fromaddr");
+        let first_recipient_address = Addr::unchecked("toaddr1");
+        let second_recipient_address = Addr::unchecked("toaddr2");
+
+        deps.querier.set_cw20_balances(
+            Addr::unchecked("masomecoin"),
+            &[(
+                sender_address.clone(),
+                Uint128::new(500_000) * SCALING_FACTOR,
+            )],
+        );
+
+        {
+            let mut sender_user = User::default();
+            set_bit(&mut sender_user.collateral_assets, market.index).unwrap();
+            USERS
+                .save(deps.as_mut().storage, &sender_address, &sender_user)
+                .unwrap();
+        }
+
+        // sender sends its whole balance to two recipients
+        let msg = ExecuteMsg::FinalizeLiquidityTokenTransferBatch {
+            transfers: vec![
+                LiquidityTokenTransfer {
+                    sender_address: sender_address.clone(),
+                    recipient_address: first_recipient_address.clone(),
+                    sender_previous_balance: Uint128::new(1_000_000),
+                    recipient_previous_balance: Uint128::zero(),
+                    amount: Uint128::new(400_000),
+                },
+                LiquidityTokenTransfer {
+                    sender_address: sender_address.clone(),
+                    recipient_address: second_recipient_address.clone(),
+                    sender_previous_balance: Uint128::new(600_000),
+                    recipient_previous_balance: Uint128::zero(),
+                    amount: Uint128::new(600_000),
+                },
+            ],
+        };
+
+        // sender health factor is checked as with single transfers
+        {
+            let debt = Debt {
+                amount_scaled: Uint128::new(500_000) * SCALING_FACTOR,
+                uncollateralized: false,
+            };
+            DEBTS
+                .save(deps.as_mut().storage, (b"debtcoin", &sender_address), &debt)
+                .unwrap();
+            let mut sender_user = USERS.load(&deps.storage, &sender_address).unwrap();
+            set_bit(&mut sender_user.borrowed_assets, debt_market.index).unwrap();
+            USERS
+                .save(deps.as_mut().storage, &sender_address, &sender_user)
+                .unwrap();
+
+            let error_res = execute(
+                deps.as_mut(),
+                env.clone(),
+                info_matoken.clone(),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                error_res,
+                ContractError::CannotTransferTokenWhenInvalidHealthFactor {}
+            );
+
+            DEBTS.remove(deps.as_mut().storage, (b"debtcoin", &sender_address));
+            let mut sender_user = USERS.load(&deps.storage, &sender_address).unwrap();
+            unset_bit(&mut sender_user.borrowed_assets, debt_market.index).unwrap();
+            USERS
+                .save(deps.as_mut().storage, &sender_address, &sender_user)
+                .unwrap();
+        }
+
+        let res = execute(deps.as_mut(), env, info_matoken, msg).unwrap();
+        assert_eq!(
+            res.attributes,
+            vec![
+                attr("action", "finalize_liquidity_token_transfer_batch"),
+                attr("transfer_count", "2"),
+            ]
+        );
+        assert_eq!(
+            res.events,
+            vec![
+                build_collateral_position_changed_event(
+                    "somecoin",
+                    true,
+                    first_recipient_address.to_string()
+                ),
+                build_collateral_position_changed_event(
+                    "somecoin",
+                    false,
+                    sender_address.to_string()
+                ),
+                build_collateral_position_changed_event(
+                    "somecoin",
+                    true,
+                    second_recipient_address.to_string()
+                ),
+            ]
+        );
+
+        let sender_user = USERS.load(&deps.storage, &sender_address).unwrap();
+        assert!(!get_bit(sender_user.collateral_assets, market.index).unwrap());
+        for recipient_address in [first_recipient_address, second_recipient_address] {
+            let recipient_user = USERS.load(&deps.storage, &recipient_address).unwrap();
+            assert!(get_bit(recipient_user.collateral_assets, market.index).unwrap());
+        }
+    }
+
     #[test]
     fn test_uncollateralized_loan_limits() {
         let available_liquidity = Uint128::from(2000000000u128);
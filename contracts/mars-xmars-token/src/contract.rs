@@ -22,8 +22,8 @@ use crate::allowances::{execute_burn_from, execute_send_from, execute_transfer_f
 use crate::core;
 use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
 use crate::snapshots::{
-    capture_balance_snapshot, capture_total_supply_snapshot, get_balance_snapshot_value_at,
-    get_total_supply_snapshot_value_at,
+    capture_balance_snapshot, capture_total_supply_snapshot, find_total_supply_snapshot_value_at,
+    get_balance_snapshot_value_at,
 };
 use crate::TotalSupplyResponse;
 
@@ -260,8 +260,11 @@ pub fn query_balance_at(deps: Deps, address: String, block: u64) -> StdResult<Ba
 }
 
 pub fn query_total_supply_at(deps: Deps, block: u64) -> StdResult<TotalSupplyResponse> {
-    let total_supply = get_total_supply_snapshot_value_at(deps.storage, block)?;
-    Ok(TotalSupplyResponse { total_supply })
+    let snapshot = find_total_supply_snapshot_value_at(deps.storage, block)?;
+    Ok(TotalSupplyResponse {
+        total_supply: snapshot.unwrap_or_else(Uint128::zero),
+        snapshot_found: snapshot.is_some(),
+    })
 }
 
 #[cfg(test)]
@@ -880,6 +883,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn total_supply_at_reports_missing_snapshot() {
+        let mut deps = mock_dependencies(&[]);
+        let amount = Uint128::new(11223344);
+        do_instantiate(deps.as_mut(), "addr0000", amount);
+
+        // instantiation records the first total supply snapshot
+        let height = mock_env().block.height;
+        assert_eq!(
+            query_total_supply_at(deps.as_ref(), height - 1).unwrap(),
+            TotalSupplyResponse {
+                total_supply: Uint128::zero(),
+                snapshot_found: false,
+            }
+        );
+        assert_eq!(
+            query_total_supply_at(deps.as_ref(), height).unwrap(),
+            TotalSupplyResponse {
+                total_supply: amount,
+                snapshot_found: true,
+            }
+        );
+    }
+
     #[test]
     fn snapshots_are_taken_and_retrieved_correctly() {
         let mut deps = mock_dependencies(&[]);
@@ -7,12 +7,12 @@ pub const BALANCE_SNAPSHOTS: Map<(&Addr, U64Key), Uint128> = Map::new("balance_s
 
 // CORE
 
-fn get_snapshot_value_at(
+fn find_snapshot_value_at(
     storage: &dyn Storage,
     prefix: Prefix<Uint128>,
     block: u64,
-) -> StdResult<Uint128> {
-    // Look for the last value recorded before the current block (if none then value is zero)
+) -> StdResult<Option<Uint128>> {
+    // Look for the last value recorded before the current block
     let end = Bound::inclusive(U64Key::new(block));
     let last_value_up_to_block = prefix
         .range(storage, None, Some(end), Order::Descending)
@@ -20,10 +20,19 @@ fn get_snapshot_value_at(
 
     if let Some(value) = last_value_up_to_block {
         let (_, v) = value?;
-        return Ok(v);
+        return Ok(Some(v));
     }
 
-    Ok(Uint128::zero())
+    Ok(None)
+}
+
+fn get_snapshot_value_at(
+    storage: &dyn Storage,
+    prefix: Prefix<Uint128>,
+    block: u64,
+) -> StdResult<Uint128> {
+    // If no value was recorded before the block then value is zero
+    Ok(find_snapshot_value_at(storage, prefix, block)?.unwrap_or_else(Uint128::zero))
 }
 
 // BALANCE
@@ -58,3 +67,11 @@ pub fn capture_total_supply_snapshot(
 pub fn get_total_supply_snapshot_value_at(storage: &dyn Storage, block: u64) -> StdResult<Uint128> {
     get_snapshot_value_at(storage, TOTAL_SUPPLY_SNAPSHOTS.prefix(()), block)
 }
+
+/// Total supply at the given block, or None if no total supply was recorded at or before it
+pub fn find_total_supply_snapshot_value_at(
+    storage: &dyn Storage,
+    block: u64,
+) -> StdResult<Option<Uint128>> {
+    find_snapshot_value_at(storage, TOTAL_SUPPLY_SNAPSHOTS.prefix(()), block)
+}
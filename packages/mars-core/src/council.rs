@@ -1,9 +1,9 @@
-use cosmwasm_std::{Addr, CosmosMsg, Uint128};
+use cosmwasm_std::{to_vec, Addr, CosmosMsg, StdResult, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::error::MarsError;
-use crate::helpers::decimal_param_le_one;
+use crate::helpers::{decimal_param_le_one, zero_address};
 use crate::math::decimal::Decimal;
 
 use self::error::ContractError;
@@ -11,8 +11,21 @@ use self::error::ContractError;
 pub const MINIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE: u64 = 50;
 pub const MAXIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE: u64 = 100;
 
+// Proposal validation attributes
+pub const MIN_TITLE_LENGTH: u64 = 4;
+pub const MIN_DESC_LENGTH: u64 = 4;
+pub const MIN_LINK_LENGTH: u64 = 12;
+pub const DEFAULT_TITLE_MAX_LENGTH: u64 = 64;
+pub const DEFAULT_DESC_MAX_LENGTH: u64 = 1024;
+pub const DEFAULT_LINK_MAX_LENGTH: u64 = 128;
+pub const DEFAULT_AVG_BLOCK_TIME_SECONDS: u64 = 6;
+pub const MAX_MEMO_LENGTH: u64 = 256;
+
 /// Council global configuration
+/// Fields missing from configs stored before they were added take their recommended default,
+/// which is also the instantiate default
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(default = "Config::recommended_defaults")]
 pub struct Config {
     /// Address provider returns addresses for all protocol contracts
     pub address_provider_address: Addr,
@@ -28,13 +41,236 @@ pub struct Config {
     pub proposal_required_deposit: Uint128,
     /// % of total voting power required to participate in the proposal in order to consider it successfull
     pub proposal_required_quorum: Decimal,
-    /// % of for votes required in order to consider the proposal successful
+    /// % of for votes required in order to consider the proposal successful. A tie (as many
+    /// for as against votes) always fails, regardless of the threshold
     pub proposal_required_threshold: Decimal,
+    /// Blocks a submitter needs to wait after submitting a proposal before being able to submit
+    /// a new one. Zero disables the cooldown
+    pub submitter_cooldown: u64,
+    /// Address allowed to pause proposal submissions and voting without going through a
+    /// proposal. It cannot update any other parameter
+    pub emergency_owner: Option<Addr>,
+    /// Blocks after submission during which a proposal is visible but cannot be voted on.
+    /// Zero opens voting on submission
+    pub discussion_period: u64,
+    /// Proposal submissions are rejected while paused
+    pub submissions_paused: bool,
+    /// Votes are rejected while paused
+    pub voting_paused: bool,
+    /// Maximum length of a proposal title
+    pub title_max: u64,
+    /// Maximum length of a proposal description
+    pub description_max: u64,
+    /// Maximum length of a proposal link
+    pub link_max: u64,
+    /// Where the deposits of rejected proposals are sent
+    pub slashed_deposit_destination: SlashedDepositDestination,
+    /// Contracts that passed proposals can call without waiting for the effective delay, when all
+    /// of the proposal messages target them
+    pub fast_track_targets: Vec<Addr>,
+    /// Blocks after the end of the voting period after which the submitter can end the proposal
+    /// through ReclaimStaleDeposit
+    pub reclaim_grace_period: u64,
+    /// Blocks before the proposal submission at which voting power is measured. Must be
+    /// greater than zero so voting power cannot be acquired in the submission block
+    pub snapshot_offset: u64,
+    /// MARS paid from the treasury to whoever ends a proposal (unless it is the submitter).
+    /// Zero disables the reward
+    pub end_proposal_reward: Uint128,
+    /// Supply the proposal quorum is measured against
+    pub quorum_basis: QuorumBasis,
+    /// Addresses whose xMARS balance at the snapshot is not counted as circulating supply when
+    /// quorum basis is CirculatingSupply (e.g. the treasury)
+    pub quorum_excluded_addresses: Vec<Addr>,
+    /// Maximum MARS that can be minted through MintTreasury by a single proposal
+    pub mint_per_proposal_cap: Uint128,
+    /// Maximum MARS that can be minted through MintTreasury in total
+    pub mint_total_cap: Uint128,
+    /// Blocks a submitter has to finalize a reserved proposal before its deposit can be slashed.
+    /// Zero disables reservations
+    pub reservation_period: u64,
+    /// Allow proposals to change the MARS token admin, code or minter
+    pub allow_privileged_token_calls: bool,
+    /// Extra voting power multiplier for votes cast when voting opens, decaying linearly to
+    /// zero at the end of the voting period. Zero disables the bonus
+    pub early_vote_bonus: Decimal,
+    /// Token used instead of xMARS to measure voting power. It needs to implement the xMARS
+    /// BalanceAt and TotalSupplyAt queries
+    pub voting_token_override: Option<Addr>,
+    /// Average block time used to estimate the time remaining until a proposal ends
+    pub avg_block_time_seconds: u64,
+    /// For votes a proposal needs to pass, on top of the quorum and threshold. Zero disables
+    /// the floor
+    pub min_for_votes_absolute: Uint128,
+    /// Share of the MARS total supply required as proposal deposit, when greater than
+    /// proposal_required_deposit. Zero requires the flat deposit only
+    pub proposal_required_deposit_ratio: Decimal,
+    /// Voting power a voter needs at the snapshot block to cast a vote. Zero only rejects
+    /// voters without voting power
+    pub min_vote_power: Uint128,
+    /// Blocks during which a submitter cannot submit a proposal with the same title as one of its
+    /// active proposals. Zero disables the check
+    pub dedupe_window: u64,
+    /// Addresses allowed to execute passed proposals. Empty allows anyone
+    pub executor_allowlist: Vec<Addr>,
+    /// Interchain account controller proposal messages with an Ica route are sent through. None
+    /// rejects Ica routes
+    pub ica_controller_address: Option<Addr>,
+    /// Number of past blocks in which submitted proposals count towards the deposit surge. Zero
+    /// disables the surge
+    pub surge_window: u64,
+    /// Number of proposals submitted within the surge window above which the surge multiplier
+    /// applies to the required deposit
+    pub surge_threshold: u64,
+    /// Multiplier (>= 1) applied to the required deposit while proposal submissions surge
+    pub surge_multiplier: Decimal,
+    /// Categories proposals can be submitted with. Empty only allows uncategorized proposals
+    pub allowed_categories: Vec<String>,
+    /// cw20 tokens other than MARS accepted as proposal deposit, with the minimum deposit of each
+    pub accepted_deposit_tokens: Vec<(Addr, Uint128)>,
+    /// Whether the deposit of signaling proposals (without messages) is refunded even if they
+    /// are rejected, as they carry no execution risk
+    pub refund_signaling_always: bool,
+    /// Blocks after the end of the voting period after which queries report Active proposals
+    /// nobody ended as Expired. Their stored status is unchanged until they are ended. Zero
+    /// disables it
+    pub auto_expire_grace: u64,
+    /// Whether submissions are rejected when a message executes an address without a contract
+    /// (probed with a smart query), which catches mistyped addresses
+    pub require_call_simulation: bool,
 }
 
 impl Config {
+    /// Canonical parameters council deployments are compared against. The address provider is
+    /// deployment specific, so it is left empty
+    pub fn recommended_defaults() -> Config {
+        Config {
+            address_provider_address: zero_address(),
+            proposal_voting_period: 57_600,
+            proposal_effective_delay: 11_520,
+            proposal_expiration_period: 115_200,
+            proposal_required_deposit: Uint128::new(100_000_000),
+            proposal_required_quorum: Decimal::percent(10),
+            proposal_required_threshold: Decimal::percent(50),
+            submitter_cooldown: 0,
+            emergency_owner: None,
+            discussion_period: 0,
+            submissions_paused: false,
+            voting_paused: false,
+            title_max: DEFAULT_TITLE_MAX_LENGTH,
+            description_max: DEFAULT_DESC_MAX_LENGTH,
+            link_max: DEFAULT_LINK_MAX_LENGTH,
+            slashed_deposit_destination: SlashedDepositDestination::Staking,
+            fast_track_targets: vec![],
+            reclaim_grace_period: 0,
+            snapshot_offset: 1,
+            end_proposal_reward: Uint128::zero(),
+            quorum_basis: QuorumBasis::TotalSupply,
+            quorum_excluded_addresses: vec![],
+            mint_per_proposal_cap: Uint128::zero(),
+            mint_total_cap: Uint128::zero(),
+            reservation_period: 0,
+            allow_privileged_token_calls: false,
+            early_vote_bonus: Decimal::zero(),
+            voting_token_override: None,
+            avg_block_time_seconds: DEFAULT_AVG_BLOCK_TIME_SECONDS,
+            min_for_votes_absolute: Uint128::zero(),
+            proposal_required_deposit_ratio: Decimal::zero(),
+            min_vote_power: Uint128::zero(),
+            dedupe_window: 0,
+            executor_allowlist: vec![],
+            ica_controller_address: None,
+            surge_window: 0,
+            surge_threshold: 0,
+            surge_multiplier: Decimal::one(),
+            allowed_categories: vec![],
+            accepted_deposit_tokens: vec![],
+            refund_signaling_always: false,
+            auto_expire_grace: 0,
+            require_call_simulation: false,
+        }
+    }
+
+    /// Names and JSON encoded values of the fields that differ from `other`, in declaration
+    /// order. The address provider is not compared
+    pub fn diff(&self, other: &Config) -> StdResult<Vec<(String, String)>> {
+        let mut fields = vec![];
+
+        macro_rules! diff_fields {
+            ($($field:ident),* $(,)?) => {
+                // Destructuring to force a compile error if a field is added without being
+                // compared
+                let Config {
+                    address_provider_address: _,
+                    $($field: _,)*
+                } = other;
+
+                $(
+                    if self.$field != other.$field {
+                        fields.push((
+                            stringify!($field).to_string(),
+                            String::from_utf8_lossy(&to_vec(&self.$field)?).into_owned(),
+                        ));
+                    }
+                )*
+            };
+        }
+
+        diff_fields!(
+            proposal_voting_period,
+            proposal_effective_delay,
+            proposal_expiration_period,
+            proposal_required_deposit,
+            proposal_required_quorum,
+            proposal_required_threshold,
+            submitter_cooldown,
+            emergency_owner,
+            discussion_period,
+            submissions_paused,
+            voting_paused,
+            title_max,
+            description_max,
+            link_max,
+            slashed_deposit_destination,
+            fast_track_targets,
+            reclaim_grace_period,
+            snapshot_offset,
+            end_proposal_reward,
+            quorum_basis,
+            quorum_excluded_addresses,
+            mint_per_proposal_cap,
+            mint_total_cap,
+            reservation_period,
+            allow_privileged_token_calls,
+            early_vote_bonus,
+            voting_token_override,
+            avg_block_time_seconds,
+            min_for_votes_absolute,
+            proposal_required_deposit_ratio,
+            min_vote_power,
+            dedupe_window,
+            executor_allowlist,
+            ica_controller_address,
+            surge_window,
+            surge_threshold,
+            surge_multiplier,
+            allowed_categories,
+            accepted_deposit_tokens,
+            refund_signaling_always,
+            auto_expire_grace,
+            require_call_simulation,
+        );
+
+        Ok(fields)
+    }
+
     pub fn validate(&self) -> Result<(), ContractError> {
         decimal_param_le_one(&self.proposal_required_quorum, "proposal_required_quorum")?;
+        decimal_param_le_one(&self.early_vote_bonus, "early_vote_bonus")?;
+        decimal_param_le_one(
+            &self.proposal_required_deposit_ratio,
+            "proposal_required_deposit_ratio",
+        )?;
 
         let minimum_proposal_required_threshold =
             Decimal::percent(MINIMUM_PROPOSAL_REQUIRED_THRESHOLD_PERCENTAGE);
@@ -55,15 +291,110 @@ impl Config {
             .into());
         }
 
+        // With a zero quorum a single dust vote could pass a proposal
+        if self.proposal_required_quorum.is_zero() {
+            return Err(MarsError::InvalidParam {
+                param_name: "proposal_required_quorum".to_string(),
+                invalid_value: self.proposal_required_quorum.to_string(),
+                predicate: "> 0".to_string(),
+            }
+            .into());
+        }
+
+        if self.surge_multiplier < Decimal::one() {
+            return Err(MarsError::InvalidParam {
+                param_name: "surge_multiplier".to_string(),
+                invalid_value: self.surge_multiplier.to_string(),
+                predicate: ">= 1".to_string(),
+            }
+            .into());
+        }
+
+        if self.snapshot_offset == 0 {
+            return Err(MarsError::InvalidParam {
+                param_name: "snapshot_offset".to_string(),
+                invalid_value: self.snapshot_offset.to_string(),
+                predicate: "> 0".to_string(),
+            }
+            .into());
+        }
+
+        max_length_param_ge_min(self.title_max, MIN_TITLE_LENGTH, "title_max")?;
+        max_length_param_ge_min(self.description_max, MIN_DESC_LENGTH, "description_max")?;
+        max_length_param_ge_min(self.link_max, MIN_LINK_LENGTH, "link_max")?;
+
         Ok(())
     }
 }
 
+fn max_length_param_ge_min(
+    max_length: u64,
+    min_length: u64,
+    param_name: &str,
+) -> Result<(), ContractError> {
+    if max_length < min_length {
+        return Err(MarsError::InvalidParam {
+            param_name: param_name.to_string(),
+            invalid_value: max_length.to_string(),
+            predicate: format!(">= {}", min_length),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Destination of the deposits of rejected proposals
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SlashedDepositDestination {
+    /// Sent to the staking contract, to be distributed between stakers
+    Staking,
+    /// Burned
+    Burn,
+    /// Sent to the given address
+    Treasury(Addr),
+}
+
+/// Supply the proposal quorum is measured against
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuorumBasis {
+    /// Total voting power at the snapshot block
+    TotalSupply,
+    /// Total voting power at the snapshot block minus the xMARS balances of the quorum excluded
+    /// addresses
+    CirculatingSupply,
+}
+
 /// Global state
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct GlobalState {
     /// Number of proposals
     pub proposal_count: u64,
+    /// Total MARS deposited by proposals that have not ended yet. Backfilled from the active
+    /// proposals on migration
+    #[serde(default)]
+    pub locked_deposits: Uint128,
+    /// Total MARS minted through MintTreasury
+    #[serde(default)]
+    pub total_minted: Uint128,
+}
+
+/// Proposal id reserved (with its deposit) to be finalized later
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalReservation {
+    /// Address reserving the proposal. Only it can finalize the proposal
+    pub submitter_address: Addr,
+    /// Tokens deposited on the reservation, including the co-sponsor deposits. Moved to the
+    /// proposal when finalized, slashed if the reservation expires
+    pub deposit_amount: Uint128,
+    /// Token the deposit was made in, one of the config accepted deposit tokens. None for MARS
+    pub deposit_token: Option<Addr>,
+    /// Deposits added through CoSponsor by addresses other than the submitter
+    pub co_sponsors: Vec<(Addr, Uint128)>,
+    /// Last block at which the proposal can be finalized
+    pub expiration_height: u64,
 }
 
 /// Proposal metadata stored in state
@@ -78,10 +409,26 @@ pub struct Proposal {
     pub for_votes: Uint128,
     /// Number of against votes
     pub against_votes: Uint128,
-    /// Block at which voting for the porposal starts
+    /// Number of addresses that voted for
+    #[serde(default)]
+    pub for_voter_count: u64,
+    /// Number of addresses that voted against
+    #[serde(default)]
+    pub against_voter_count: u64,
+    /// Early vote bonus included in the for and against votes. It counts towards the threshold
+    /// but not towards the quorum
+    #[serde(default)]
+    pub early_vote_bonus_votes: Uint128,
+    /// Block at which the proposal was submitted
     pub start_height: u64,
+    /// Block at which voting for the porposal starts (after the discussion period)
+    #[serde(default)]
+    pub vote_start_height: u64,
     /// Block at which voting for the porposal ends
     pub end_height: u64,
+    /// Block at which voting power is measured for the proposal
+    #[serde(default)]
+    pub voting_power_snapshot_block: u64,
     /// Title for the proposal
     pub title: String,
     /// Description for the proposal
@@ -91,9 +438,44 @@ pub struct Proposal {
     pub link: Option<String>,
     /// Set of messages available to get executed if the proposal passes
     pub messages: Option<Vec<ProposalMessage>>,
-    /// MARS tokens deposited on the proposal submission. Will be returned to
+    /// Tokens deposited on the proposal submission. Will be returned to
     /// submitter if proposal passes and sent to xMars stakers otherwise
     pub deposit_amount: Uint128,
+    /// Token the deposit was made in, one of the config accepted deposit tokens. None for MARS
+    pub deposit_token: Option<Addr>,
+    /// Deposit the proposal was required to make in its deposit token when it was submitted
+    /// (or finalized), regardless of later config changes
+    #[serde(default)]
+    pub required_deposit_at_submission: Uint128,
+    /// Whether the deposit is still held by the council, refunded or slashed
+    #[serde(default)]
+    pub deposit_status: DepositStatus,
+    /// Part of the deposit added through CoSponsor by addresses other than the submitter. If
+    /// the deposit is refunded, each co-sponsor claims its part through ClaimSponsorRefund
+    #[serde(default)]
+    pub co_sponsors: Vec<(Addr, Uint128)>,
+    /// Quorum reached when the proposal was ended (None while it is active)
+    pub final_quorum: Option<Decimal>,
+    /// Share of for votes when the proposal was ended (None while it is active)
+    pub final_threshold: Option<Decimal>,
+    /// Total voting power the quorum was measured against when the proposal was ended (None
+    /// while it is active)
+    pub final_total_voting_power: Option<Uint128>,
+    /// Block at which the description or link were last amended, if ever
+    pub amended_at: Option<u64>,
+    /// Whether the council executes the proposal when it passes, without waiting for someone to
+    /// call ExecuteProposal. If the execution fails the proposal stays Passed
+    #[serde(default)]
+    pub auto_execute: bool,
+    /// Category the proposal was submitted with, one of the config allowed categories
+    pub category: Option<String>,
+    /// Total votes the proposal needs, on top of the ratio quorum
+    pub min_quorum_power: Option<Uint128>,
+    /// Blocks after the effective delay during which the proposal can be executed, when shorter
+    /// than the config expiration period
+    pub execution_deadline_blocks: Option<u64>,
+    /// Note given when the proposal was marked as implemented, if it was
+    pub implementation_note: Option<String>,
 }
 
 /// Execute call that will be executed by the DAO if the proposal succeeds
@@ -103,6 +485,35 @@ pub struct ProposalMessage {
     pub execution_order: u64,
     /// CosmosMsg that will be executed by the council
     pub msg: CosmosMsg,
+    /// Metadata for off-chain relayers (e.g. an IBC memo), emitted as an attribute when the
+    /// message is executed
+    pub memo: Option<String>,
+    /// Chain the message is executed on. Defaults to Local
+    #[serde(default)]
+    pub route: ExecuteRoute,
+}
+
+/// Where a proposal message is executed
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteRoute {
+    /// Executed by the council on this chain
+    #[default]
+    Local,
+    /// Executed by the council interchain account on the other end of the channel, through the
+    /// configured ICA controller
+    Ica { channel: String },
+}
+
+/// Execute message of the ICA controller used for messages with an Ica route
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IcaControllerExecuteMsg {
+    /// Send the messages as a transaction of the interchain account on the channel
+    SendTx {
+        channel: String,
+        msgs: Vec<CosmosMsg>,
+    },
 }
 
 /// Proposal Status
@@ -117,6 +528,42 @@ pub enum ProposalStatus {
     Rejected,
     /// Proposal has been approved and executed
     Executed,
+    /// Proposal was cancelled through ForceCancelProposal while being voted on
+    Cancelled,
+    /// Signaling proposal that passed and was marked as carried out off-chain through
+    /// MarkImplemented
+    Implemented,
+    /// Proposal voting period ended more than auto_expire_grace blocks ago but nobody ended it.
+    /// Only reported by queries, proposals are never stored as Expired
+    Expired,
+}
+
+/// Deposit Status
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DepositStatus {
+    /// Deposit is held by the council until the proposal ends or its transfer is claimed
+    #[default]
+    Locked,
+    /// Deposit was returned to the submitter
+    Refunded,
+    /// Deposit was slashed
+    Slashed,
+}
+
+impl std::fmt::Display for ProposalStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let display_str = match self {
+            ProposalStatus::Active => "active",
+            ProposalStatus::Passed => "passed",
+            ProposalStatus::Rejected => "rejected",
+            ProposalStatus::Executed => "executed",
+            ProposalStatus::Cancelled => "cancelled",
+            ProposalStatus::Implemented => "implemented",
+            ProposalStatus::Expired => "expired",
+        };
+        write!(f, "{}", display_str)
+    }
 }
 
 /// Single vote made by an address
@@ -126,6 +573,10 @@ pub struct ProposalVote {
     pub option: ProposalVoteOption,
     /// Voting power
     pub power: Uint128,
+    /// Voting power counted towards the proposal tally, including the early vote bonus. Votes
+    /// cast before it was added get their power on migration
+    #[serde(default)]
+    pub weighted_power: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -145,6 +596,128 @@ impl std::fmt::Display for ProposalVoteOption {
     }
 }
 
+/// Council configuration. Lists every config field so clients don't lose visibility of new
+/// ones, plus the contract version for feature detection
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    /// Contract version (as stored by cw2)
+    pub contract_version: String,
+    /// Address provider returns addresses for all protocol contracts
+    pub address_provider_address: Addr,
+    /// Blocks during which a proposal is active since being submitted
+    pub proposal_voting_period: u64,
+    /// Blocks that need to pass since a proposal succeeds in order for it to be available to be
+    /// executed
+    pub proposal_effective_delay: u64,
+    /// Blocks after the effective_delay during which a successful proposal can be activated before it expires
+    pub proposal_expiration_period: u64,
+    /// Number of Mars needed to make a proposal. Will be returned if successful. Will be
+    /// distributed between stakers if rejected.
+    pub proposal_required_deposit: Uint128,
+    /// % of total voting power required to participate in the proposal in order to consider it successfull
+    pub proposal_required_quorum: Decimal,
+    /// % of for votes required in order to consider the proposal successful. A tie (as many
+    /// for as against votes) always fails, regardless of the threshold
+    pub proposal_required_threshold: Decimal,
+    /// Blocks a submitter needs to wait after submitting a proposal before being able to submit
+    /// a new one. Zero disables the cooldown
+    pub submitter_cooldown: u64,
+    /// Address allowed to pause proposal submissions and voting without going through a
+    /// proposal. It cannot update any other parameter
+    pub emergency_owner: Option<Addr>,
+    /// Blocks after submission during which a proposal is visible but cannot be voted on.
+    /// Zero opens voting on submission
+    pub discussion_period: u64,
+    /// Proposal submissions are rejected while paused
+    pub submissions_paused: bool,
+    /// Votes are rejected while paused
+    pub voting_paused: bool,
+    /// Maximum length of a proposal title
+    pub title_max: u64,
+    /// Maximum length of a proposal description
+    pub description_max: u64,
+    /// Maximum length of a proposal link
+    pub link_max: u64,
+    /// Where the deposits of rejected proposals are sent
+    pub slashed_deposit_destination: SlashedDepositDestination,
+    /// Contracts that passed proposals can call without waiting for the effective delay, when all
+    /// of the proposal messages target them
+    pub fast_track_targets: Vec<Addr>,
+    /// Blocks after the end of the voting period after which the submitter can end the proposal
+    /// through ReclaimStaleDeposit
+    pub reclaim_grace_period: u64,
+    /// Blocks before the proposal submission at which voting power is measured. Must be
+    /// greater than zero so voting power cannot be acquired in the submission block
+    pub snapshot_offset: u64,
+    /// MARS paid from the treasury to whoever ends a proposal (unless it is the submitter).
+    /// Zero disables the reward
+    pub end_proposal_reward: Uint128,
+    /// Supply the proposal quorum is measured against
+    pub quorum_basis: QuorumBasis,
+    /// Addresses whose xMARS balance at the snapshot is not counted as circulating supply when
+    /// quorum basis is CirculatingSupply (e.g. the treasury)
+    pub quorum_excluded_addresses: Vec<Addr>,
+    /// Maximum MARS that can be minted through MintTreasury by a single proposal
+    pub mint_per_proposal_cap: Uint128,
+    /// Maximum MARS that can be minted through MintTreasury in total
+    pub mint_total_cap: Uint128,
+    /// Blocks a submitter has to finalize a reserved proposal before its deposit can be slashed.
+    /// Zero disables reservations
+    pub reservation_period: u64,
+    /// Allow proposals to change the MARS token admin, code or minter
+    pub allow_privileged_token_calls: bool,
+    /// Extra voting power multiplier for votes cast when voting opens, decaying linearly to
+    /// zero at the end of the voting period. Zero disables the bonus
+    pub early_vote_bonus: Decimal,
+    /// Token used instead of xMARS to measure voting power. It needs to implement the xMARS
+    /// BalanceAt and TotalSupplyAt queries
+    pub voting_token_override: Option<Addr>,
+    /// Average block time used to estimate the time remaining until a proposal ends
+    pub avg_block_time_seconds: u64,
+    /// For votes a proposal needs to pass, on top of the quorum and threshold. Zero disables
+    /// the floor
+    pub min_for_votes_absolute: Uint128,
+    /// Share of the MARS total supply required as proposal deposit, when greater than
+    /// proposal_required_deposit. Zero requires the flat deposit only
+    pub proposal_required_deposit_ratio: Decimal,
+    /// Voting power a voter needs at the snapshot block to cast a vote. Zero only rejects
+    /// voters without voting power
+    pub min_vote_power: Uint128,
+    /// Blocks during which a submitter cannot submit a proposal with the same title as one of its
+    /// active proposals. Zero disables the check
+    pub dedupe_window: u64,
+    /// Addresses allowed to execute passed proposals. Empty allows anyone
+    pub executor_allowlist: Vec<Addr>,
+    /// Interchain account controller proposal messages with an Ica route are sent through. None
+    /// rejects Ica routes
+    pub ica_controller_address: Option<Addr>,
+    /// Number of past blocks in which submitted proposals count towards the deposit surge. Zero
+    /// disables the surge
+    pub surge_window: u64,
+    /// Number of proposals submitted within the surge window above which the surge multiplier
+    /// applies to the required deposit
+    pub surge_threshold: u64,
+    /// Multiplier (>= 1) applied to the required deposit while proposal submissions surge
+    pub surge_multiplier: Decimal,
+    /// Categories proposals can be submitted with. Empty only allows uncategorized proposals
+    pub allowed_categories: Vec<String>,
+    /// cw20 tokens other than MARS accepted as proposal deposit, with the minimum deposit of each
+    pub accepted_deposit_tokens: Vec<(Addr, Uint128)>,
+    /// Whether the deposit of signaling proposals (without messages) is refunded even if they
+    /// are rejected, as they carry no execution risk
+    pub refund_signaling_always: bool,
+    /// Blocks after the end of the voting period after which queries report Active proposals
+    /// nobody ended as Expired. Their stored status is unchanged until they are ended. Zero
+    /// disables it
+    pub auto_expire_grace: u64,
+    /// Whether submissions are rejected when a message executes an address without a contract
+    /// (probed with a smart query), which catches mistyped addresses
+    pub require_call_simulation: bool,
+    /// Decimals of the MARS token, to display the deposit amounts. Resolved when queried, None
+    /// if the MARS token cannot be resolved (e.g. it is not set in the address provider yet)
+    pub deposit_token_decimals: Option<u8>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ProposalsListResponse {
     /// Total proposals submitted
@@ -153,6 +726,242 @@ pub struct ProposalsListResponse {
     pub proposal_list: Vec<Proposal>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TotalLockedDepositsResponse {
+    /// Total MARS deposited by proposals that have not ended yet
+    pub amount: Uint128,
+}
+
+/// MARS held by the council compared to the deposits it has to give back
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowBalanceResponse {
+    /// MARS balance of the council
+    pub balance: Uint128,
+    /// Total MARS deposited by proposals that have not ended yet
+    pub locked_deposits: Uint128,
+}
+
+/// Config fields that differ from the recommended defaults
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigDiffResponse {
+    /// Field names with their current JSON encoded values
+    pub fields: Vec<(String, String)>,
+}
+
+/// Config as it was set by an update
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigHistoryEntry {
+    /// Block at which the config was updated
+    pub height: u64,
+    /// Proposal whose execution updated the config. None for updates through sudo
+    pub proposal_id: Option<u64>,
+    pub config: Config,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigHistoryResponse {
+    pub entries: Vec<ConfigHistoryEntry>,
+}
+
+/// Time remaining until the end of a proposal voting period
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalTimeRemainingResponse {
+    pub proposal_id: u64,
+    /// Zero if voting has ended
+    pub blocks_remaining: u64,
+    /// Estimated with the configured average block time
+    pub estimated_seconds_remaining: u64,
+}
+
+/// Whether a proposal can be executed at the current block
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsExecutableResponse {
+    pub proposal_id: u64,
+    pub is_executable: bool,
+    pub reason: ProposalExecutableReason,
+}
+
+/// Executable proposals whose execution window closes soon
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExpiringExecutableProposalsResponse {
+    pub proposals: Vec<ExpiringExecutableProposal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExpiringExecutableProposal {
+    pub proposal_id: u64,
+    /// Last block at which the proposal can be executed
+    pub expiration_height: u64,
+}
+
+/// Vote tallies of a batch of proposals. Proposals that do not exist are skipped
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalTalliesResponse {
+    pub tallies: Vec<ProposalTally>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalTally {
+    pub proposal_id: u64,
+    pub for_votes: Uint128,
+    pub against_votes: Uint128,
+    pub status: ProposalStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalExecutableReason {
+    /// Proposal status is not passed (it is still active, was rejected or already executed)
+    NotPassed,
+    /// Proposal passed but the effective delay has not ended yet
+    DelayNotEnded,
+    /// Proposal passed but the expiration period ended without it being executed
+    Expired,
+    /// Proposal can be executed
+    Ready,
+}
+
+/// Deposit required to submit a proposal at the current block
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrentRequiredDepositResponse {
+    pub amount: Uint128,
+    /// Token the deposit is paid in (MARS)
+    pub token_address: Addr,
+}
+
+/// Protocol addresses the council resolves through the address provider
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ResolvedAddressesResponse {
+    /// MARS token, used for deposits
+    pub mars_token_address: Addr,
+    /// xMARS token, used for voting power
+    pub xmars_token_address: Addr,
+    /// Staking contract, default destination of slashed deposits
+    pub staking_address: Addr,
+}
+
+/// Headline governance numbers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StatsResponse {
+    /// Total proposals submitted
+    pub proposal_count: u64,
+    pub active_proposal_count: u64,
+    pub passed_proposal_count: u64,
+    pub rejected_proposal_count: u64,
+    pub executed_proposal_count: u64,
+    pub cancelled_proposal_count: u64,
+    pub implemented_proposal_count: u64,
+    /// Total MARS deposited by proposals that have not ended yet
+    pub locked_deposits: Uint128,
+    pub proposal_required_quorum: Decimal,
+    pub proposal_required_threshold: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VoterPowerResponse {
+    pub proposal_id: u64,
+    pub voter_address: Addr,
+    /// Block at which voting power is measured for the proposal
+    pub voting_power_snapshot_block: u64,
+    pub voting_power: Uint128,
+}
+
+/// Additional For voting power a proposal needs to pass
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PowerNeededToPassResponse {
+    pub proposal_id: u64,
+    /// Total voting power at the proposal's snapshot block
+    pub total_voting_power: Uint128,
+    /// Zero if the proposal would already pass
+    pub power_needed: Uint128,
+}
+
+/// Proposal data returned by the proposal query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalInfo {
+    pub proposal_id: u64,
+    /// Proposal id as a string. JSON numbers are parsed as doubles by JavaScript clients, which
+    /// lose precision above 2^53, so clients expecting string ids should read this field
+    pub proposal_id_str: String,
+    pub submitter_address: Addr,
+    pub status: ProposalStatus,
+    pub for_votes: Uint128,
+    pub against_votes: Uint128,
+    pub for_voter_count: u64,
+    pub against_voter_count: u64,
+    pub start_height: u64,
+    pub vote_start_height: u64,
+    pub end_height: u64,
+    pub title: String,
+    pub description: String,
+    pub link: Option<String>,
+    /// Omitted for proposals with many messages, which need to be queried through
+    /// ProposalMessages instead
+    pub messages: Option<Vec<ProposalMessage>>,
+    pub message_count: u64,
+    pub deposit_amount: Uint128,
+    pub deposit_token: Option<Addr>,
+    pub required_deposit_at_submission: Uint128,
+    pub deposit_status: DepositStatus,
+    pub co_sponsors: Vec<(Addr, Uint128)>,
+    /// Block at which voting power is measured for the proposal
+    pub voting_power_snapshot_block: u64,
+    /// Quorum, threshold and total voting power computed when the proposal was ended (None
+    /// while it is active)
+    pub final_quorum: Option<Decimal>,
+    pub final_threshold: Option<Decimal>,
+    pub final_total_voting_power: Option<Uint128>,
+    /// Block at which the description or link were last amended, if ever
+    pub amended_at: Option<u64>,
+    pub auto_execute: bool,
+    pub category: Option<String>,
+    pub min_quorum_power: Option<Uint128>,
+    pub execution_deadline_blocks: Option<u64>,
+    pub implementation_note: Option<String>,
+    /// Vote cast on the proposal by the voter passed in the query (None if no voter was passed
+    /// or if the voter has not voted)
+    pub caller_vote: Option<ProposalVoteResponse>,
+}
+
+/// Status a proposal moved to and the block at which it did
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalStatusChange {
+    pub status: ProposalStatus,
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalHistoryResponse {
+    pub proposal_id: u64,
+    /// Status changes in the order they happened, starting with the proposal becoming active
+    pub history: Vec<ProposalStatusChange>,
+}
+
+/// Proposal message that executed successfully
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExecutionResult {
+    /// Position of the message in execution order
+    pub position: u64,
+    pub execution_order: u64,
+    /// Block at which the message executed
+    pub height: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExecutionResultsResponse {
+    pub proposal_id: u64,
+    /// Messages that executed successfully, in execution order. A proposal that was executed
+    /// has one result per message
+    pub results: Vec<ExecutionResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProposalMessagesResponse {
+    pub proposal_id: u64,
+    /// Messages sorted by execution order
+    pub messages: Vec<ProposalMessage>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ProposalVotesResponse {
     pub proposal_id: u64,
@@ -164,17 +973,18 @@ pub struct ProposalVoteResponse {
     pub voter_address: String,
     pub option: ProposalVoteOption,
     pub power: Uint128,
+    pub weighted_power: Uint128,
 }
 
 pub mod msg {
-    use cosmwasm_std::Uint128;
+    use cosmwasm_std::{Binary, Uint128};
     use cw20::Cw20ReceiveMsg;
     use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
 
     use crate::math::decimal::Decimal;
 
-    use super::{ProposalMessage, ProposalVoteOption};
+    use super::{ProposalMessage, ProposalVoteOption, QuorumBasis, SlashedDepositDestination};
 
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
     pub struct InstantiateMsg {
@@ -191,10 +1001,45 @@ pub mod msg {
         pub proposal_required_deposit: Option<Uint128>,
         pub proposal_required_quorum: Option<Decimal>,
         pub proposal_required_threshold: Option<Decimal>,
+        pub submitter_cooldown: Option<u64>,
+        pub discussion_period: Option<u64>,
+        pub emergency_owner: Option<String>,
+        pub title_max: Option<u64>,
+        pub description_max: Option<u64>,
+        pub link_max: Option<u64>,
+        pub slashed_deposit_destination: Option<SlashedDepositDestination>,
+        pub fast_track_targets: Option<Vec<String>>,
+        pub reclaim_grace_period: Option<u64>,
+        pub snapshot_offset: Option<u64>,
+        pub end_proposal_reward: Option<Uint128>,
+        pub quorum_basis: Option<QuorumBasis>,
+        pub quorum_excluded_addresses: Option<Vec<String>>,
+        pub mint_per_proposal_cap: Option<Uint128>,
+        pub mint_total_cap: Option<Uint128>,
+        pub reservation_period: Option<u64>,
+        pub allow_privileged_token_calls: Option<bool>,
+        pub early_vote_bonus: Option<Decimal>,
+        pub voting_token_override: Option<String>,
+        pub avg_block_time_seconds: Option<u64>,
+        pub min_for_votes_absolute: Option<Uint128>,
+        pub proposal_required_deposit_ratio: Option<Decimal>,
+        pub min_vote_power: Option<Uint128>,
+        pub dedupe_window: Option<u64>,
+        pub executor_allowlist: Option<Vec<String>>,
+        pub ica_controller_address: Option<String>,
+        pub surge_window: Option<u64>,
+        pub surge_threshold: Option<u64>,
+        pub surge_multiplier: Option<Decimal>,
+        pub allowed_categories: Option<Vec<String>>,
+        pub accepted_deposit_tokens: Option<Vec<(String, Uint128)>>,
+        pub refund_signaling_always: Option<bool>,
+        pub auto_expire_grace: Option<u64>,
+        pub require_call_simulation: Option<bool>,
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
     #[serde(rename_all = "snake_case")]
+    #[allow(clippy::large_enum_variant)]
     pub enum ExecuteMsg {
         /// Implementation cw20 receive msg
         Receive(Cw20ReceiveMsg),
@@ -205,14 +1050,117 @@ pub mod msg {
             vote: ProposalVoteOption,
         },
 
+        /// Vote for several proposals in a single message. Each (proposal_id, vote) pair is
+        /// processed as an individual CastVote by the sender
+        CastVotes {
+            votes: Vec<(u64, ProposalVoteOption)>,
+        },
+
         /// End proposal after voting period has passed
         EndProposal { proposal_id: u64 },
 
+        /// End several proposals in a single message. Proposals that are not active or whose
+        /// voting period has not ended are skipped. Proposals that cannot be ended for another
+        /// reason (e.g. a missing voting power snapshot) are skipped and reported in a
+        /// `skipped_proposal_id` attribute
+        EndProposalsBatch { proposal_ids: Vec<u64> },
+
+        /// End a proposal nobody ended once the reclaim grace period has passed after its voting
+        /// period (only callable by the submitter). Votes are tallied as in EndProposal
+        ReclaimStaleDeposit { proposal_id: u64 },
+
         /// Execute a successful proposal
         ExecuteProposal { proposal_id: u64 },
 
         /// Update config
         UpdateConfig { config: CreateOrUpdateConfig },
+
+        /// Pause or unpause proposal submissions and voting (only callable by the emergency owner
+        /// or the council itself)
+        EmergencySetPaused { submissions: bool, voting: bool },
+
+        /// Propose a new emergency owner (only callable by the emergency owner or the council
+        /// itself). The new emergency owner takes over once it calls AcceptGuardianship
+        TransferGuardianship { new_guardian: String },
+
+        /// Become the emergency owner (only callable by the address proposed through
+        /// TransferGuardianship)
+        AcceptGuardianship {},
+
+        /// Populate a proposal reserved through ReserveProposal, opening it for voting (only
+        /// callable by the address that reserved it). The reservation deposit, including the
+        /// co-sponsor deposits, needs to reach the required deposit
+        FinalizeProposal {
+            proposal_id: u64,
+            title: String,
+            description: String,
+            link: Option<String>,
+            messages: Option<Vec<ProposalMessage>>,
+        },
+
+        /// Remove a reservation that was not finalized in time, slashing its deposit
+        ExpireReservation { proposal_id: u64 },
+
+        /// Update the description and/or link of a proposal before voting starts (only callable
+        /// by the submitter). Title and messages cannot be amended
+        AmendProposal {
+            proposal_id: u64,
+            description: Option<String>,
+            link: Option<String>,
+        },
+
+        /// Mint MARS to recipient (only callable by the council itself, which needs to be a
+        /// MARS minter). Bounded by the per proposal and total mint caps
+        MintTreasury { recipient: String, amount: Uint128 },
+
+        /// Retry the deposit refund of an ended proposal that failed when the proposal was ended
+        ClaimPendingDeposit { proposal_id: u64 },
+
+        /// Claim the refund of the deposit the sender added to a proposal through CoSponsor,
+        /// once the proposal ended with its deposit refunded
+        ClaimSponsorRefund { proposal_id: u64 },
+
+        /// Send the slashed deposits whose transfer failed when their proposals were ended, using
+        /// the current slashed deposit destination and staking address
+        SweepPendingSlashes {},
+
+        /// Cancel an active proposal regardless of the votes cast, slashing its deposit to the
+        /// configured destination (only callable by the emergency owner or the council itself)
+        ForceCancelProposal { proposal_id: u64 },
+
+        /// Transfer cw20 tokens sent to the council by mistake (only callable by the council
+        /// itself). MARS and the accepted deposit tokens cannot be rescued as they back the
+        /// proposal deposits
+        RescueTokens {
+            token: String,
+            recipient: String,
+            amount: Uint128,
+        },
+
+        /// Move finalized proposals with an id lower than `before_id` to the archive, which is
+        /// only searched by queries passing `include_archived` (only callable by the emergency
+        /// owner or the council itself). Finalized proposals are the executed, rejected,
+        /// cancelled and implemented ones, and the passed ones that expired without being
+        /// executed. Proposals are scanned in pages of `limit` proposals after `start_after`
+        ArchiveProposals {
+            before_id: u64,
+            start_after: Option<u64>,
+            limit: Option<u32>,
+        },
+
+        /// Set the address provider and the voting token override at once (only callable by the
+        /// council itself). The voting token they resolve to must support the xMARS BalanceAt
+        /// query and differ from the MARS token. A None override measures voting power with the
+        /// xMARS token of the new address provider
+        RotateGovernanceTokens {
+            address_provider: String,
+            voting_token_override: Option<String>,
+        },
+
+        /// Close out a passed signaling proposal (without messages) whose mandate was carried out
+        /// off-chain, moving it to Implemented with the given note (only callable by the
+        /// emergency owner or the council itself)
+        MarkImplemented { proposal_id: u64, note: String },
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -225,30 +1173,169 @@ pub mod msg {
             description: String,
             link: Option<String>,
             messages: Option<Vec<ProposalMessage>>,
+            /// Execute the proposal as soon as it passes. Only allowed when all its messages
+            /// target fast track contracts
+            auto_execute: Option<bool>,
+            /// Category used to filter proposals. Must be one of the config allowed categories
+            category: Option<String>,
+            /// Total votes the proposal needs to pass, on top of the configured quorum ratio.
+            /// Cannot exceed the total voting power at submission
+            min_quorum_power: Option<Uint128>,
+            /// Blocks after the effective delay during which the proposal can be executed if it
+            /// passes. Only applies when shorter than the config expiration period
+            execution_deadline_blocks: Option<u64>,
         },
+        /// Reserve a proposal id, to be finalized by the sender before the reservation period
+        /// ends. The deposit can be partial, as long as it reaches the SubmitProposal deposit
+        /// through CoSponsor before the proposal is finalized
+        ReserveProposal {},
+        /// Add to the deposit of a reserved proposal. Needs to be in the reservation deposit
+        /// token. The deposit is refunded or slashed along with the rest of the proposal deposit
+        CoSponsor { proposal_id: u64 },
+        /// Add to the deposit of an active proposal, e.g. when the required deposit rose after
+        /// it was submitted. Needs to be in the proposal deposit token. Contributions other than
+        /// the submitter's are refunded through ClaimSponsorRefund if the deposit is refunded
+        TopUpDeposit { proposal_id: u64 },
+    }
+
+    /// Messages sent by the chain (e.g. by chain governance) through sudo
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    #[serde(rename_all = "snake_case")]
+    pub enum SudoMsg {
+        /// Update config without going through a council proposal
+        UpdateConfig { config: CreateOrUpdateConfig },
+    }
+
+    /// Proposals created before the submitter index and the status counts were introduced are
+    /// indexed on migration. Large proposal sets can be indexed in several migrations by passing
+    /// the last indexed proposal id as `start_after`
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct MigrateMsg {
+        pub start_after: Option<u64>,
+        /// Maximum number of proposals and votes migrated
+        pub limit: Option<u32>,
     }
 
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
     #[serde(rename_all = "snake_case")]
     pub enum QueryMsg {
         Config {},
+        /// Get the config fields whose values differ from the recommended defaults, with their
+        /// current values
+        ConfigDiff {},
+        /// Get the config set by each update, in update order. `start_after` is the height of
+        /// the last entry returned by the previous page. The updates of a block are never split
+        /// across pages, so a page can have more than `limit` entries
+        ConfigHistory {
+            start_after: Option<u64>,
+            limit: Option<u32>,
+        },
+        /// Get proposals starting from the given id. If category is passed, only proposals with
+        /// that category are returned and `limit` counts matching proposals only. Archived
+        /// proposals are only returned if `include_archived` is true
         Proposals {
             start: Option<u64>,
             limit: Option<u32>,
+            category: Option<String>,
+            include_archived: Option<bool>,
         },
+        /// Get a proposal. If voter is passed, the response includes the vote cast by that address
+        /// on the proposal (if any). Archived proposals are only found if `include_archived` is
+        /// true
         Proposal {
             proposal_id: u64,
+            voter: Option<String>,
+            include_archived: Option<bool>,
+        },
+        /// Get the status changes of a proposal
+        ProposalHistory {
+            proposal_id: u64,
         },
+        /// Get the messages of an executed proposal that were confirmed to execute successfully
+        ExecutionResults {
+            proposal_id: u64,
+        },
+        /// Get a proposal by the sha256 hash of its title, submitter address and start height
+        /// (as big endian bytes)
+        ProposalByHash {
+            hash: Binary,
+        },
+        /// Get the messages of a proposal sorted by execution order. `start_after` is the
+        /// position of the last message returned in that order
+        ProposalMessages {
+            proposal_id: u64,
+            start_after: Option<u64>,
+            limit: Option<u32>,
+        },
+        /// Get the blocks and estimated seconds remaining until a proposal voting period ends
+        ProposalTimeRemaining {
+            proposal_id: u64,
+        },
+        /// Get whether a proposal can be executed at the current block, and the reason if it
+        /// cannot
+        IsExecutable {
+            proposal_id: u64,
+        },
+        /// Get the proposals that can be executed now and whose execution window closes within
+        /// the given number of blocks, soonest expiration first
+        ExpiringExecutableProposals {
+            within_blocks: u64,
+            limit: Option<u32>,
+        },
+        /// Get the proposals open for voting at the current block on which the voter has not
+        /// voted yet. Votes are not indexed by voter, so all active proposals are scanned
+        VotableProposalsFor {
+            voter: String,
+            limit: Option<u32>,
+        },
+        /// Get the votes and status of the given proposals, including archived ones. Proposals
+        /// that do not exist are skipped
+        ProposalTallies {
+            ids: Vec<u64>,
+        },
+        /// Get the votes cast on a proposal. If option is passed, only votes for that option are
+        /// returned. Votes are not indexed by option, so non-matching votes are skipped while
+        /// scanning and `limit` counts matching votes only
         ProposalVotes {
             proposal_id: u64,
             start_after: Option<String>,
             limit: Option<u32>,
+            option: Option<ProposalVoteOption>,
+        },
+        /// Get the total amount of MARS held as deposit by proposals that have not ended yet
+        TotalLockedDeposits {},
+        /// Get the MARS balance of the council along with the locked deposits, which the balance
+        /// should cover
+        EscrowBalance {},
+        /// Get proposal counts (total and by status), locked deposits and the current quorum and
+        /// threshold
+        Stats {},
+        /// Get the deposit a proposal submitted now requires, which depends on the MARS supply
+        /// if a deposit ratio is configured
+        CurrentRequiredDeposit {},
+        /// Get the voting power a voter has on a proposal, measured at the proposal's snapshot
+        /// block
+        VoterPower {
+            proposal_id: u64,
+            voter: String,
+        },
+        /// Get the additional For voting power an active proposal needs to meet quorum,
+        /// threshold and the absolute For votes minimum if voting ended now
+        PowerNeededToPass {
+            proposal_id: u64,
+        },
+        /// Get a proposal reservation that has not been finalized yet
+        Reservation {
+            proposal_id: u64,
         },
+        /// Get the MARS, xMARS and staking addresses the council resolves through the address
+        /// provider
+        ResolvedAddresses {},
     }
 }
 
 pub mod error {
-    use cosmwasm_std::StdError;
+    use cosmwasm_std::{OverflowError, StdError, Uint128};
     use thiserror::Error;
 
     use crate::error::MarsError;
@@ -261,21 +1348,82 @@ pub mod error {
         #[error("{0}")]
         Mars(#[from] MarsError),
 
+        #[error("{0}")]
+        Overflow(#[from] OverflowError),
+
         #[error("Invalid Proposal: {error:?}")]
         InvalidProposal { error: String },
+        #[error("Proposal title must be at least {min:?} characters long")]
+        TitleTooShort { min: u64 },
+        #[error("Proposal title must be at most {max:?} characters long")]
+        TitleTooLong { max: u64 },
+        #[error("Proposal description must be at least {min:?} characters long")]
+        DescriptionTooShort { min: u64 },
+        #[error("Proposal description must be at most {max:?} characters long")]
+        DescriptionTooLong { max: u64 },
+        #[error("Proposal link must be at least {min:?} characters long")]
+        LinkTooShort { min: u64 },
+        #[error("Proposal link must be at most {max:?} characters long")]
+        LinkTooLong { max: u64 },
+        #[error("Proposal message memo must be at most {max:?} characters long")]
+        MemoTooLong { max: u64 },
+        #[error("Must deposit at least {required:?} MARS tokens")]
+        InsufficientDeposit { required: Uint128 },
+
+        #[error("Proposal submissions are paused")]
+        SubmissionsPaused {},
+        #[error("Proposal reservations are disabled")]
+        ReservationsDisabled {},
+        #[error("Reservation expired at block {expiration_height:?}")]
+        FinalizeProposalReservationExpired { expiration_height: u64 },
+        #[error("Reservation can only be expired after block {expiration_height:?}")]
+        ExpireReservationNotExpired { expiration_height: u64 },
+        #[error("Co-sponsor deposit must be in the reservation deposit token")]
+        CoSponsorInvalidToken {},
+        #[error("There is no sponsor refund to claim")]
+        NoSponsorRefund {},
+        #[error("Deposit top up must be in the proposal deposit token")]
+        TopUpInvalidToken {},
+        #[error("Submitter cannot submit a new proposal until block {next_submission_height:?}")]
+        SubmitProposalCooldownNotEnded { next_submission_height: u64 },
+        #[error("Proposal duplicates active proposal {proposal_id:?} from the same submitter")]
+        DuplicateProposal { proposal_id: u64 },
+        #[error("Ica routes require an ICA controller to be configured")]
+        IcaRouteNotSupported {},
 
         #[error("Proposal is not active")]
         ProposalNotActive {},
 
+        #[error("Voting is paused")]
+        VotingPaused {},
         #[error("User has already voted on this proposal")]
         VoteUserAlreadyVoted {},
         #[error("User has no voting power at block: {block:?}")]
         VoteNoVotingPower { block: u64 },
+        #[error("User voting power is below the minimum of {min_vote_power:?}")]
+        VoteBelowMinimumPower { min_vote_power: Uint128 },
+        #[error(
+            "Proposal can only be amended before voting starts at block: {vote_start_height:?}"
+        )]
+        AmendProposalVotingStarted { vote_start_height: u64 },
+        #[error("Voting has not started, it starts at block: {vote_start_height:?}")]
+        VoteVotingNotStarted { vote_start_height: u64 },
         #[error("Voting period has ended")]
         VoteVotingPeriodEnded {},
 
+        #[error("Batch cannot contain more than {max:?} items")]
+        BatchTooLarge { max: usize },
+
         #[error("Voting period has not ended")]
         EndProposalVotingPeriodNotEnded {},
+        #[error("Voting power snapshot is not available at block: {block:?}")]
+        SnapshotUnavailable { block: u64 },
+        #[error("There is no pending guardianship transfer")]
+        NoPendingGuardianship {},
+        #[error("There are no pending slashed deposits to sweep")]
+        NoPendingSlashes {},
+        #[error("Deposit can only be reclaimed after block {reclaim_height:?}")]
+        ReclaimStaleDepositGracePeriodNotEnded { reclaim_height: u64 },
 
         #[error("Proposal has not passed or has already been executed")]
         ExecuteProposalNotPassed {},
@@ -283,6 +1431,31 @@ pub mod error {
         ExecuteProposalDelayNotEnded {},
         #[error("Proposal has expired")]
         ExecuteProposalExpired {},
+
+        #[error("Mint amount exceeds the per proposal cap of {cap:?}")]
+        MintTreasuryProposalCapExceeded { cap: Uint128 },
+        #[error("Mint amount exceeds the remaining total cap of {remaining:?}")]
+        MintTreasuryTotalCapExceeded { remaining: Uint128 },
+
+        #[error("MARS cannot be rescued as it backs the proposal deposits")]
+        RescueTokensMarsToken {},
+
+        #[error("Accepted deposit tokens cannot be rescued as they back the proposal deposits")]
+        RescueTokensDepositToken {},
+
+        #[error("Only signaling proposals can be marked as implemented")]
+        MarkImplementedNotSignaling {},
+        #[error("Implementation note must be at most {max:?} characters long")]
+        ImplementationNoteTooLong { max: u64 },
+
+        #[error("Voting token {voting_token} is not compatible with xMARS")]
+        IncompatibleVotingToken { voting_token: String },
+
+        #[error("Cannot migrate from contract {contract}")]
+        MigrateInvalidContract { contract: String },
+
+        #[error("Cannot migrate from version {version}")]
+        MigrateInvalidVersion { version: String },
     }
 
     impl ContractError {
@@ -1,21 +1,42 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Uint128};
+use cw20::Expiration;
+
+use crate::asset::Asset;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct Config {
     pub red_bank_address: Addr,
     pub incentives_address: Addr,
+    /// Money market asset the token represents deposits of
+    pub underlying_asset: Asset,
+    /// Expiration given to new allowances increased without one. None lets them never expire
+    #[serde(default)]
+    pub default_allowance_expiry: Option<Expiration>,
+}
+
+/// Limit on the amount a spender can use from an owner's allowance in a single block
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AllowanceSpendLimit {
+    /// Maximum amount the spender can use per block
+    pub per_block_limit: Uint128,
+    /// Last block at which the spender used the allowance
+    pub last_spend_block: u64,
+    /// Amount used at `last_spend_block`
+    pub spent_this_block: Uint128,
 }
 
 pub mod msg {
-    use cosmwasm_std::{Binary, Uint128};
+    use cosmwasm_std::{Binary, Timestamp, Uint128};
     use cw20::{Cw20Coin, Expiration, Logo, MinterResponse};
     use cw20_base::msg::InstantiateMarketingInfo;
     use schemars::JsonSchema;
     use serde::{Deserialize, Serialize};
 
+    use crate::asset::Asset;
+
     #[derive(Serialize, Deserialize, JsonSchema)]
     pub struct InstantiateMsg {
         // cw20_base params
@@ -30,6 +51,8 @@ pub mod msg {
         pub init_hook: Option<InitHook>,
         pub red_bank_address: String,
         pub incentives_address: String,
+        pub underlying_asset: Asset,
+        pub default_allowance_expiry: Option<Expiration>,
     }
 
     /// Hook to be called after token initialization
@@ -46,6 +69,10 @@ pub mod msg {
         /// by the money market.
         Transfer { recipient: String, amount: Uint128 },
 
+        /// Transfers amount to each (recipient, amount) pair, in order. All the transfers are
+        /// finalized by the money market through a single message.
+        TransferBatch { transfers: Vec<(String, Uint128)> },
+
         /// Forced transfer called by the money market when an account is being liquidated
         TransferOnLiquidation {
             sender: String,
@@ -69,6 +96,11 @@ pub mod msg {
         /// and adds to the recipient balance.
         Mint { recipient: String, amount: Uint128 },
 
+        /// Only with the "mintable" extension. If authorized, creates new tokens for each
+        /// (recipient, amount) pair. Total supply is updated and checked against the cap once for
+        /// the whole batch.
+        MintBatch { mints: Vec<(String, Uint128)> },
+
         /// Only with "approval" extension. Allows spender to access an additional amount tokens
         /// from the owner's (env.sender) account. If expires is Some(), overwrites current allowance
         /// expiration with this one.
@@ -85,6 +117,22 @@ pub mod msg {
             amount: Uint128,
             expires: Option<Expiration>,
         },
+        /// Limits the amount of the owner's (info.sender) allowance the spender can use per block.
+        /// Setting None removes the limit
+        SetAllowanceSpendLimit {
+            spender: String,
+            per_block_limit: Option<Uint128>,
+        },
+        /// Proposes moving the owner's (info.sender) allowance for spender to new_owner. The
+        /// allowance stays with the owner until new_owner accepts it
+        TransferAllowance { spender: String, new_owner: String },
+        /// Accepts a pending allowance transfer to the sender, moving the owner's allowance for
+        /// spender with its amount, expiration and spend limit. Fails if the sender already has
+        /// an allowance for spender
+        AcceptAllowance { owner: String, spender: String },
+        /// Removes the owner's (info.sender) allowance and spend limit for spender, whatever the
+        /// remaining amount. Does nothing if there is no allowance
+        RevokeAllowance { spender: String },
         /// Only with "approval" extension. Transfers amount tokens from owner -> recipient
         /// if `env.sender` has sufficient pre-approval.
         TransferFrom {
@@ -100,6 +148,9 @@ pub mod msg {
             amount: Uint128,
             msg: Binary,
         },
+        /// Renames the token. Only callable by the money market. Name and symbol are validated
+        /// as on instantiation, decimals cannot be changed.
+        UpdateTokenInfo { name: String, symbol: String },
         /// Only with the "marketing" extension. If authorized, updates marketing metadata.
         /// Setting None/null for any of these will leave it unchanged.
         /// Setting Some("") will clear this field on the contract storage
@@ -123,6 +174,12 @@ pub mod msg {
         Balance {
             address: String,
         },
+        /// Returns the current balance of each of the given addresses (0 if unset), in the same
+        /// order. At most MAX_BALANCE_BATCH_ADDRESSES addresses can be queried at once.
+        /// Return type: Vec<(String, Uint128)>.
+        BalanceBatch {
+            addresses: Vec<String>,
+        },
         /// Returns both balance (0 if unset) and total supply
         /// Used by incentives contract when computing unclaimed rewards
         /// Return type: BalanceAndTotalSupplyResponse
@@ -140,6 +197,12 @@ pub mod msg {
             owner: String,
             spender: String,
         },
+        /// Returns the per block limit on the spender's use of the owner's allowance, if any.
+        /// Return type: Option<AllowanceSpendLimit>.
+        AllowanceSpendLimit {
+            owner: String,
+            spender: String,
+        },
         /// Only with "enumerable" extension (and "allowances")
         /// Returns all allowances this owner has approved. Supports pagination.
         /// Return type: AllAllowancesResponse.
@@ -148,6 +211,16 @@ pub mod msg {
             start_after: Option<String>,
             limit: Option<u32>,
         },
+        /// Returns the owner's allowances expiring at a height at or before before_block or, if
+        /// before_time is given, at a time at or before before_time. Allowances that never expire
+        /// are not returned.
+        /// Return type: AllAllowancesResponse.
+        ExpiringAllowances {
+            owner: String,
+            before_block: u64,
+            before_time: Option<Timestamp>,
+            limit: Option<u32>,
+        },
         /// Only with "enumerable" extension
         /// Returns all accounts that have balances. Supports pagination.
         /// Return type: AllAccountsResponse.
@@ -170,8 +243,22 @@ pub mod msg {
         UnderlyingAssetBalance {
             address: String,
         },
+        /// Returns the money market asset the token represents deposits of.
+        /// Return type: Asset.
+        UnderlyingAsset {},
+        /// Returns the total supply as of the end of the given block, recorded whenever tokens
+        /// are minted or burned. snapshot_found is false if no supply was recorded at or before it.
+        /// Return type: xmars_token::TotalSupplyResponse.
+        TotalSupplyAt {
+            block: u64,
+        },
     }
 
+    /// Tokens deployed before the underlying asset was stored get it from their red bank market
+    /// on migration
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct MigrateMsg {}
+
     #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
     pub struct BalanceAndTotalSupplyResponse {
         pub balance: Uint128,
@@ -151,6 +151,29 @@ pub enum ExecuteMsg {
         /// Transfer amount
         amount: Uint128,
     },
+
+    /// Called by liquidity token (maToken). Same as FinalizeLiquidityTokenTransfer for several
+    /// transfers made in the same call, in the order they were made
+    FinalizeLiquidityTokenTransferBatch {
+        transfers: Vec<LiquidityTokenTransfer>,
+    },
+}
+
+/// Liquidity token transfer to be finalized by the red bank
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidityTokenTransfer {
+    /// Token sender. Address is trusted because it should have been verified in
+    /// the token contract
+    pub sender_address: Addr,
+    /// Token recipient. Address is trusted because it should have been verified in
+    /// the token contract
+    pub recipient_address: Addr,
+    /// Sender's balance before the token transfer
+    pub sender_previous_balance: Uint128,
+    /// Recipient's balance before the token transfer
+    pub recipient_previous_balance: Uint128,
+    /// Transfer amount
+    pub amount: Uint128,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
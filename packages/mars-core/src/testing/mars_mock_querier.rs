@@ -1,15 +1,17 @@
+use std::collections::HashSet;
+
 use cosmwasm_std::{
     from_binary, from_slice,
     testing::{MockQuerier, MOCK_CONTRACT_ADDR},
-    Addr, Coin, Fraction, Querier, QuerierResult, QueryRequest, StdResult, SystemError, Uint128,
-    WasmQuery,
+    Addr, Coin, ContractResult, Fraction, Querier, QuerierResult, QueryRequest, StdResult,
+    SystemError, Uint128, WasmQuery,
 };
 use cw20::Cw20QueryMsg;
 use terra_cosmwasm::TerraQueryWrapper;
 
 use crate::{
-    address_provider, incentives, ma_token, oracle, staking, testing::mock_address_provider,
-    vesting, xmars_token,
+    address_provider, incentives, ma_token, oracle, red_bank, staking,
+    testing::mock_address_provider, vesting, xmars_token,
 };
 use astroport::{
     asset::{Asset, PairInfo},
@@ -23,6 +25,7 @@ use super::{
     incentives_querier::IncentivesQuerier,
     native_querier::NativeQuerier,
     oracle_querier::OracleQuerier,
+    red_bank_querier::RedBankQuerier,
     staking_querier::StakingQuerier,
     vesting_querier::VestingQuerier,
     xmars_querier::XMarsQuerier,
@@ -40,6 +43,11 @@ pub struct MarsMockQuerier {
     staking_querier: StakingQuerier,
     vesting_querier: VestingQuerier,
     incentives_querier: IncentivesQuerier,
+    red_bank_querier: RedBankQuerier,
+    /// Contracts answering smart queries the mock does not support with a contract error, as a
+    /// contract without such query would. Unsupported queries to any other address fail as if
+    /// there was no contract at it
+    contracts: HashSet<Addr>,
 }
 
 impl Querier for MarsMockQuerier {
@@ -72,6 +80,8 @@ impl MarsMockQuerier {
             staking_querier: StakingQuerier::default(),
             vesting_querier: VestingQuerier::default(),
             incentives_querier: IncentivesQuerier::default(),
+            red_bank_querier: RedBankQuerier::default(),
+            contracts: HashSet::new(),
         }
     }
 
@@ -131,6 +141,17 @@ impl MarsMockQuerier {
         token_info.symbol = symbol;
     }
 
+    #[allow(clippy::or_fun_call)]
+    pub fn set_cw20_decimals(&mut self, cw20_address: Addr, decimals: u8) {
+        let token_info = self
+            .cw20_querier
+            .token_info_responses
+            .entry(cw20_address)
+            .or_insert(mock_token_info_response());
+
+        token_info.decimals = decimals;
+    }
+
     pub fn set_oracle_price(&mut self, asset_reference: Vec<u8>, price: Decimal) {
         self.oracle_querier.prices.insert(asset_reference, price);
     }
@@ -159,6 +180,10 @@ impl MarsMockQuerier {
         self.xmars_querier.total_supplies_at.insert(block, balance);
     }
 
+    pub fn set_xmars_total_supply_missing_at(&mut self, block: u64) {
+        self.xmars_querier.missing_total_supplies_at.insert(block);
+    }
+
     pub fn set_vesting_address(&mut self, address: Addr) {
         self.vesting_querier.vesting_address = address;
     }
@@ -237,6 +262,19 @@ impl MarsMockQuerier {
             .insert(Addr::unchecked(user_address), unclaimed_rewards);
     }
 
+    pub fn set_red_bank_markets_list(
+        &mut self,
+        red_bank_address: Addr,
+        markets_list: &[red_bank::MarketInfo],
+    ) {
+        self.red_bank_querier.red_bank_address = red_bank_address;
+        self.red_bank_querier.markets_list = markets_list.to_vec();
+    }
+
+    pub fn set_contract(&mut self, address: Addr) {
+        self.contracts.insert(address);
+    }
+
     pub fn handle_query(&self, request: &QueryRequest<TerraQueryWrapper>) -> QuerierResult {
         match &request {
             QueryRequest::Custom(TerraQueryWrapper { route, query_data }) => {
@@ -254,6 +292,12 @@ impl MarsMockQuerier {
                         .handle_cw20_query(&contract_addr, cw20_query);
                 }
 
+                // XMars Queries (before MaToken ones, as both tokens support TotalSupplyAt)
+                let parse_xmars_query: StdResult<xmars_token::msg::QueryMsg> = from_binary(msg);
+                if let Ok(xmars_query) = parse_xmars_query {
+                    return self.xmars_querier.handle_query(&contract_addr, xmars_query);
+                }
+
                 // MaToken Queries
                 let parse_ma_token_query: StdResult<ma_token::msg::QueryMsg> = from_binary(msg);
                 if let Ok(ma_token_query) = parse_ma_token_query {
@@ -262,12 +306,6 @@ impl MarsMockQuerier {
                         .handle_ma_token_query(&contract_addr, ma_token_query);
                 }
 
-                // XMars Queries
-                let parse_xmars_query: StdResult<xmars_token::msg::QueryMsg> = from_binary(msg);
-                if let Ok(xmars_query) = parse_xmars_query {
-                    return self.xmars_querier.handle_query(&contract_addr, xmars_query);
-                }
-
                 // Address Provider Queries
                 let parse_address_provider_query: StdResult<address_provider::msg::QueryMsg> =
                     from_binary(msg);
@@ -324,7 +362,27 @@ impl MarsMockQuerier {
                         .handle_query(&contract_addr, vesting_query);
                 }
 
-                panic!("[mock]: Unsupported wasm query: {:?}", msg);
+                // Red Bank Queries (only MarketsList)
+                let parse_red_bank_query: StdResult<red_bank::msg::QueryMsg> = from_binary(msg);
+                if let Ok(red_bank_query @ red_bank::msg::QueryMsg::MarketsList {}) =
+                    parse_red_bank_query
+                {
+                    return self
+                        .red_bank_querier
+                        .handle_query(&contract_addr, red_bank_query);
+                }
+
+                if self.contracts.contains(&contract_addr) {
+                    return Ok(ContractResult::Err(format!(
+                        "[mock]: Unsupported wasm query: {:?}",
+                        msg
+                    )))
+                    .into();
+                }
+                Err(SystemError::NoSuchContract {
+                    addr: contract_addr.to_string(),
+                })
+                .into()
             }
 
             _ => self.base.handle_query(request),
@@ -8,21 +8,24 @@ use crate::address_provider::MarsContract;
 
 pub fn handle_query(contract_addr: &Addr, query: QueryMsg) -> QuerierResult {
     let address_provider = Addr::unchecked("address_provider");
-    if *contract_addr != address_provider {
+    // Address provider without any address set, which resolves every contract to an empty address
+    let unconfigured_address_provider = Addr::unchecked("unconfigured_address_provider");
+    let get_address = if *contract_addr == address_provider {
+        get_contract_address
+    } else if *contract_addr == unconfigured_address_provider {
+        |_| Addr::unchecked("")
+    } else {
         panic!(
             "[mock]: Address provider request made to {} shoud be {}",
             contract_addr, address_provider
         );
-    }
+    };
 
     let ret: ContractResult<Binary> = match query {
-        QueryMsg::Address { contract } => to_binary(&get_contract_address(contract)).into(),
+        QueryMsg::Address { contract } => to_binary(&get_address(contract)).into(),
 
         QueryMsg::Addresses { contracts } => {
-            let addresses = contracts
-                .into_iter()
-                .map(get_contract_address)
-                .collect::<Vec<_>>();
+            let addresses = contracts.into_iter().map(get_address).collect::<Vec<_>>();
             to_binary(&addresses).into()
         }
 
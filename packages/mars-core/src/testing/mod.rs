@@ -9,6 +9,7 @@ mod mock_address_provider;
 mod mocks;
 mod native_querier;
 mod oracle_querier;
+mod red_bank_querier;
 mod staking_querier;
 mod vesting_querier;
 mod xmars_querier;
@@ -0,0 +1,38 @@
+use cosmwasm_std::{to_binary, Addr, Binary, ContractResult, QuerierResult};
+
+use crate::red_bank::msg::QueryMsg;
+use crate::red_bank::{MarketInfo, MarketsListResponse};
+
+pub struct RedBankQuerier {
+    /// red bank contract address to be used in queries
+    pub red_bank_address: Addr,
+    /// markets returned by the MarketsList query
+    pub markets_list: Vec<MarketInfo>,
+}
+
+impl Default for RedBankQuerier {
+    fn default() -> Self {
+        RedBankQuerier {
+            red_bank_address: Addr::unchecked(""),
+            markets_list: vec![],
+        }
+    }
+}
+
+impl RedBankQuerier {
+    pub fn handle_query(&self, contract_addr: &Addr, query: QueryMsg) -> QuerierResult {
+        if contract_addr != &self.red_bank_address {
+            panic!( "[mock]: made a red bank query but red bank contract address is incorrect, was: {}, should be {}",  contract_addr, self.red_bank_address );
+        }
+
+        let ret: ContractResult<Binary> = match query {
+            QueryMsg::MarketsList {} => to_binary(&MarketsListResponse {
+                markets_list: self.markets_list.clone(),
+            })
+            .into(),
+            _ => Err("[mock]: query not supported ").into(),
+        };
+
+        Ok(ret).into()
+    }
+}
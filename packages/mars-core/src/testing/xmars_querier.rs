@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use cosmwasm_std::{to_binary, Addr, QuerierResult, SystemError, Uint128};
 use cw20::BalanceResponse;
@@ -13,6 +13,8 @@ pub struct XMarsQuerier {
     pub balances_at: HashMap<(Addr, u64), Uint128>,
     /// maps block to a specific xmars balance
     pub total_supplies_at: HashMap<u64, Uint128>,
+    /// blocks with no total supply snapshot
+    pub missing_total_supplies_at: HashSet<u64>,
 }
 
 impl XMarsQuerier {
@@ -22,10 +24,14 @@ impl XMarsQuerier {
         query: xmars_token::msg::QueryMsg,
     ) -> QuerierResult {
         if contract_addr != &self.xmars_address {
-            panic!(
-                "[mock]: made an xmars query but xmars address is incorrect, was: {}, should be {}",
-                contract_addr, self.xmars_address
-            );
+            return Err(SystemError::InvalidRequest {
+                error: format!(
+                    "[mock]: made an xmars query but xmars address is incorrect, was: {}, should be {}",
+                    contract_addr, self.xmars_address
+                ),
+                request: Default::default(),
+            })
+            .into();
         }
 
         match query {
@@ -52,9 +58,18 @@ impl XMarsQuerier {
                 match self.total_supplies_at.get(&block) {
                     Some(balance) => Ok(to_binary(&xmars_token::TotalSupplyResponse {
                         total_supply: *balance,
+                        snapshot_found: true,
                     })
                     .into())
                     .into(),
+                    None if self.missing_total_supplies_at.contains(&block) => {
+                        Ok(to_binary(&xmars_token::TotalSupplyResponse {
+                            total_supply: Uint128::zero(),
+                            snapshot_found: false,
+                        })
+                        .into())
+                        .into()
+                    }
                     None => Err(SystemError::InvalidRequest {
                         error: format!("[mock]: no total supply at block {}", block),
                         request: Default::default(),
@@ -78,6 +93,7 @@ impl Default for XMarsQuerier {
             xmars_address: Addr::unchecked(""),
             balances_at: HashMap::new(),
             total_supplies_at: HashMap::new(),
+            missing_total_supplies_at: HashSet::new(),
         }
     }
 }
@@ -6,6 +6,15 @@ use cosmwasm_std::Uint128;
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
 pub struct TotalSupplyResponse {
     pub total_supply: Uint128,
+    /// Whether a total supply was recorded at or before the queried block. If not, total supply
+    /// is zero because there is no snapshot rather than because there is no supply. Assumed
+    /// true for tokens that do not report it
+    #[serde(default = "default_snapshot_found")]
+    pub snapshot_found: bool,
+}
+
+fn default_snapshot_found() -> bool {
+    true
 }
 
 pub mod msg {